@@ -0,0 +1,624 @@
+//! Shell completion script generation.
+//!
+//! These are usually exposed through a `#[hidden]` flag:
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         #[hidden]
+//!         ("--completions", shell) => {
+//!             match shell.as_deref() {
+//!                 Some("bash") => print!("{}", argtea::completions::bash("foo", Self::FLAGS)),
+//!                 Some("zsh") => print!("{}", argtea::completions::zsh("foo", Self::FLAGS)),
+//!                 Some("fish") => print!("{}", argtea::completions::fish("foo", Self::FLAGS)),
+//!                 Some("powershell") => {
+//!                     print!("{}", argtea::completions::powershell("foo", Self::FLAGS))
+//!                 }
+//!                 Some(shell) if shell.starts_with("dynamic-") => {
+//!                     match argtea::completions::dynamic_script(&shell[8..], "foo") {
+//!                         Some(script) => print!("{script}"),
+//!                         None => return Err(format!("unsupported shell `{shell}`")),
+//!                     }
+//!                 }
+//!                 _ => return Err("expected a shell after `--completions`".to_owned()),
+//!             }
+//!
+//!             std::process::exit(0);
+//!         }
+//!         # (_file) => {}
+//!     }
+//!     impl Foo {
+//!         const FLAGS: &'static [Flag] = docs!();
+//!         # fn parse() -> Result<(), String> { parse!(std::env::args().skip(1)); Ok(()) }
+//!     }
+//! }
+//! ```
+//!
+//! ## Dynamic completions
+//! Static scripts (from [`bash`], [`zsh`], [`fish`], and [`powershell`]) never run the command,
+//! so they can only complete flags, possible values, and file names. To complete context
+//! dependent values (eg profile names from a config file), use the script returned by
+//! [`dynamic_script`] instead. It calls the command itself with a hidden `--__complete` flag,
+//! which then uses [`dynamic`] to find the candidates (including the flags):
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         #[hidden]
+//!         ("--__complete", shell, line, point) => {
+//!             let line = line.unwrap_or_default();
+//!             let point = point.and_then(|p| p.parse().ok()).unwrap_or(line.len());
+//!
+//!             let candidates = argtea::completions::dynamic(Self::FLAGS, &line, point, |flag| {
+//!                 match flag.flags.first() {
+//!                     Some(&"--profile") => vec!["debug".to_owned(), "release".to_owned()],
+//!                     _ => Vec::new(),
+//!                 }
+//!             });
+//!
+//!             for candidate in candidates {
+//!                 println!("{candidate}");
+//!             }
+//!
+//!             std::process::exit(0);
+//!         }
+//!
+//!         /// Selects a profile.
+//!         ("--profile", profile) => {}
+//!         # (_file) => {}
+//!     }
+//!     impl Foo {
+//!         const FLAGS: &'static [Flag] = docs!();
+//!         # fn parse() -> Result<(), String> { parse!(std::env::args().skip(1)); Ok(()) }
+//!     }
+//! }
+//! ```
+
+use crate::{parse::NormalizeArgs, Flag};
+
+/// Generates a bash completion script for the command `cmd_name`.
+///
+/// All aliases of the provided flags are completed. Arguments after a flag with parameters (and
+/// positional arguments) are completed as file names.
+pub fn bash(cmd_name: &str, flags: &[Flag]) -> String {
+    let function = format!("_{}_completions", identifier(cmd_name));
+
+    let mut switches = Vec::new();
+    let mut value_flags = Vec::new();
+    let mut choices = String::new();
+
+    for flag in flags {
+        let list = if flag.params.is_empty() {
+            &mut switches
+        } else {
+            &mut value_flags
+        };
+
+        list.extend(flag.flags.iter().map(|alias| shell_quote(alias.trim())));
+
+        let possible_values = flag.possible_values(0);
+        if !possible_values.is_empty() {
+            let aliases: Vec<String> = flag
+                .flags
+                .iter()
+                .map(|alias| shell_quote(alias.trim()))
+                .collect();
+
+            choices += &format!(
+                "        {}) COMPREPLY=($(compgen -W {} -- \"$cur\")); return 0 ;;\n",
+                aliases.join("|"),
+                shell_quote(&possible_values.join(" ")),
+            );
+        }
+    }
+
+    if !choices.is_empty() {
+        choices = format!("    case \"$prev\" in\n{choices}    esac\n\n");
+    }
+
+    format!(
+        r#"{function}() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+
+    local switches=({switches})
+    local value_flags=({value_flags})
+
+    # `--flag=value` is split into `--flag`, `=`, and `value`
+    if [[ "$cur" == "=" ]]; then
+        cur=""
+    elif [[ "$prev" == "=" ]]; then
+        prev="${{COMP_WORDS[COMP_CWORD-2]}}"
+    fi
+
+{choices}    local flag
+    for flag in "${{value_flags[@]}}"; do
+        if [[ "$prev" == "$flag" ]]; then
+            COMPREPLY=($(compgen -f -- "$cur"))
+            return 0
+        fi
+    done
+
+    if [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "${{switches[*]}} ${{value_flags[*]}}" -- "$cur"))
+        return 0
+    fi
+
+    COMPREPLY=($(compgen -f -- "$cur"))
+}}
+
+complete -F {function} {cmd_name}
+"#,
+        switches = switches.join(" "),
+        value_flags = value_flags.join(" "),
+        cmd_name = shell_quote(cmd_name),
+    )
+}
+
+/// Generates a zsh completion script for the command `cmd_name`.
+///
+/// The first sentence of each flag's documentation is shown as its description. Arguments after
+/// a flag with parameters (and positional arguments) are completed as file names.
+pub fn zsh(cmd_name: &str, flags: &[Flag]) -> String {
+    let function = format!("_{}", identifier(cmd_name));
+
+    let mut specs = Vec::new();
+
+    for flag in flags {
+        let aliases: Vec<&str> = flag.flags.iter().map(|alias| alias.trim()).collect();
+
+        // Multiple aliases exclude each other and are listed with brace expansion, which can't be
+        // quoted
+        let (mut spec, mut body) = match aliases[..] {
+            [] => (String::new(), "*".to_owned()),
+            [alias] => (String::new(), alias.to_owned()),
+            _ => (
+                format!(
+                    "{}{{{}}}",
+                    shell_quote(&format!("({})", aliases.join(" "))),
+                    aliases.join(",")
+                ),
+                String::new(),
+            ),
+        };
+
+        let description = flag.first_sentence();
+
+        if !flag.is_positional() && !description.is_empty() {
+            body += &format!("[{}]", zsh_escape(&description));
+        }
+
+        for (i, param) in flag.params.iter().enumerate() {
+            let choices = flag.possible_values(i);
+
+            if choices.is_empty() {
+                body += &format!(":{}:_files", zsh_escape(param));
+            } else {
+                let choices: Vec<String> = choices.iter().map(|c| zsh_escape(c)).collect();
+                body += &format!(":{}:({})", zsh_escape(param), choices.join(" "));
+            }
+        }
+
+        if !body.is_empty() {
+            spec += &shell_quote(&body);
+        }
+
+        specs.push(spec);
+    }
+
+    format!(
+        r#"{compdef}{function}() {{
+    _arguments -s \
+        {specs}
+}}
+
+if [ "$funcstack[1]" = "{function}" ]; then
+    {function} "$@"
+else
+    compdef {function} {quoted}
+fi
+"#,
+        compdef = zsh_compdef(cmd_name),
+        quoted = shell_quote(cmd_name),
+        specs = specs.join(" \\\n        "),
+    )
+}
+
+/// Generates a fish completion script for the command `cmd_name`.
+///
+/// The first sentence of each flag's documentation is shown as its description. Arguments after
+/// a flag with parameters are completed as file names.
+pub fn fish(cmd_name: &str, flags: &[Flag]) -> String {
+    let mut script = String::new();
+
+    for flag in flags.iter().filter(|flag| !flag.is_positional()) {
+        let mut shorts = Vec::new();
+        let mut longs = Vec::new();
+
+        for alias in flag.flags.iter().map(|alias| alias.trim()) {
+            if let Some(long) = alias.strip_prefix("--") {
+                longs.push(format!("-l {}", fish_quote(long)));
+            } else {
+                let short = alias.strip_prefix('-').unwrap_or(alias);
+
+                if short.chars().count() == 1 {
+                    shorts.push(format!("-s {}", fish_quote(short)));
+                } else {
+                    // Single dash multi-character flags (eg `-Wall`)
+                    longs.push(format!("-o {}", fish_quote(short)));
+                }
+            }
+        }
+
+        // fish only supports one long option per `complete` line
+        let mut lines = longs.into_iter().map(Some).collect::<Vec<_>>();
+        if lines.is_empty() {
+            lines.push(None);
+        }
+
+        for (i, long) in lines.into_iter().enumerate() {
+            script += &format!("complete -c {}", fish_quote(cmd_name));
+
+            if i == 0 {
+                for short in &shorts {
+                    script += " ";
+                    script += short;
+                }
+            }
+
+            if let Some(long) = long {
+                script += " ";
+                script += &long;
+            }
+
+            let description = flag.first_sentence();
+            if !description.is_empty() {
+                script += &format!(" -d {}", fish_quote(&description));
+            }
+
+            let choices = flag.possible_values(0);
+
+            if !choices.is_empty() {
+                script += &format!(" -x -a {}", fish_quote(&choices.join(" ")));
+            } else if !flag.params.is_empty() {
+                script += " -r";
+            }
+
+            script += "\n";
+        }
+    }
+
+    script
+}
+
+/// Quotes a string for fish.
+fn fish_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// Generates a PowerShell completion script for the command `cmd_name`.
+///
+/// All aliases of the provided flags are completed, and the first sentence of each flag's
+/// documentation is shown as its tooltip.
+pub fn powershell(cmd_name: &str, flags: &[Flag]) -> String {
+    let mut entries = String::new();
+
+    for flag in flags {
+        let description = flag.first_sentence();
+
+        for alias in flag.flags.iter().map(|alias| alias.trim()) {
+            // Tooltips cannot be empty
+            let tooltip = if description.is_empty() {
+                alias
+            } else {
+                &description
+            };
+
+            entries += &format!(
+                "        @{{ Name = {}; Tooltip = {} }}\n",
+                powershell_quote(alias),
+                powershell_quote(tooltip),
+            );
+        }
+    }
+
+    format!(
+        r#"Register-ArgumentCompleter -Native -CommandName {cmd_name} -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $flags = @(
+{entries}    )
+
+    $flags | Where-Object {{ $_.Name.StartsWith($wordToComplete) }} | ForEach-Object {{
+        [System.Management.Automation.CompletionResult]::new($_.Name, $_.Name, 'ParameterName', $_.Tooltip)
+    }}
+}}
+"#,
+        cmd_name = powershell_quote(cmd_name),
+    )
+}
+
+/// Quotes a string for PowerShell. Backticks and `$` are not special in single quoted strings,
+/// but all of the single quote characters that PowerShell recognizes have to be doubled.
+fn powershell_quote(s: &str) -> String {
+    let mut quoted = String::from("'");
+
+    for c in s.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            quoted.push(c);
+        }
+
+        quoted.push(c);
+    }
+
+    quoted.push('\'');
+    quoted
+}
+
+/// Generates a completion script for `shell` (`bash`, `zsh`, `fish`, or `powershell`) that gets
+/// its candidates by running `cmd_name --__complete <shell> <line> <point>`.
+///
+/// This replaces the static script for the same shell: the command has to handle
+/// `--__complete`, since the script doesn't contain any flags itself.
+///
+/// `line` is the command line and `point` is the byte offset of the cursor in it. See the module
+/// documentation for the corresponding `--__complete` flag. Returns `None` for unknown shells.
+pub fn dynamic_script(shell: &str, cmd_name: &str) -> Option<String> {
+    let function = format!("_{}_complete", identifier(cmd_name));
+    let quoted = shell_quote(cmd_name);
+
+    let script = match shell {
+        "bash" => format!(
+            r#"{function}() {{
+    local IFS=$'\n'
+    COMPREPLY=($({quoted} --__complete bash "$COMP_LINE" "$COMP_POINT" 2>/dev/null))
+
+    # Candidates replace the whole shell word, but bash splits words at characters like `=`
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local word="${{COMP_LINE:0:COMP_POINT}}"
+    word="${{word##* }}"
+    local prefix="${{word%"$cur"}}"
+    COMPREPLY=("${{COMPREPLY[@]#"$prefix"}}")
+}}
+
+complete -o default -F {function} {quoted}
+"#
+        ),
+        "zsh" => format!(
+            r#"{compdef}{function}() {{
+    setopt localoptions nomultibyte
+    local -a candidates
+    candidates=(${{(f)"$({quoted} --__complete zsh "$LBUFFER" "${{#LBUFFER}}" 2>/dev/null)"}})
+    compadd -U -a candidates
+}}
+
+if [ "$funcstack[1]" = "{function}" ]; then
+    {function} "$@"
+else
+    compdef {function} {quoted}
+fi
+"#,
+            compdef = zsh_compdef(cmd_name),
+        ),
+        "fish" => format!(
+            r#"function {function}
+    set -l line (commandline -cp)
+    {cmd} --__complete fish "$line" (printf '%s' "$line" | wc -c) 2>/dev/null
+end
+
+complete -c {cmd} -f -a '({function})'
+"#,
+            cmd = fish_quote(cmd_name),
+        ),
+        "powershell" => format!(
+            r#"Register-ArgumentCompleter -Native -CommandName {cmd} -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $point = $cursorPosition - $commandAst.Extent.StartOffset
+    $line = $commandAst.ToString().PadRight($point).Substring(0, $point)
+    $bytes = [System.Text.Encoding]::UTF8.GetByteCount($line)
+
+    & {cmd} --__complete powershell $line $bytes 2>$null | ForEach-Object {{
+        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+    }}
+}}
+"#,
+            cmd = powershell_quote(cmd_name),
+        ),
+        _ => return None,
+    };
+
+    Some(script)
+}
+
+/// Finds the completion candidates for the word under the cursor in `line`.
+///
+/// `point` is the byte offset of the cursor in `line`. The arguments before the cursor are
+/// matched using the same rules as `parse!()` to determine whether the cursor is on a flag, on a
+/// flag's value (including values in the same argument, like `--output=file` or `-ofile`), or on
+/// a positional argument. Candidates for values and positional arguments are provided by
+/// `value_fn`, which is called with the corresponding flag.
+pub fn dynamic<F>(flags: &[Flag], line: &str, point: usize, value_fn: F) -> Vec<String>
+where
+    F: Fn(&Flag) -> Vec<String>,
+{
+    let mut point = point.min(line.len());
+    while !line.is_char_boundary(point) {
+        point -= 1;
+    }
+
+    let (mut words, ended) = split_words(&line[..point]);
+    if ended {
+        words.push(String::new());
+    }
+
+    // The first word is the command itself
+    let [_, previous @ .., current] = &words[..] else {
+        return Vec::new();
+    };
+
+    let candidates = |flag: &Flag, prefix: &str, value: &str| -> Vec<String> {
+        value_fn(flag)
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(value))
+            .map(|candidate| format!("{prefix}{candidate}"))
+            .collect()
+    };
+
+    // The flag that takes the following argument(s) and how many values it still needs
+    let mut pending: Option<(&Flag, usize)> = None;
+    let mut only_positionals = false;
+
+    // The previous words are split like `parse!()` splits them
+    let mut args = NormalizeArgs::new(previous.iter().cloned());
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            only_positionals = true;
+            break;
+        }
+
+        let Some(flag) = find_flag(flags, &arg) else {
+            continue;
+        };
+
+        for taken in 0..flag.params.len() {
+            if args.inline_value().or_else(|| args.next_raw()).is_none() {
+                pending = Some((flag, flag.params.len() - taken));
+                break;
+            }
+        }
+    }
+
+    if let Some((flag, _)) = pending {
+        return candidates(flag, "", current);
+    }
+
+    if only_positionals || !current.starts_with('-') {
+        return match flags.iter().find(|flag| flag.is_positional()) {
+            Some(flag) => candidates(flag, "", current),
+            None => Vec::new(),
+        };
+    }
+
+    // The cursor is on a value in the same word as its flag (eg `--output=fi` or `-vofi`)
+    let mut args = NormalizeArgs::new(core::iter::once(current.clone()));
+
+    while let Some(arg) = args.next() {
+        let Some(flag) = find_flag(flags, &arg).filter(|flag| !flag.params.is_empty()) else {
+            continue;
+        };
+
+        if arg == *current {
+            break;
+        }
+
+        let value = args.inline_value().unwrap_or_default();
+        let (prefix, value) = current.split_at(current.len() - value.len());
+        return candidates(flag, prefix, value);
+    }
+
+    flags
+        .iter()
+        .flat_map(|flag| flag.flags)
+        .map(|alias| alias.trim())
+        .filter(|alias| alias.starts_with(&**current))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Splits a command line into words like a POSIX shell would (handling quotes and backslashes).
+///
+/// Unterminated quotes are closed at the end of the line, so this can be used on partial command
+/// lines.
+pub fn shell_split(line: &str) -> Vec<String> {
+    split_words(line).0
+}
+
+/// Splits `line` into words, and returns whether the line ends with a word separator.
+fn split_words(line: &str) -> (Vec<String>, bool) {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => word.push('\\'),
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    let ended = word.is_none();
+    words.extend(word);
+
+    (words, ended)
+}
+
+fn find_flag<'a>(flags: &'a [Flag], name: &str) -> Option<&'a Flag> {
+    flags
+        .iter()
+        .find(|flag| flag.flags.iter().any(|alias| alias.trim() == name))
+}
+
+/// Escapes characters that are special inside of zsh `_arguments` specs.
+fn zsh_escape(s: &str) -> String {
+    s.replace('\\', r"\\")
+        .replace('[', r"\[")
+        .replace(']', r"\]")
+        .replace(':', r"\:")
+}
+
+/// The `#compdef` line of a zsh script, which lets compinit load the script from `$fpath`.
+///
+/// compinit splits the line at whitespace without removing quotes, and treats a leading `-` or a
+/// `=` as options, so it is omitted for names that can't be written there. Such scripts still
+/// register themselves with `compdef` when they're sourced.
+fn zsh_compdef(cmd_name: &str) -> String {
+    let valid = !cmd_name.is_empty()
+        && !cmd_name.starts_with('-')
+        && !cmd_name.contains(|c: char| c.is_whitespace() || c == '=');
+
+    if valid {
+        format!("#compdef {cmd_name}\n\n")
+    } else {
+        String::new()
+    }
+}
+
+/// Quotes a string for POSIX shells.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Turns a command name into a valid shell function name.
+fn identifier(cmd_name: &str) -> String {
+    cmd_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
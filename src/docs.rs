@@ -1,280 +1,955 @@
+use core::fmt;
+
+use crate::help::{flag_header, AliasStyle, ParamStyle};
+
+/// A flag's documentation, aliases, and parameters (see `docs!()` in the crate-level
+/// documentation).
+///
+/// New fields can be added in minor versions, so flags are built with [`Flag::new`] (and the
+/// `with_*` methods) outside of argtea.
+#[non_exhaustive]
 pub struct Flag {
     pub doc: &'static [&'static str],
     pub flags: &'static [&'static str],
     pub params: &'static [&'static str],
+    /// The allowed values of each parameter (eg `["auto", "never"]` for
+    /// `color in ["auto", "never"]`). Parameters without a list of choices have an empty list.
+    pub possible_values: &'static [&'static [&'static str]],
+    /// Example command lines from the flag's `#[example("...")]` (or `#[example = "..."]`)
+    /// attributes.
+    pub examples: &'static [&'static str],
+    /// True if the flag is `#[hidden]`, which is only possible with `docs!(all)` and
+    /// `docs!(hidden)`.
+    pub hidden: bool,
+    /// The name from the flag's `#[section = "..."]` attribute, if it has one.
+    pub section: Option<&'static str>,
+}
+
+/// Formats the flag's aliases and parameters like `-o, --output <path>`.
+impl fmt::Display for Flag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&flag_header(
+            self,
+            AliasStyle::Conventional,
+            ParamStyle::Angle,
+        ))
+    }
+}
+
+impl Flag {
+    /// A flag without any documentation, aliases, or parameters.
+    pub const EMPTY: Flag = Flag::new(&[], &[], &[]);
+
+    /// Creates a flag that isn't hidden and doesn't have any possible values or examples.
+    ///
+    /// ```rust
+    /// use argtea::Flag;
+    ///
+    /// const OUTPUT: Flag = Flag::new(&[" Sets the output file."], &["--output", "-o"], &["path"])
+    ///     .with_examples(&["cc -o main main.c"]);
+    ///
+    /// assert_eq!(OUTPUT.to_string(), "-o, --output <path>");
+    /// ```
+    pub const fn new(
+        doc: &'static [&'static str],
+        flags: &'static [&'static str],
+        params: &'static [&'static str],
+    ) -> Self {
+        Self {
+            doc,
+            flags,
+            params,
+            possible_values: &[],
+            examples: &[],
+            hidden: false,
+            section: None,
+        }
+    }
+
+    /// Sets [`possible_values`](Self::possible_values).
+    pub const fn with_possible_values(
+        self,
+        possible_values: &'static [&'static [&'static str]],
+    ) -> Self {
+        Self {
+            possible_values,
+            ..self
+        }
+    }
+
+    /// Sets [`examples`](Self::examples).
+    pub const fn with_examples(self, examples: &'static [&'static str]) -> Self {
+        Self { examples, ..self }
+    }
+
+    /// Sets [`hidden`](Self::hidden).
+    pub const fn with_hidden(self, hidden: bool) -> Self {
+        Self { hidden, ..self }
+    }
+
+    /// Sets [`section`](Self::section).
+    pub const fn with_section(self, section: Option<&'static str>) -> Self {
+        Self { section, ..self }
+    }
+
+    /// Returns true if this is a catch-all flag (eg `(file) => {...}`), which matches positional
+    /// arguments.
+    pub fn is_positional(&self) -> bool {
+        self.flags.is_empty()
+    }
+
+    /// Returns the allowed values of the parameter at `index`, or an empty list if any value is
+    /// allowed.
+    pub fn possible_values(&self, index: usize) -> &'static [&'static str] {
+        self.possible_values.get(index).copied().unwrap_or_default()
+    }
+
+    /// Returns the width of the flag's aliases and parameters in characters when they are
+    /// formatted like `-o, --output <path>` (see the [`Display`](fmt::Display) implementation).
+    ///
+    /// This is a `const fn`, so `flag_width!()` (see the crate-level documentation) can compute
+    /// the widest flag at compile time.
+    pub const fn width(&self) -> usize {
+        let mut width = 0;
+
+        let mut i = 0;
+        while i < self.flags.len() {
+            width += char_count(self.flags[i].trim_ascii());
+            i += 1;
+        }
+
+        // The commas after every alias except the last one
+        width += self.flags.len().saturating_sub(1);
+
+        let mut i = 0;
+        while i < self.params.len() {
+            let choices: &[&str] = if i < self.possible_values.len() {
+                self.possible_values[i]
+            } else {
+                &[]
+            };
+
+            // `<param>` or `<choice|choice>`
+            if choices.is_empty() {
+                width += char_count(self.params[i]) + 2;
+            } else {
+                let mut j = 0;
+                while j < choices.len() {
+                    width += char_count(choices[j]);
+                    j += 1;
+                }
+
+                width += choices.len() - 1 + 2;
+            }
+
+            i += 1;
+        }
+
+        // The spaces between the words
+        width + (self.flags.len() + self.params.len()).saturating_sub(1)
+    }
+
+    /// Returns the lines of the flag's documentation. Unlike [`doc`](Self::doc), doc attributes
+    /// that contain newlines (eg `#[doc = include_str!("...")]`) are split into several lines.
+    pub fn doc_lines(&self) -> impl Iterator<Item = &'static str> {
+        self.doc.iter().flat_map(|doc| lines(doc))
+    }
+
+    /// Returns the first sentence of the flag's documentation without the trailing period (eg
+    /// `"Sets the output file path"`).
+    pub fn first_sentence(&self) -> String {
+        let paragraph = self
+            .doc_lines()
+            .map(|line| line.trim())
+            .skip_while(|line| line.is_empty())
+            .take_while(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let sentence = match paragraph.find(". ") {
+            Some(idx) => &paragraph[..idx],
+            None => &paragraph,
+        };
+
+        sentence.strip_suffix('.').unwrap_or(sentence).to_owned()
+    }
+}
+
+/// A type whose flags are defined with [`argtea_impl`](crate::argtea_impl), so generic code (eg a
+/// help renderer shared by several programs) can access its flags with `T::FLAGS`.
+///
+/// `argtea_impl!` implements it like any other trait, with `docs!()` (or another selector) as the
+/// value of `FLAGS`:
+/// ```rust
+/// use argtea::{argtea_impl, Flag, Parser};
+///
+/// fn flag_names<T: Parser>() -> Vec<String> {
+///     T::FLAGS.iter().map(|flag| flag.to_string()).collect()
+/// }
+///
+/// struct Arguments;
+///
+/// argtea_impl! {
+///     {
+///         /// Sets the output file.
+///         ("--output" | "-o", path) => {}
+///     }
+///     impl Parser for Arguments {
+///         const FLAGS: &'static [Flag] = docs!();
+///     }
+/// }
+///
+/// assert_eq!(flag_names::<Arguments>(), ["-o, --output <path>"]);
+/// ```
+pub trait Parser {
+    /// The flags of the type, usually `docs!()`.
+    const FLAGS: &'static [Flag];
+}
+
+/// Splits a doc attribute into lines (ignoring a trailing newline), so a doc attribute like
+/// `#[doc = include_str!("...")]` is handled like several doc comments.
+pub(crate) fn lines(doc: &str) -> core::str::Split<'_, char> {
+    doc.strip_suffix('\n').unwrap_or(doc).split('\n')
+}
+
+/// The number of `char`s in `s`.
+const fn char_count(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut count = 0;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        // Every `char` has exactly one byte that isn't a continuation byte (`0b10xxxxxx`)
+        if bytes[i] & 0xC0 != 0x80 {
+            count += 1;
+        }
+
+        i += 1;
+    }
+
+    count
 }
 
 /// Helper macro; corresponds to `docs!()`.
 ///
-/// The argument corresponds to the flags given to [`argtea_impl`]
-/// (before the `impl`)
+/// The argument corresponds to the flags selected by [`_select_flags`]
 #[doc(hidden)]
 #[macro_export]
 macro_rules! _docs {
     {
         {
             $(
-                $(#[doc = $doc:literal])*
-                ($($flag:literal)|* $(,)? $($param:ident),* $(,)? ) => $block:block
+                $(#[cfg $cfg:tt])*
+                [$($hidden:ident)?] [$($section:literal)?]
+                $(#[doc = $doc:expr])*
+                $(#[example = $example:literal])*
+                ($($flag:literal)|* $(,)? $($param:ident $(in [$($choice:literal),* $(,)?] $($ci:ident)?)? $(: $ty:ty)?),* $(,)? ) => $block:block
             )*
         }
     } => {
         &[
             $(
-                $crate::Flag {
-                    doc: &[
-                        $($doc,)*
-                    ],
-                    flags: &[
-                        $($flag,)*
-                    ],
-                    params: &[
-                        $(::core::stringify!($param),)*
-                    ],
-                },
+                $(#[cfg $cfg])*
+                $crate::Flag::new(
+                    $crate::_docs!(@doc [$($flag),*] [$($doc),*]),
+                    &[$($flag,)*],
+                    &[$(::core::stringify!($param),)*],
+                )
+                .with_possible_values(&[$(&[$($($choice,)*)?],)*])
+                .with_examples(&[$($example,)*])
+                .with_hidden($crate::_docs!(@hidden $($hidden)?))
+                .with_section($crate::_docs!(@section $($section)?)),
             )*
         ]
-    }
+    };
+
+    (@hidden) => { false };
+    (@hidden hidden) => { true };
+
+    (@section) => { ::core::option::Option::None };
+    (@section $section:literal) => { ::core::option::Option::Some($section) };
+
+    // `{flag}` in the docs of a flag is replaced with its first alias. Positional arguments don't
+    // have one, so their docs are unchanged, and neither are lines without a placeholder. The
+    // interpolated docs aren't promoted to `'static` like literals, so they're built in an inline
+    // `const` block.
+    (@doc [] [$($doc:expr),*]) => {
+        &[$($doc),*]
+    };
+    (@doc [$first:literal $(, $flag:literal)*] [$($doc:expr),*]) => {
+        const {
+            &[$(
+                if $crate::formatters::has_flag_placeholder($doc) {
+                    $crate::_simple_format!(
+                        @str "docs!"
+                        $crate::formatters::interpolate_flag::<{ $crate::formatters::interpolated_len($doc, $first) }>($doc, $first)
+                    )
+                } else {
+                    $doc
+                }
+            ),*]
+        }
+    };
 }
 
 /// Helper macro; parses the right-hand-side of `const` items
 ///
 /// The `flags` argument corresponds to the flags given to [`argtea_impl`]
-/// (before the `impl`)
+/// (before the `impl`) after [`_normalize_doc_flags`], and the `help_width` argument
+/// corresponds to its `#[help_width(...)]` attribute (`[]` if there isn't one)
 #[doc(hidden)]
 #[macro_export]
 macro_rules! _constant_expression {
+    // Called by `_select_flags` for each `docs!()` in a macro call
     {
-        $flags:tt
-        docs!()
+        {
+            $(
+                [$($hidden:ident)?] [$($section:literal)?]
+                $(#[doc = $doc:expr])*
+                $(#[example = $example:literal])*
+                ($($flag:literal)|* $(,)? $($param:ident $(in [$($choice:literal),* $(,)?] $($ci:ident)?)? $(: $ty:ty)?),* $(,)? ) => $block:block
+            )*
+        }
+        @selected $flags:tt {$($done:tt)*} $rest:tt $($macro:ident)::+
     } => {
-        $crate::_docs!($flags)
+        $crate::_constant_expression!(
+            @scan $flags
+            {
+                $($done)*
+                [
+                    $(
+                        {
+                            doc: [
+                                $($doc),*
+                            ],
+                            flags: [
+                                $($flag),*
+                            ],
+                            params: [
+                                $($param),*
+                            ],
+                            examples: [
+                                $($example),*
+                            ]
+                        }
+                    ),*
+                ]
+            }
+            $rest
+            $($macro)::+
+        )
     };
 
+    // The flags passed to formatting macros can't be conditionally compiled, so a flag with a
+    // `#[cfg]` attribute defines a macro that continues with the flag, and one that continues
+    // without it. Only one of them is compiled, so every flag is only split once.
     {
-        $flags:tt
-        docs! $args:tt
-    } => {
-        ::core::compiler_error!("doc! macro does not accept arguments")
-    };
+        @split {$($before:tt)*}
+        {
+            #[cfg $cfg:tt]
+            $(#[cfg $more_cfg:tt])*
+            [$($hidden:ident)?] [$($section:tt)?]
+            $(#[doc = $doc:tt])*
+            $(#[example = $example:tt])*
+            ($($lhs:tt)*) => $rhs:tt
+            $($rem:tt)*
+        }
+        $($call:tt)*
+    } => {{
+        #[cfg $cfg]
+        macro_rules! __argtea_split {
+            () => {
+                $crate::_constant_expression!(
+                    @split {$($before)*}
+                    {
+                        $(#[cfg $more_cfg])* [$($hidden)?] [$($section)?] $(#[doc = $doc])* $(#[example = $example])* ($($lhs)*) => $rhs
+                        $($rem)*
+                    }
+                    $($call)*
+                )
+            };
+        }
+        #[cfg(not $cfg)]
+        macro_rules! __argtea_split {
+            () => {
+                $crate::_constant_expression!(@split {$($before)*} {$($rem)*} $($call)*)
+            };
+        }
 
+        __argtea_split!()
+    }};
     {
+        @split {$($before:tt)*}
         {
-            $(
-                $(#[doc = $doc:literal])*
-                ($($flag:literal)|* $(,)? $($param:ident),* $(,)? ) => $block:block
-            )*
+            [$($hidden:ident)?] [$($section:tt)?]
+            $(#[doc = $doc:tt])*
+            $(#[example = $example:tt])*
+            ($($lhs:tt)*) => $rhs:tt
+            $($rem:tt)*
         }
-        $(@ pre_args: {$($pre_args:tt)+})?
-        $($macro:ident)::+ ! (docs!() $($post_args:tt)*)
+        $($call:tt)*
     } => {
-        $($macro)::+ !(
-            $($($pre_args)+)?
-            [
-                $(
-                    {
-                        doc: [
-                            $($doc),*
-                        ],
-                        flags: [
-                            $($flag),*
-                        ],
-                        params: [
-                            $($param),*
-                        ]
-                    }
-                ),*
-            ]
-            $($post_args)*
+        $crate::_constant_expression!(
+            @split {$($before)* [$($hidden)?] [$($section)?] $(#[doc = $doc])* $(#[example = $example])* ($($lhs)*) => $rhs}
+            {$($rem)*}
+            $($call)*
         )
     };
     {
-        $flags:tt
-        $(@ pre_args: {$($pre_args:tt)+})?
-        $($macro:ident)::+ ! ($pre_arg:tt $($rem:tt)+)
+        @split {$($before:tt)*} {} $($macro:ident)::+ ! ($($args:tt)*)
     } => {
-        $crate::_constant_expression!(
-            $flags @ pre_args: {$($($pre_args)+)? $pre_arg}
-            $($macro)::+ ! ($($rem)+)
-        )
+        $crate::_constant_expression!(@scan {$($before)*} {} {$($args)*} $($macro)::+)
     };
 
+    // Replaces each `docs!()` in the macro's arguments with the selected flags
     {
-        $flags:tt
-        $(@ pre_args: {$($pre_args:tt)+})?
-        $($macro:ident)::+ ! ($($args:tt)*)
+        @scan $flags:tt {$($done:tt)*} {docs! $selector:tt $($rest:tt)*} $($macro:ident)::+
+    } => {
+        $crate::_select_flags!{
+            $selector $flags {}
+            _constant_expression!(@selected $flags {$($done)*} {$($rest)*} $($macro)::+)
+        }
+    };
+    {
+        @scan $flags:tt {$($done:tt)*} {$next:tt $($rest:tt)*} $($macro:ident)::+
+    } => {
+        $crate::_constant_expression!(@scan $flags {$($done)* $next} {$($rest)*} $($macro)::+)
+    };
+    {
+        @scan $flags:tt {$($done:tt)*} {} $($macro:ident)::+
+    } => {
+        $($macro)::+ ! ($($done)*)
+    };
+
+    {
+        $flags:tt $help_width:tt
+        docs! $selector:tt
+    } => {
+        $crate::_select_flags!{$selector $flags {} _docs!()}
+    };
+
+    {
+        $flags:tt $help_width:tt
+        flag_width! $selector:tt
+    } => {
+        $crate::formatters::max_flag_width($crate::_select_flags!{$selector $flags {} _docs!()})
+    };
+
+    {
+        $flags:tt $help_width:tt
+        $($macro:ident)::+ ! $args:tt
     } => {
-        $($macro)::+ ! ($($args)*)
+        $crate::_help_width!{$help_width {} $($macro)::+ ! $args _constant_expression!(@split {} $flags)}
     };
 }
 
-/// Helper macro: removes all `#[hidden]` flags and then calls the provided
-/// macro with the filtered flags as the first argument.
+/// Helper macro: adds the `width` option from `#[help_width(...)]` to calls to argtea's formatting
+/// macros, and then calls the provided macro with the call appended to its arguments.
+///
+/// Other macros are called unchanged, since they may not accept options.
 #[doc(hidden)]
 #[macro_export]
-macro_rules! _filter_hidden_flags {
+macro_rules! _help_width {
+    {
+        [] {} $($macro:ident)::+ ! $args:tt
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::$local_macro_to_call!($($other_args)* $($macro)::+ ! $args)
+    };
+
+    // Finds the name of the macro (the last segment of its path)
+    {
+        $help_width:tt {$($path:tt)*} $segment:ident :: $($rem:tt)*
+    } => {
+        $crate::_help_width!{$help_width {$($path)* $segment ::} $($rem)*}
+    };
+    {
+        $help_width:tt $path:tt simple_format ! $($rem:tt)*
+    } => {
+        $crate::_help_width!{@inject $help_width $path simple_format $($rem)*}
+    };
     {
-        $(@{
-            pre_flags: {$($pre_flags:tt)*}
-            attrs: {$($attrs:tt)*}
-            hidden: $($hidden:ident)?
-        })?
+        $help_width:tt $path:tt plain_format ! $($rem:tt)*
+    } => {
+        $crate::_help_width!{@inject $help_width $path plain_format $($rem)*}
+    };
+    {
+        $help_width:tt $path:tt table_format ! $($rem:tt)*
+    } => {
+        $crate::_help_width!{@inject $help_width $path table_format $($rem)*}
+    };
+    {
+        $help_width:tt $path:tt standard_help ! $($rem:tt)*
+    } => {
+        $crate::_help_width!{@inject $help_width $path standard_help $($rem)*}
+    };
+    {
+        $help_width:tt {$($path:tt)*} $name:ident ! $args:tt
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::$local_macro_to_call!($($other_args)* $($path)* $name ! $args)
+    };
+
+    {
+        @inject [$width:literal] {$($path:tt)*} $name:ident ({$($options:tt)*} $($args:tt)*)
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::$local_macro_to_call!($($other_args)* $($path)* $name ! ({width: $width, $($options)*} $($args)*))
+    };
+    {
+        @inject [$width:literal] {$($path:tt)*} $name:ident ($($args:tt)*)
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::$local_macro_to_call!($($other_args)* $($path)* $name ! ({width: $width} $($args)*))
+    };
+}
+
+/// Helper macro: selects the flags for `docs!($selector)` and then calls the
+/// provided macro with the selected flags as the first argument.
+///
+/// The selector is `()` (all flags that aren't `#[hidden]`), `(hidden)`, `(all)`, or
+/// `(section = "...")`. The flags correspond to the output of
+/// [`_normalize_doc_flags`], and the selected flags only keep their
+/// `#[cfg(...)]` attributes, `[hidden?]` and `["section"?]` markers, doc comments, and examples.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _select_flags {
+    {
+        $selector:tt {} {$($selected:tt)*}
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::$local_macro_to_call!{{$($selected)*} $($other_args)*}
+    };
+
+    {
+        () {
+            $(#[cfg $cfg:tt])* [] [$($section:tt)?] $(#[doc = $doc:tt])* $(#[example = $example:tt])* ($($lhs:tt)*) => $rhs:tt
+            $($rem:tt)*
+        }
+        {$($selected:tt)*}
+        $($call:tt)*
+    } => {
+        $crate::_select_flags!{
+            () {$($rem)*} {$($selected)* $(#[cfg $cfg])* [] [$($section)?] $(#[doc = $doc])* $(#[example = $example])* ($($lhs)*) => $rhs}
+            $($call)*
+        }
+    };
+    {
+        (hidden) {
+            $(#[cfg $cfg:tt])* [$hidden:ident] [$($section:tt)?] $(#[doc = $doc:tt])* $(#[example = $example:tt])* ($($lhs:tt)*) => $rhs:tt
+            $($rem:tt)*
+        }
+        {$($selected:tt)*}
+        $($call:tt)*
+    } => {
+        $crate::_select_flags!{
+            (hidden) {$($rem)*} {$($selected)* $(#[cfg $cfg])* [$hidden] [$($section)?] $(#[doc = $doc])* $(#[example = $example])* ($($lhs)*) => $rhs}
+            $($call)*
+        }
+    };
+    {
+        (section = $wanted:tt) {
+            $(#[cfg $cfg:tt])* [] [$section:tt] $(#[doc = $doc:tt])* $(#[example = $example:tt])* ($($lhs:tt)*) => $rhs:tt
+            $($rem:tt)*
+        }
+        {$($selected:tt)*}
+        $($call:tt)*
+    } => {{
+        // Literals can only be compared by a macro that has them in its patterns
+        macro_rules! __argtea_select_section {
+            ($wanted) => {
+                $crate::_select_flags!{
+                    (section = $wanted) {$($rem)*}
+                    {$($selected)* $(#[cfg $cfg])* [] [$section] $(#[doc = $doc])* $(#[example = $example])* ($($lhs)*) => $rhs}
+                    $($call)*
+                }
+            };
+            ($section) => {
+                $crate::_select_flags!{(section = $wanted) {$($rem)*} {$($selected)*} $($call)*}
+            };
+        }
+
+        __argtea_select_section!($section)
+    }};
+
+    {
+        (all) {
+            $(#[cfg $cfg:tt])* [$($hidden:ident)?] [$($section:tt)?] $(#[doc = $doc:tt])* $(#[example = $example:tt])* ($($lhs:tt)*) => $rhs:tt
+            $($rem:tt)*
+        }
+        {$($selected:tt)*}
+        $($call:tt)*
+    } => {
+        $crate::_select_flags!{
+            (all) {$($rem)*} {$($selected)* $(#[cfg $cfg])* [$($hidden)?] [$($section)?] $(#[doc = $doc])* $(#[example = $example])* ($($lhs)*) => $rhs}
+            $($call)*
+        }
+    };
+
+    // Skips the flags that weren't selected
+    {
+        () {
+            $(#[cfg $cfg:tt])* [$($hidden:ident)?] [$($section:tt)?] $(#[doc = $doc:tt])* $(#[example = $example:tt])* ($($lhs:tt)*) => $rhs:tt
+            $($rem:tt)*
+        }
+        $selected:tt
+        $($call:tt)*
+    } => {
+        $crate::_select_flags!{() {$($rem)*} $selected $($call)*}
+    };
+    {
+        (hidden) {
+            $(#[cfg $cfg:tt])* [$($hidden:ident)?] [$($section:tt)?] $(#[doc = $doc:tt])* $(#[example = $example:tt])* ($($lhs:tt)*) => $rhs:tt
+            $($rem:tt)*
+        }
+        $selected:tt
+        $($call:tt)*
+    } => {
+        $crate::_select_flags!{(hidden) {$($rem)*} $selected $($call)*}
+    };
+    {
+        (section = $wanted:tt) {
+            $(#[cfg $cfg:tt])* [$($hidden:ident)?] [$($section:tt)?] $(#[doc = $doc:tt])* $(#[example = $example:tt])* ($($lhs:tt)*) => $rhs:tt
+            $($rem:tt)*
+        }
+        $selected:tt
+        $($call:tt)*
+    } => {
+        $crate::_select_flags!{(section = $wanted) {$($rem)*} $selected $($call)*}
+    };
+
+    {
+        $selector:tt $($rem:tt)*
+    } => {
+        ::core::compile_error!(::core::concat!(
+            "Invalid arguments to `docs!", ::core::stringify!($selector),
+            "` expected `docs!()`, `docs!(hidden)`, `docs!(all)`, or `docs!(section = \"...\")`"
+        ))
+    };
+}
+
+/// Helper macro: normalizes the attributes of each flag and then calls the
+/// provided macro with the normalized flags as the first argument.
+///
+/// Each flag becomes
+/// `#[cfg(...)]* [hidden?] ["section"?] #[doc = "..."]* #[example = "..."]* (...) => {}`,
+/// where `#[cfg_attr(..., cfg(...))]` is also turned into a `#[cfg(...)]`. Other attributes (eg
+/// `#[fake]` or `#[allow(...)]`) and the flag's code are removed, and unknown attributes are
+/// reported with `compile_error!()`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _normalize_doc_flags {
+    {
+        @{$($flags:tt)*} $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
         {}
         $local_macro_to_call:ident!($($other_args:tt)*)
     } => {
-        $crate::$local_macro_to_call!{{$($($pre_flags)*)?} $($other_args)*}
+        $crate::$local_macro_to_call!{{$($flags)*} $($other_args)*}
     };
 
     {
-        $(@{
-            pre_flags: {$($pre_flags:tt)*}
-            attrs: {$($attrs:tt)*}
-            hidden: $($hidden:ident)?
-        })?
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
         {
             #[hidden]
             $($remaining:tt)*
         }
-        $local_macro_to_call:ident!($($other_args:tt)*)
+        $($call:tt)*
     } => {
-        $crate::_filter_hidden_flags! {
-            @{
-                pre_flags: {$($($pre_flags)*)?}
-                attrs: {$($($attrs)*)?}
-                hidden: true
-            }
-            {$($remaining)*}
-            $local_macro_to_call!($($other_args)*)
+        $crate::_normalize_doc_flags!{@$flags $cfgs [hidden] $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[section = $name:tt]
+            $($remaining:tt)*
         }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden [$name] $docs $examples {$($remaining)*} $($call)*}
     };
 
     {
-        $(@{
-            pre_flags: {$($pre_flags:tt)*}
-            attrs: {$($attrs:tt)*}
-            hidden: $($hidden:ident)?
-        })?
+        @$flags:tt {$($cfgs:tt)*} $hidden:tt $section:tt $docs:tt $examples:tt
         {
-            #[fake]
+            #[cfg $predicate:tt]
             $($remaining:tt)*
         }
-        $local_macro_to_call:ident!($($other_args:tt)*)
+        $($call:tt)*
     } => {
-        $crate::_filter_hidden_flags! {
-            @{
-                pre_flags: {$($($pre_flags)*)?}
-                attrs: {$($($attrs)*)?}
-                hidden: $($($hidden)?)?
-            }
-            {$($remaining)*}
-            $local_macro_to_call!($($other_args)*)
+        $crate::_normalize_doc_flags!{@$flags {$($cfgs)* #[cfg $predicate]} $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    // `#[cfg_attr(predicate, cfg(...))]` is equivalent to `#[cfg(any(not(predicate), ...))]`
+    {
+        @$flags:tt {$($cfgs:tt)*} $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[cfg_attr($predicate:meta, cfg($($cfg:tt)*) $(, $($attrs:tt)*)?)]
+            $($remaining:tt)*
         }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags {$($cfgs)* #[cfg(any(not($predicate), $($cfg)*))]} $hidden $section $docs $examples {$($remaining)*} $($call)*}
     };
 
     {
-        $(@{
-            pre_flags: {$($pre_flags:tt)*}
-            attrs: {$($attrs:tt)*}
-            hidden: $($hidden:ident)?
-        })?
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt {$($docs:tt)*} $examples:tt
         {
             #[doc = $cmt:literal]
             $($remaining:tt)*
         }
-        $local_macro_to_call:ident!($($other_args:tt)*)
+        $($call:tt)*
     } => {
-        $crate::_filter_hidden_flags! {
-            @{
-                pre_flags: {$($($pre_flags)*)?}
-                attrs: {$($($attrs)*)? #[doc = $cmt]}
-                hidden: $($($hidden)?)?
-            }
-            {$($remaining)*}
-            $local_macro_to_call!($($other_args)*)
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section {$($docs)* #[doc = $cmt]} $examples {$($remaining)*} $($call)*}
+    };
+    // Doc attributes that aren't literals (eg `#[doc = include_str!("...")]`) can only be matched
+    // as `expr`s afterwards, so literals are kept as literals for formatting macros that match them
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt {$($docs:tt)*} $examples:tt
+        {
+            #[doc = $cmt:expr]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section {$($docs)* #[doc = $cmt]} $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt {$($examples:tt)*}
+        {
+            #[example($example:literal)]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs {$($examples)* #[example = $example]} {$($remaining)*} $($call)*}
+    };
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt {$($examples:tt)*}
+        {
+            #[example = $example:literal]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs {$($examples)* #[example = $example]} {$($remaining)*} $($call)*}
+    };
+
+    // The parsing attributes, lint attributes (which are placed on the flag's code), and other
+    // `#[cfg_attr(...)]`s don't affect the documentation
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[fake]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[eager]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[strict_value]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[optional_value]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[key_value]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[allow($($lints:tt)*)]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[expect($($lints:tt)*)]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[warn($($lints:tt)*)]
+            $($remaining:tt)*
         }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
     };
 
     {
-        $(@{
-            pre_flags: {$($pre_flags:tt)*}
-            attrs: {$($attrs:tt)*}
-            hidden: $($hidden:ident)?
-        })?
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[deny($($lints:tt)*)]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[forbid($($lints:tt)*)]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            #[cfg_attr($($cfg_attr:tt)*)]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{@$flags $cfgs $hidden $section $docs $examples {$($remaining)*} $($call)*}
+    };
+
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
         {
             #[$($attr:tt)*]
             $($remaining:tt)*
         }
-        $local_macro_to_call:ident!($($other_args:tt)*)
+        $($call:tt)*
     } => {
         compile_error!(::core::concat!("Invalid flag attribute #[", ::core::stringify!($($attr)*), "]"))
     };
 
+    // Guards (eg `("--debug") if cfg!(debug_assertions) => {}`) don't affect the documentation
     {
-        $(@{
-            pre_flags: {$($pre_flags:tt)*}
-            attrs: {$($attrs:tt)*}
-            hidden: $($hidden:ident)?
-        })?
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
         {
-            ($flag_binding:ident @ $($lhs:tt)*) => $rhs:tt
+            ($($lhs:tt)*) if $guard:expr => $rhs:tt
             $($remaining:tt)*
         }
-        $local_macro_to_call:ident!($($other_args:tt)*)
+        $($call:tt)*
     } => {
-        $crate::_filter_hidden_flags! {
-            @{
-                pre_flags: {$($($pre_flags)* $($attrs)*)? ($($lhs)*) => $rhs}
-                attrs: {}
-                hidden:
-            }
-            {$($remaining)*}
-            $local_macro_to_call!($($other_args)*)
+        $crate::_normalize_doc_flags!{
+            @$flags $cfgs $hidden $section $docs $examples
+            {($($lhs)*) => $rhs $($remaining)*}
+            $($call)*
         }
     };
 
     {
-        $(@{
-            pre_flags: {$($pre_flags:tt)*}
-            attrs: {$($attrs:tt)*}
-            hidden:
-        })?
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
         {
-            ($($lhs:tt)*) => $rhs:tt
+            ($flag_binding:ident @ $($lhs:tt)*) => $rhs:tt
             $($remaining:tt)*
         }
-        $local_macro_to_call:ident!($($other_args:tt)*)
+        $($call:tt)*
     } => {
-        $crate::_filter_hidden_flags! {
-            @{
-                pre_flags: {$($($pre_flags)* $($attrs)*)? ($($lhs)*) => $rhs}
-                attrs: {}
-                hidden:
-            }
-            {$($remaining)*}
-            $local_macro_to_call!($($other_args)*)
+        $crate::_normalize_doc_flags!{
+            @$flags $cfgs $hidden $section $docs $examples
+            {($($lhs)*) => $rhs $($remaining)*}
+            $($call)*
         }
     };
 
+    // The code of flags is removed, so expression bodies (`("-v") => verbose = true,`) and blocks
+    // followed by a comma become `{}`
     {
-        $(@{
-            pre_flags: {$($pre_flags:tt)*}
-            attrs: {$($attrs:tt)*}
-            hidden: $hidden:ident
-        })?
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
         {
-            ($($lhs:tt)*) => $rhs:tt
+            ($($lhs:tt)*) => {$($rhs:tt)*},
             $($remaining:tt)*
         }
-        $local_macro_to_call:ident!($($other_args:tt)*)
+        $($call:tt)*
     } => {
-        $crate::_filter_hidden_flags! {
-            @{
-                pre_flags: {$($($pre_flags)*)?}
-                attrs: {}
-                hidden:
-            }
+        $crate::_normalize_doc_flags!{
+            @$flags $cfgs $hidden $section $docs $examples
+            {($($lhs)*) => {} $($remaining)*}
+            $($call)*
+        }
+    };
+    {
+        @{$($flags:tt)*} {$($cfgs:tt)*} [$($hidden:ident)?] [$($section:tt)?] {$($docs:tt)*} {$($examples:tt)*}
+        {
+            ($($lhs:tt)*) => {$($rhs:tt)*}
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{
+            @{$($flags)* $($cfgs)* [$($hidden)?] [$($section)?] $($docs)* $($examples)* ($($lhs)*) => {}}
+            {} [] [] {} {}
             {$($remaining)*}
-            $local_macro_to_call!($($other_args)*)
+            $($call)*
         }
     };
+    {
+        @$flags:tt $cfgs:tt $hidden:tt $section:tt $docs:tt $examples:tt
+        {
+            ($($lhs:tt)*) => $body:expr $(, $($remaining:tt)*)?
+        }
+        $($call:tt)*
+    } => {
+        $crate::_normalize_doc_flags!{
+            @$flags $cfgs $hidden $section $docs $examples
+            {($($lhs)*) => {} $($($remaining)*)?}
+            $($call)*
+        }
+    };
+
+    {
+        $flags:tt
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_normalize_doc_flags!{@{} {} [] [] {} {} $flags $local_macro_to_call!($($other_args)*)}
+    };
 }
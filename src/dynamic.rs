@@ -0,0 +1,176 @@
+use core::{fmt::Display, str::FromStr};
+
+use crate::{error::suggest, parse::NormalizeArgs, Flag, ParseError};
+
+/// A flag definition for [`parse_dynamic`].
+///
+/// This is the runtime equivalent of a flag given to [`argtea_impl`](crate::argtea_impl): `flags`
+/// are the aliases (eg `["--output", "-o"]`) and `params` are the names of the flag's
+/// parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlagSpec {
+    pub flags: &'static [&'static str],
+    pub params: &'static [&'static str],
+}
+
+impl From<&Flag> for FlagSpec {
+    fn from(flag: &Flag) -> Self {
+        Self {
+            flags: flag.flags,
+            params: flag.params,
+        }
+    }
+}
+
+/// A flag that was matched by [`parse_dynamic`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// All aliases of the matched flag.
+    pub aliases: &'static [&'static str],
+    /// The alias that was used (eg `-o`).
+    pub name: String,
+    /// The values of the flag's parameters.
+    pub values: Vec<String>,
+}
+
+/// The result of [`parse_dynamic`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Matches {
+    /// The matched flags in the order they appeared.
+    pub flags: Vec<Match>,
+    /// All arguments that weren't flags or flag values.
+    pub positionals: Vec<String>,
+}
+
+impl Matches {
+    /// Returns true if the flag with the alias `flag` was passed.
+    pub fn contains(&self, flag: &str) -> bool {
+        self.matches_of(flag).next().is_some()
+    }
+
+    /// Returns how many times the flag with the alias `flag` was passed.
+    pub fn count(&self, flag: &str) -> usize {
+        self.matches_of(flag).count()
+    }
+
+    /// Converts the first value of the last occurrence of `flag` using [`FromStr`].
+    ///
+    /// Returns `None` if the flag wasn't passed or doesn't take any parameters.
+    pub fn get_one<T>(&self, flag: &str) -> Option<Result<T, ParseError>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let m = self.matches_of(flag).last()?;
+        let value = m.values.first()?;
+
+        Some(convert(m, value))
+    }
+
+    /// Converts all values of all occurrences of `flag` using [`FromStr`].
+    pub fn get_many<T>(&self, flag: &str) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.matches_of(flag)
+            .flat_map(|m| m.values.iter().map(move |value| convert(m, value)))
+            .collect()
+    }
+
+    fn matches_of<'a>(&'a self, flag: &'a str) -> impl Iterator<Item = &'a Match> + 'a {
+        self.flags.iter().filter(move |m| m.aliases.contains(&flag))
+    }
+}
+
+fn convert<T>(m: &Match, value: &str) -> Result<T, ParseError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|err: T::Err| ParseError::InvalidValue {
+            flag: m.name.clone(),
+            value: value.to_owned(),
+            reason: err.to_string(),
+        })
+}
+
+/// Parses `args` at runtime using the flags in `specs`.
+///
+/// This uses the same rules as `parse!()`: short flags can be clustered (`-sw 80`), and values can
+/// be glued onto flags (`--output=file`, `-ofile`, or `-o=file`). Additionally, `--` ends flag
+/// parsing, so all of the following arguments are positional.
+///
+/// ```rust
+/// use argtea::{parse_dynamic, FlagSpec};
+///
+/// let specs = [
+///     FlagSpec { flags: &["--verbose", "-v"], params: &[] },
+///     FlagSpec { flags: &["--output", "-o"], params: &["path"] },
+/// ];
+///
+/// let args = ["-vofile", "input.c"].map(String::from);
+/// let matches = parse_dynamic(&specs, args).unwrap();
+///
+/// assert!(matches.contains("--verbose"));
+/// assert_eq!(matches.get_one::<String>("-o"), Some(Ok("file".to_owned())));
+/// assert_eq!(matches.positionals, ["input.c"]);
+/// ```
+pub fn parse_dynamic<I>(specs: &[FlagSpec], args: I) -> Result<Matches, ParseError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = NormalizeArgs::new(args.into_iter());
+    let mut matches = Matches::default();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            matches.positionals.extend(args);
+            break;
+        }
+
+        if arg.starts_with('-') && arg != "-" {
+            let spec = find_spec(specs, &arg)?;
+            matches.flags.push(take_values(spec, arg, &mut args)?);
+        } else {
+            matches.positionals.push(arg);
+        }
+    }
+
+    Ok(matches)
+}
+
+fn find_spec<'a>(specs: &'a [FlagSpec], name: &str) -> Result<&'a FlagSpec, ParseError> {
+    specs
+        .iter()
+        .find(|spec| spec.flags.contains(&name))
+        .ok_or_else(|| ParseError::UnknownFlag {
+            flag: name.to_owned(),
+            suggestion: suggest(name, specs.iter().flat_map(|spec| spec.flags).copied()),
+        })
+}
+
+/// Takes the values of all of `spec`'s parameters, starting with the value in the flag's argument
+/// (eg `file` in `-ofile`), if any.
+fn take_values<I: Iterator<Item = String>>(
+    spec: &FlagSpec,
+    name: String,
+    args: &mut NormalizeArgs<I>,
+) -> Result<Match, ParseError> {
+    let mut values = Vec::with_capacity(spec.params.len());
+
+    for param in spec.params {
+        match args.inline_value().or_else(|| args.next_raw()) {
+            Some(value) => values.push(value),
+            None => return Err(ParseError::MissingValue { flag: name, param }),
+        }
+    }
+
+    Ok(Match {
+        aliases: spec.flags,
+        name,
+        values,
+    })
+}
@@ -0,0 +1,106 @@
+use core::fmt;
+
+/// An error returned by `parse!()` (for typed parameters, choices, and `#[strict_value]`
+/// positionals), [`parse_dynamic`](crate::parse_dynamic), and the [`Matches`](crate::Matches)
+/// accessors.
+///
+/// Argtea functions can return it directly, or any error type that implements
+/// `From<ParseError>`. This includes `String`, for functions that return `Result<_, String>`:
+/// ```rust
+/// # use argtea::ParseError;
+/// let err = ParseError::MissingValue { flag: "-o".to_owned(), param: "path" };
+/// let message: String = err.into();
+///
+/// assert_eq!(message, "expected <path> after `-o`");
+/// ```
+///
+/// Flags can return their own errors with [`ParseError::Custom`] (eg with `?` on a
+/// `Result<_, String>`, using the `From<String>` impl).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// An argument looked like a flag, but no flag with that name exists. `suggestion` is the most
+    /// similar flag, if any flag is close enough (eg `--verbose` for `--verbos`).
+    UnknownFlag {
+        flag: String,
+        suggestion: Option<&'static str>,
+    },
+    /// A flag was given without one of its parameters.
+    MissingValue { flag: String, param: &'static str },
+    /// A flag's value couldn't be converted to the expected type.
+    InvalidValue {
+        flag: String,
+        value: String,
+        reason: String,
+    },
+    /// Any other error, with its message.
+    Custom(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFlag {
+                flag,
+                suggestion: None,
+            } => write!(f, "unknown flag `{flag}`"),
+            Self::UnknownFlag {
+                flag,
+                suggestion: Some(suggestion),
+            } => write!(f, "unknown flag `{flag}`, did you mean `{suggestion}`?"),
+            Self::MissingValue { flag, param } => write!(f, "expected <{param}> after `{flag}`"),
+            Self::InvalidValue {
+                flag,
+                value,
+                reason,
+            } => write!(f, "invalid value `{value}` for `{flag}`: {reason}"),
+            Self::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for String {
+    fn from(err: ParseError) -> Self {
+        err.to_string()
+    }
+}
+
+impl From<String> for ParseError {
+    fn from(message: String) -> Self {
+        Self::Custom(message)
+    }
+}
+
+/// Returns the flag in `flags` that is the most similar to `flag`, if it is close enough to be a
+/// likely typo (used for [`ParseError::UnknownFlag`]).
+pub(crate) fn suggest<'a>(flag: &str, flags: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = flag.chars().count() / 3;
+
+    flags
+        .into_iter()
+        .map(|candidate| (edit_distance(flag, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between `a` and `b` (in `char`s).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
@@ -0,0 +1,216 @@
+/// Declares a set of flags outside of [`argtea_impl`](crate::argtea_impl), which can then be
+/// spliced into the flags of any number of `argtea_impl!` calls with `use_flags!(...)`.
+///
+/// The flag set becomes a macro with the given name and visibility (eg `pub(crate)`), so it can be
+/// used from other modules by its path (eg `use_flags!(crate::io::io_flags(...))`).
+///
+/// Macros can't see the local variables of the function that calls them, so the flags' code only
+/// has access to the variables listed after the set's name. These are borrowed mutably from the
+/// variables given to `use_flags!(...)` (in the same order), so the flags' code uses them
+/// through a `&mut` reference:
+/// ```rust
+/// mod io {
+///     argtea::argtea_flags! {
+///         pub(crate) io_flags(output, inputs) {
+///             /// Sets the output file.
+///             ("--output" | "-o", path) => {
+///                 *output = path;
+///             }
+///
+///             /// Adds an input file.
+///             ("--input" | "-i", path) => {
+///                 inputs.extend(path);
+///             }
+///         }
+///     }
+/// }
+///
+/// struct Arguments {
+///     output: Option<String>,
+///     inputs: Vec<String>,
+/// }
+///
+/// argtea::argtea_impl! {
+///     {
+///         use_flags!(io::io_flags(output, inputs));
+///
+///         (flag) => {
+///             return Err(format!("Unknown flag `{flag}`"));
+///         }
+///     }
+///     impl Arguments {
+///         fn parse_from(args: Vec<String>) -> Result<Self, String> {
+///             let mut output = None;
+///             let mut inputs = Vec::new();
+///
+///             parse!(args.into_iter());
+///
+///             Ok(Self { output, inputs })
+///         }
+///     }
+/// }
+///
+/// let args = ["-i", "a.txt", "--output", "b.txt"].map(String::from).to_vec();
+/// let args = Arguments::parse_from(args).unwrap();
+/// assert_eq!(args.output.as_deref(), Some("b.txt"));
+/// assert_eq!(args.inputs, ["a.txt"]);
+/// ```
+///
+/// Flag sets can't be `pub` because macros made by other macros can't be exported from a crate.
+/// ```rust,compile_fail
+/// argtea::argtea_flags! {
+///     // error: `io_flags` is only public within the crate, and cannot be re-exported outside
+///     pub io_flags {
+///         ("--verbose") => {}
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! argtea_flags {
+    {
+        $(#[$attr:meta])*
+        $vis:vis $name:ident {$($flags:tt)*}
+    } => {
+        $crate::_flag_set!{@define ($) [$(#[$attr])*] $vis $name [] {$($flags)*}}
+    };
+    {
+        $(#[$attr:meta])*
+        $vis:vis $name:ident($($shared:ident),* $(,)?) {$($flags:tt)*}
+    } => {
+        $crate::_flag_set!{@start ($) [$(#[$attr])*] $vis $name [$($shared),*] {$($flags)*}}
+    };
+    {
+        $($rem:tt)*
+    } => {
+        compile_error!("Expected a flag set like `pub(crate) name(variables...) { flags... }`");
+    };
+}
+
+/// Helper macro; defines the macro of a flag set from [`argtea_flags`].
+///
+/// The flag set's macro is called by `argtea_impl!` for each `use_flags!(...)` with the variables
+/// to share, and then calls `argtea_impl!` back with the set's flags added to the flags before it.
+///
+/// Each flag's code (and guard) is put in a block that first borrows the shared variables. The
+/// flags are otherwise unchanged, so their attributes are still normalized by `argtea_impl!`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _flag_set {
+    {
+        @start ($d:tt) $attrs:tt $vis:vis $name:ident [$($shared:ident),*] $flags:tt
+    } => {
+        $crate::_flag_set!{
+            @wrap ($d) $attrs $vis $name [$($shared),*]
+            {$(#[allow(unused_variables)] let $shared = &mut $d $shared;)*}
+            {} $flags
+        }
+    };
+
+    {
+        @wrap ($d:tt) $attrs:tt $vis:vis $name:ident $shared:tt $borrows:tt {$($done:tt)*}
+        {
+            $(# $attr:tt)*
+            ($($lhs:tt)*) $(if $guard:expr)? => {$($body:tt)*},
+            $($rem:tt)*
+        }
+    } => {
+        $crate::_flag_set!{
+            @wrap ($d) $attrs $vis $name $shared $borrows {
+                $($done)*
+                $(# $attr)*
+                ($($lhs)*) $(if $crate::_flag_set!(@borrow $borrows $guard))? => {
+                    $crate::_flag_set!(@borrow $borrows {$($body)*})
+                }
+            }
+            {$($rem)*}
+        }
+    };
+    {
+        @wrap ($d:tt) $attrs:tt $vis:vis $name:ident $shared:tt $borrows:tt {$($done:tt)*}
+        {
+            $(# $attr:tt)*
+            ($($lhs:tt)*) $(if $guard:expr)? => {$($body:tt)*}
+            $($rem:tt)*
+        }
+    } => {
+        $crate::_flag_set!{
+            @wrap ($d) $attrs $vis $name $shared $borrows {
+                $($done)*
+                $(# $attr)*
+                ($($lhs)*) $(if $crate::_flag_set!(@borrow $borrows $guard))? => {
+                    $crate::_flag_set!(@borrow $borrows {$($body)*})
+                }
+            }
+            {$($rem)*}
+        }
+    };
+    {
+        @wrap ($d:tt) $attrs:tt $vis:vis $name:ident $shared:tt $borrows:tt {$($done:tt)*}
+        {
+            $(# $attr:tt)*
+            ($($lhs:tt)*) $(if $guard:expr)? => $body:expr $(, $($rem:tt)*)?
+        }
+    } => {
+        $crate::_flag_set!{
+            @wrap ($d) $attrs $vis $name $shared $borrows {
+                $($done)*
+                $(# $attr)*
+                ($($lhs)*) $(if $crate::_flag_set!(@borrow $borrows $guard))? => {
+                    $crate::_flag_set!(@borrow $borrows $body)
+                }
+            }
+            {$($($rem)*)?}
+        }
+    };
+    {
+        @wrap ($d:tt) $attrs:tt $vis:vis $name:ident $shared:tt $borrows:tt $done:tt {}
+    } => {
+        $crate::_flag_set!{@define ($d) $attrs $vis $name $shared $done}
+    };
+    {
+        @wrap ($d:tt) $attrs:tt $vis:vis $name:ident $shared:tt $borrows:tt $done:tt {$next:tt $($rem:tt)*}
+    } => {
+        compile_error!(concat!(
+            "Expected a flag like `(\"--flag\" | \"-f\", param) => { ... }`, found `",
+            stringify!($next),
+            "`"
+        ));
+    };
+
+    // Borrows the shared variables for a flag's code or guard
+    {
+        @borrow {$($borrows:tt)*} $code:expr
+    } => {{
+        $($borrows)*
+        $code
+    }};
+
+    {
+        @define ($d:tt) [$($attr:tt)*] $vis:vis $name:ident [$($shared:ident),*] {$($flags:tt)*}
+    } => {
+        $($attr)*
+        macro_rules! $name {
+            {
+                @argtea_use_flags [$($d $shared:ident),*] $d __argtea_state:tt
+                {$d($d __argtea_done:tt)*} $d __argtea_flags:tt $d($d __argtea_rem:tt)*
+            } => {
+                $crate::argtea_impl!{
+                    @use_flags $d __argtea_state {$d($d __argtea_done)* $($flags)*}
+                    $d __argtea_flags $d($d __argtea_rem)*
+                }
+            };
+            {
+                $d($d __argtea_args:tt)*
+            } => {
+                compile_error!(concat!(
+                    "Expected `use_flags!(",
+                    stringify!($name($($shared),*)),
+                    ");` in the flags of `argtea_impl!`"
+                ));
+            };
+        }
+
+        #[allow(unused_imports)]
+        $vis use $name;
+    };
+}
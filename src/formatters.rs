@@ -1,41 +1,1439 @@
+use crate::{Flag, ParamStyle, Undocumented};
+
 /// Simple compile-time formatting of commandline options.
 ///
 /// See crate-level documentation for usage
 ///
 /// This macro automatically indents but does not automatically perform line
 /// wrapping.
+///
+/// The lines around `docs!()` can either be literals, macros that expand to literals
+/// (eg `env!("CARGO_PKG_NAME")`, `concat!(...)`, or `include_str!(...)`), or constant `&str`
+/// expressions. Constants can be written as paths (eg `HEADER` or `Self::HEADER`); other
+/// expressions must be wrapped in parentheses. `docs!()` can be used more than once (eg
+/// `docs!(section = "Advanced")`, see the crate-level documentation).
+///
+/// The indentation can be customized with an optional group of options before everything else:
+/// - `flag_indent` is placed before each flag (default: `"  "`)
+/// - `doc_indent` is placed before each line of documentation (default: `"   "`)
+/// - `doc_prefix` is placed after `doc_indent` (default: `""`)
+/// - `param_style` is `angle` (`<path>`, the default), `square` (`[path]`), or `caps` (`PATH`,
+///   see [`ParamStyle`](crate::ParamStyle))
+/// - `undocumented` is `show` (the default), `skip`, or `placeholder("...")` (see
+///   [`Undocumented`](crate::Undocumented)). This only affects flags without any doc comments.
+/// - `separator` is placed after each flag's documentation (default: `"\n\n"`, ie two blank lines)
+/// - `width` wraps lines longer than `width` characters at spaces, keeping their indentation
+///   (default: `0`, ie no wrapping). [`argtea_impl`](crate::argtea_impl)'s `#[help_width(...)]`
+///   attribute sets this for every formatting macro.
+///
+/// Note that doc comments usually start with a space (`/// Text` => `" Text"`), so
+/// `doc_prefix: "•"` results in `• Text`.
+/// ```rust
+/// # use argtea::{argtea_impl, simple_format};
+/// # struct Foo;
+/// argtea_impl! {
+///     {
+///         /// Displays this help message.
+///         ("--help" | "-h") => {}
+///     }
+///     impl Foo {
+///         const HELP: &'static str = simple_format!(
+///             { flag_indent: "    ", doc_indent: "        ", doc_prefix: "•" }
+///             "Options:"
+///             docs!()
+///         );
+///     }
+/// }
+///
+/// assert_eq!(Foo::HELP, "Options:\n    --help, -h \n        • Displays this help message.\n\n\n");
+/// ```
 #[macro_export]
 macro_rules! simple_format {
     {
-        $($prefix:literal)*
-        [
-            $({
-                doc: [
-                    $( $doc:literal ),*
-                ],
-                flags: [
-                    $(
-                        $first_flag:literal
-                        $( , $flag:literal )*
-                    )?
-                ],
-                params: [
-                    $( $param:ident ),*
-                ]
-            }),*
-        ]
-        $($suffix:literal)*
+        {$($options:tt)*}
+        $($args:tt)*
+    } => {
+        $crate::_simple_format!{@options {"  " "   " "" angle [show] "\n\n" 0} {$($options)*} $($args)*}
+    };
+    {
+        $($args:tt)*
+    } => {
+        $crate::_simple_format!{@collect {"  " "   " "" angle [show] "\n\n" 0} {} $($args)*}
+    };
+}
+
+/// Helper macro; collects the options, the lines, and the flags selected by each `docs!()` for
+/// [`simple_format`] and [`table_format`].
+///
+/// The options are stored as `{flag_indent doc_indent doc_prefix param_style [undocumented]
+/// separator width}` for [`simple_format`] and as `{@table gutter max_width param_style
+/// [undocumented] width}` for [`table_format`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _simple_format {
+    {
+        @options {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {flag_indent: $value:literal $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@options {$value $doc_indent $doc_prefix $param_style $undocumented $separator $width} {$($($options)*)?} $($rem)*}
+    };
+    {
+        @options {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {doc_indent: $value:literal $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@options {$flag_indent $value $doc_prefix $param_style $undocumented $separator $width} {$($($options)*)?} $($rem)*}
+    };
+    {
+        @options {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {doc_prefix: $value:literal $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@options {$flag_indent $doc_indent $value $param_style $undocumented $separator $width} {$($($options)*)?} $($rem)*}
+    };
+    {
+        @options {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {param_style: $value:ident $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@options {$flag_indent $doc_indent $doc_prefix $value $undocumented $separator $width} {$($($options)*)?} $($rem)*}
+    };
+    {
+        @options {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {undocumented: $value:ident $(($text:literal))? $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{
+            @options {$flag_indent $doc_indent $doc_prefix $param_style [$value $(($text))?] $separator $width}
+            {$($($options)*)?}
+            $($rem)*
+        }
+    };
+    {
+        @options {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {separator: $value:literal $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@options {$flag_indent $doc_indent $doc_prefix $param_style $undocumented $value $width} {$($($options)*)?} $($rem)*}
+    };
+    {
+        @options {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {width: $value:literal $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@options {$flag_indent $doc_indent $doc_prefix $param_style $undocumented $separator $value} {$($($options)*)?} $($rem)*}
+    };
+    {
+        @options $options:tt {}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@collect $options {} $($rem)*}
+    };
+    {
+        @options $options:tt {$($invalid:tt)*}
+        $($rem:tt)*
+    } => {
+        ::core::compile_error!(::core::concat!(
+            "Invalid `simple_format!` options `", ::core::stringify!($($invalid)*),
+            "` expected `flag_indent`, `doc_indent`, `doc_prefix`, `param_style`, `undocumented`, `separator`, or `width`"
+        ))
+    };
+
+    {
+        @table_options {@table $gutter:literal $max_width:literal $param_style:ident $undocumented:tt $width:tt}
+        {gutter: $value:literal $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@table_options {@table $value $max_width $param_style $undocumented $width} {$($($options)*)?} $($rem)*}
+    };
+    {
+        @table_options {@table $gutter:literal $max_width:literal $param_style:ident $undocumented:tt $width:tt}
+        {max_width: $value:literal $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@table_options {@table $gutter $value $param_style $undocumented $width} {$($($options)*)?} $($rem)*}
+    };
+    {
+        @table_options {@table $gutter:literal $max_width:literal $param_style:ident $undocumented:tt $width:tt}
+        {param_style: $value:ident $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@table_options {@table $gutter $max_width $value $undocumented $width} {$($($options)*)?} $($rem)*}
+    };
+    {
+        @table_options {@table $gutter:literal $max_width:literal $param_style:ident $undocumented:tt $width:tt}
+        {undocumented: $value:ident $(($text:literal))? $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{
+            @table_options {@table $gutter $max_width $param_style [$value $(($text))?] $width}
+            {$($($options)*)?}
+            $($rem)*
+        }
+    };
+    {
+        @table_options {@table $gutter:literal $max_width:literal $param_style:ident $undocumented:tt $width:tt}
+        {width: $value:literal $(, $($options:tt)*)?}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@table_options {@table $gutter $max_width $param_style $undocumented $value} {$($($options)*)?} $($rem)*}
+    };
+    {
+        @table_options $options:tt {}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@collect $options {} $($rem)*}
+    };
+    {
+        @table_options $options:tt {$($invalid:tt)*}
+        $($rem:tt)*
+    } => {
+        ::core::compile_error!(::core::concat!(
+            "Invalid `table_format!` options `", ::core::stringify!($($invalid)*),
+            "` expected `gutter`, `max_width`, `param_style`, `undocumented`, or `width`"
+        ))
+    };
+
+    // Collects the lines (`{...}`), the constants (`(...)`), and the flags selected by each
+    // `docs!()` (`[...]`)
+    {
+        @collect $options:tt {$($segments:tt)*}
+        [$($docs:tt)*]
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@collect $options {$($segments)* [$($docs)*]} $($rem)*}
+    };
+    {
+        @collect $options:tt {$($segments:tt)*}
+        :: $($macro:ident)::+ ! $args:tt
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@collect $options {$($segments)* {:: $($macro)::+ ! $args}} $($rem)*}
+    };
+    {
+        @collect $options:tt {$($segments:tt)*}
+        $($macro:ident)::+ ! $args:tt
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@collect $options {$($segments)* {$($macro)::+ ! $args}} $($rem)*}
+    };
+    {
+        @collect $options:tt $segments:tt
+        $first:ident
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@path $options $segments {$first} $($rem)*}
+    };
+    {
+        @collect $options:tt {$($segments:tt)*}
+        ($line:expr)
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@collect $options {$($segments)* ($line)} $($rem)*}
+    };
+    {
+        @collect $options:tt {$($segments:tt)*}
+        $line:literal
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@collect $options {$($segments)* {$line}} $($rem)*}
+    };
+    {
+        @collect $options:tt $segments:tt
+    } => {
+        $crate::_simple_format!{@render $options {} {} $segments}
+    };
+
+    // Collects the rest of a path to a constant (eg `Self::HEADER`)
+    {
+        @path $options:tt $segments:tt {$($path:tt)*}
+        :: $next:ident
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@path $options $segments {$($path)* :: $next} $($rem)*}
+    };
+    {
+        @path $options:tt {$($segments:tt)*} {$($path:tt)*}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!{@collect $options {$($segments)* ($($path)*)} $($rem)*}
+    };
+
+
+    // Constants can't be passed to `concat!`, so they're concatenated by `@concat` instead
+    {
+        @render
+        {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {$($parts:tt)*}
+        {$($done:tt)*}
+        {($line:expr) $($segments:tt)*}
+    } => {
+        $crate::_simple_format!{
+            @render
+            {$flag_indent $doc_indent $doc_prefix $param_style $undocumented $separator $width}
+            {$($parts)* ::core::concat!($($done)*), $line, "\n",}
+            {}
+            {$($segments)*}
+        }
+    };
+    {
+        @render
+        {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        $parts:tt
+        {$($done:tt)*}
+        {{$($line:tt)*} $($segments:tt)*}
+    } => {
+        $crate::_simple_format!{
+            @render
+            {$flag_indent $doc_indent $doc_prefix $param_style $undocumented $separator $width}
+            $parts
+            {$($done)* $($line)*, "\n",}
+            {$($segments)*}
+        }
+    };
+    {
+        @render
+        {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {$($parts:tt)*}
+        {$($done:tt)*}
+        {
+            [
+                $({
+                    doc: [
+                        $( $doc:expr ),*
+                    ],
+                    flags: [
+                        $(
+                            $first_flag:literal
+                            $( , $flag:literal )*
+                        )?
+                    ],
+                    params: [
+                        $( $param:ident ),*
+                    ]
+                    $(, $($rest:tt)*)?
+                }),*
+            ]
+            $($segments:tt)*
+        }
+    } => {
+        $crate::_simple_format!{
+            @render
+            {$flag_indent $doc_indent $doc_prefix $param_style $undocumented $separator $width}
+            {
+                $($parts)* ::core::concat!($($done)*),
+                $(
+                    $crate::_simple_format!(
+                        @interpolate [$($first_flag)?]
+                        $crate::_simple_format!(
+                            @entry
+                            {$flag_indent $doc_indent $doc_prefix $param_style $undocumented $separator $width}
+                            [$($first_flag $(, $flag)*)?]
+                            [$($param),*]
+                            [$($doc),*]
+                        )
+                    ),
+                )*
+            }
+            {}
+            {$($segments)*}
+        }
+    };
+    {
+        @render
+        {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {}
+        {$($done:tt)*}
+        {}
+    } => {
+        $crate::_simple_format!(@finish $param_style $width ::core::concat!($($done)*))
+    };
+    {
+        @render
+        {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident $undocumented:tt $separator:literal $width:tt}
+        {$($parts:tt)*}
+        {$($done:tt)*}
+        {}
+    } => {
+        $crate::_simple_format!(
+            @finish $param_style $width
+            $crate::_simple_format!(@concat $($parts)* ::core::concat!($($done)*))
+        )
+    };
+
+    // `{flag}` in a flag's entry is replaced with its first alias (like in `docs!()`). This needs
+    // a `const fn`, so the entries of flags are always concatenated by `@concat`. Entries without
+    // a placeholder are used as-is (`interpolate_flag` returns an empty array for them)
+    (@interpolate [] $entry:expr) => {
+        $entry
+    };
+    (@interpolate [$first_flag:literal] $entry:expr) => {
+        if $crate::formatters::has_flag_placeholder($entry) {
+            $crate::_simple_format!(
+                @str "simple_format!"
+                $crate::formatters::interpolate_flag::<{ $crate::formatters::interpolated_len($entry, $first_flag) }>($entry, $first_flag)
+            )
+        } else {
+            $entry
+        }
+    };
+
+    // A flag and its documentation
+    {
+        @entry
+        {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident [skip] $separator:literal $width:tt}
+        $flags:tt $params:tt []
+    } => {
+        ""
+    };
+    {
+        @entry
+        {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident [placeholder($text:literal)] $separator:literal $width:tt}
+        $flags:tt $params:tt []
     } => {
         ::core::concat!(
-            $($prefix, "\n",)*
-            $(
-                "  ",
-                $( $first_flag, $( ", ", $flag, )* " ", )?
-                $( "<", ::core::stringify!($param), "> ", )*
-                $("\n   ", $doc,)*
-                "\n\n\n",
-            )*
-            $($suffix, "\n",)*
+            $crate::_simple_format!(@header $flag_indent $param_style $flags $params),
+            "\n", $doc_indent, $doc_prefix, " ", $text,
+            "\n", $separator,
         )
     };
+    {
+        @entry
+        {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident [$(show)? $(skip)? $(placeholder($text:literal))?] $separator:literal $width:tt}
+        $flags:tt $params:tt [$($doc:expr),*]
+    } => {
+        ::core::concat!(
+            $crate::_simple_format!(@header $flag_indent $param_style $flags $params),
+            $("\n", $doc_indent, $doc_prefix, $doc,)*
+            "\n", $separator,
+        )
+    };
+    {
+        @entry
+        {$flag_indent:literal $doc_indent:literal $doc_prefix:literal $param_style:ident [$($undocumented:tt)*] $separator:literal $width:tt}
+        $($rem:tt)*
+    } => {
+        $crate::_simple_format!(@undocumented $($undocumented)*)
+    };
+    {
+        @header $flag_indent:literal $param_style:ident
+        [$($first_flag:literal $(, $flag:literal)*)?]
+        [$($param:ident),*]
+    } => {
+        ::core::concat!(
+            $flag_indent,
+            $( $first_flag, $( ", ", $flag, )* " ", )?
+            $( $crate::_simple_format!(@param $param_style $param), " ", )*
+        )
+    };
+
+    (@undocumented show) => { $crate::Undocumented::Show };
+    (@undocumented skip) => { $crate::Undocumented::Skip };
+    (@undocumented placeholder($text:literal)) => { $crate::Undocumented::Placeholder($text) };
+    (@undocumented $($invalid:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "Invalid `undocumented` option `", ::core::stringify!($($invalid)*),
+            "` expected `show`, `skip`, or `placeholder(\"...\")`"
+        ))
+    };
+
+    // Parameter styles. `caps` parameters are surrounded by NUL characters, which are removed by
+    // `@finish` after converting the parameters to uppercase
+    (@param angle $param:ident) => { ::core::concat!("<", ::core::stringify!($param), ">") };
+    (@param square $param:ident) => { ::core::concat!("[", ::core::stringify!($param), "]") };
+    (@param caps $param:ident) => { ::core::concat!("\0", ::core::stringify!($param), "\0") };
+    (@param $style:ident $param:ident) => {
+        ::core::compile_error!(::core::concat!(
+            "Invalid parameter style `", ::core::stringify!($style), "` expected `angle`, `square`, or `caps`"
+        ))
+    };
+
+    (@finish caps $width:tt $help:expr) => {
+        $crate::_simple_format!(
+            @wrap $width
+            $crate::_simple_format!(
+                @str "simple_format!"
+                $crate::formatters::uppercase_params::<{ $crate::formatters::uppercase_params_len($help) }>($help)
+            )
+        )
+    };
+    (@finish $style:ident $width:tt $help:expr) => {
+        $crate::_simple_format!(@wrap $width $help)
+    };
+
+    (@wrap 0 $help:expr) => {
+        $help
+    };
+    (@wrap $width:literal $help:expr) => {
+        $crate::_simple_format!(
+            @str "simple_format!"
+            $crate::formatters::wrap::<{ $crate::formatters::wrapped_len($help, $width) }>($help, $width)
+        )
+    };
+
+    (@table $table:expr) => {
+        $crate::_simple_format!(@str "table_format!" $table.render::<{ $table.rendered_len() }>())
+    };
+    (@plain $help:expr) => {
+        $crate::_simple_format!(
+            @str "plain_format!"
+            $crate::formatters::strip_markup::<{ $crate::formatters::stripped_len($help) }>($help)
+        )
+    };
+    (@concat $($part:expr),*) => {
+        $crate::_simple_format!(
+            @str "simple_format!"
+            $crate::formatters::concat::<{ $crate::formatters::concat_len(&[$($part),*]) }>(&[$($part),*])
+        )
+    };
+
+    // Converts the output of one of the above `const fn`s to a string. This uses an inline `const`
+    // block instead of `const` items so that the arguments can refer to `Self`
+    (@str $name:literal $bytes:expr) => {
+        match ::core::str::from_utf8(const { &$bytes }) {
+            ::core::result::Result::Ok(help) => help,
+            ::core::result::Result::Err(_) => ::core::panic!(::core::concat!($name, " produced invalid UTF-8")),
+        }
+    };
+
+    (@param_style angle) => { $crate::ParamStyle::Angle };
+    (@param_style square) => { $crate::ParamStyle::Square };
+    (@param_style caps) => { $crate::ParamStyle::Caps };
+    (@param_style $style:ident) => {
+        ::core::compile_error!(::core::concat!(
+            "Invalid parameter style `", ::core::stringify!($style), "` expected `angle`, `square`, or `caps`"
+        ))
+    };
+    {
+        @render
+        {@table $gutter:literal $max_width:literal $param_style:ident $undocumented:tt $width:tt}
+        $parts:tt
+        {$($done:tt)*}
+        {($line:expr) $($segments:tt)*}
+    } => {
+        $crate::_simple_format!{
+            @render
+            {@table $gutter $max_width $param_style $undocumented $width}
+            $parts
+            {$($done)* $crate::formatters::TableLine::Text($line),}
+            {$($segments)*}
+        }
+    };
+    {
+        @render
+        {@table $gutter:literal $max_width:literal $param_style:ident $undocumented:tt $width:tt}
+        $parts:tt
+        {$($done:tt)*}
+        {{$($line:tt)*} $($segments:tt)*}
+    } => {
+        $crate::_simple_format!{
+            @render
+            {@table $gutter $max_width $param_style $undocumented $width}
+            $parts
+            {$($done)* $crate::formatters::TableLine::Text($($line)*),}
+            {$($segments)*}
+        }
+    };
+    {
+        @render
+        {@table $gutter:literal $max_width:literal $param_style:ident $undocumented:tt $width:tt}
+        $parts:tt
+        {$($done:tt)*}
+        {
+            [
+                $({
+                    doc: [
+                        $( $doc:expr ),*
+                    ],
+                    flags: [
+                        $( $flag:literal ),*
+                    ],
+                    params: [
+                        $( $param:ident ),*
+                    ]
+                    $(, $($rest:tt)*)?
+                }),*
+            ]
+            $($segments:tt)*
+        }
+    } => {
+        $crate::_simple_format!{
+            @render
+            {@table $gutter $max_width $param_style $undocumented $width}
+            $parts
+            {
+                $($done)*
+                $(
+                    $crate::formatters::TableLine::Row($crate::formatters::TableRow {
+                        doc: $crate::_docs!(@doc [$($flag),*] [$($doc),*]),
+                        flags: &[$($flag),*],
+                        params: &[$(::core::stringify!($param)),*],
+                    }),
+                )*
+            }
+            {$($segments)*}
+        }
+    };
+    {
+        @render
+        {@table $gutter:literal $max_width:literal $param_style:ident [$($undocumented:tt)*] $width:tt}
+        $parts:tt
+        {$($done:tt)*}
+        {}
+    } => {
+        $crate::_simple_format!(
+            @table
+            $crate::formatters::Table {
+                lines: &[$($done)*],
+                gutter: $gutter,
+                max_width: $max_width,
+                param_style: $crate::_simple_format!(@param_style $param_style),
+                undocumented: $crate::_simple_format!(@undocumented $($undocumented)*),
+                width: $width,
+            }
+        )
+    };
+}
+
+/// Compile-time formatting of commandline options in two columns:
+/// ```text
+///   -o, --output <path>  Sets the output file path.
+///   -h, --help           Displays this help message.
+/// ```
+///
+/// This takes the same arguments as [`simple_format`], but only the first line of each flag's
+/// documentation is displayed. Flags wider than `max_width` (default: `28`) are displayed on their
+/// own line, and their documentation is moved to the next line. The columns are separated by at
+/// least `gutter` spaces (default: `2`). Like [`simple_format`], the `param_style` option changes
+/// how parameters are displayed, and the `undocumented` option changes how flags without
+/// documentation are displayed. If `width` is set (eg by `#[help_width(...)]`), documentation that
+/// doesn't fit is wrapped onto lines indented to the second column. These can be changed with an
+/// optional group of options before everything else:
+/// ```rust
+/// # use argtea::{argtea_impl, table_format};
+/// # struct Foo;
+/// argtea_impl! {
+///     {
+///         /// Sets the output file path.
+///         ("--output" | "-o", path) => {}
+///
+///         /// Displays this help message.
+///         ("--help" | "-h") => {}
+///     }
+///     impl Foo {
+///         const HELP: &'static str = table_format!(
+///             { gutter: 4, max_width: 20 }
+///             "Options:"
+///             docs!()
+///         );
+///     }
+/// }
+///
+/// assert_eq!(Foo::HELP, "\
+/// Options:
+///   --output, -o <path>    Sets the output file path.
+///   --help, -h             Displays this help message.
+/// ");
+/// ```
+#[macro_export]
+macro_rules! table_format {
+    {
+        {$($options:tt)*}
+        $($args:tt)*
+    } => {
+        $crate::_simple_format!{@table_options {@table 2 28 angle [show] 0} {$($options)*} $($args)*}
+    };
+    {
+        $($args:tt)*
+    } => {
+        $crate::_simple_format!{@collect {@table 2 28 angle [show] 0} {} $($args)*}
+    };
+}
+
+/// [`simple_format`] for plain-text terminals: backticks and the asterisks around `*emphasis*` and
+/// `**strong emphasis**` are removed.
+///
+/// Only asterisks that open and close an emphasis at the start and the end of words on the same
+/// line are removed, so asterisks in words or globs (eg `a*b` or `*.rs`), list markers, and
+/// asterisks inside of code spans are kept.
+/// ```rust
+/// # use argtea::{argtea_impl, plain_format};
+/// # struct Foo;
+/// argtea_impl! {
+///     {
+///         /// Sets the output path (`a.out` by **default**).
+///         ("--output" | "-o", path) => {}
+///     }
+///     impl Foo {
+///         const HELP: &'static str = plain_format!(docs!());
+///     }
+/// }
+///
+/// assert_eq!(Foo::HELP, "  --output, -o <path> \n    Sets the output path (a.out by default).\n\n\n");
+/// ```
+#[macro_export]
+macro_rules! plain_format {
+    {
+        $($args:tt)*
+    } => {
+        $crate::_simple_format!(@plain $crate::simple_format!($($args)*))
+    };
+}
+
+/// [`simple_format`] with a standard preamble built from the package's metadata:
+/// ```text
+/// name version
+/// description
+///
+/// Usage: usage
+///
+/// Options:
+/// ```
+///
+/// The usage line comes first, and it can optionally be preceded by a group of
+/// [`simple_format`] options. If the package doesn't have a description, its line is left
+/// blank.
+/// ```rust
+/// # use argtea::{argtea_impl, standard_help};
+/// # struct Foo;
+/// argtea_impl! {
+///     {
+///         /// Displays this help message.
+///         ("--help" | "-h") => {}
+///     }
+///     impl Foo {
+///         const HELP: &'static str = standard_help!(usage: "argtea [OPTIONS]" docs!());
+///     }
+/// }
+///
+/// assert!(Foo::HELP.starts_with("argtea 2."));
+/// assert!(Foo::HELP.contains("\n\nUsage: argtea [OPTIONS]\n\nOptions:\n  --help, -h"));
+/// ```
+#[macro_export]
+macro_rules! standard_help {
+    {
+        $({$($options:tt)*})?
+        usage: $usage:literal
+        $($args:tt)*
+    } => {
+        $crate::simple_format!(
+            $({$($options)*})?
+            $crate::version_string!()
+            ::core::env!("CARGO_PKG_DESCRIPTION")
+            ""
+            ::core::concat!("Usage: ", $usage)
+            ""
+            "Options:"
+            $($args)*
+        )
+    };
+}
+
+/// The package's name and version (eg `"argtea 2.1.0"`) for `--version` flags.
+/// ```rust
+/// # use argtea::version_string;
+/// assert_eq!(version_string!(), concat!("argtea ", env!("CARGO_PKG_VERSION")));
+/// ```
+#[macro_export]
+macro_rules! version_string {
+    () => {
+        ::core::concat!(
+            ::core::env!("CARGO_PKG_NAME"),
+            " ",
+            ::core::env!("CARGO_PKG_VERSION")
+        )
+    };
+}
+
+/// Checks at compile time that a formatting macro accepts the pattern `docs!()` is replaced with
+/// (see "Formatting macros" in the crate-level documentation).
+///
+/// The macro is expanded once with a representative set of flags, once with no flags, and once
+/// with a flag that has a field added after `params`, as future versions of `argtea` may do. The
+/// results are evaluated as constants of type `&'static str` unless another type is given (eg
+/// `assert_formatter!(my_format: MyHelp)`). This can be placed in a formatting macro's crate (or
+/// its tests) to catch breakage in CI:
+/// ```rust
+/// macro_rules! flag_list {
+///     ([$({
+///         doc: [$($doc:literal),*],
+///         flags: [$($flag:literal),*],
+///         params: [$($param:ident),*]
+///         $(, $($rest:tt)*)?
+///     }),*]) => {
+///         concat!($($($flag, "\n",)*)*)
+///     };
+/// }
+///
+/// argtea::assert_formatter!(flag_list);
+/// ```
+#[macro_export]
+macro_rules! assert_formatter {
+    ($($macro:ident)::+) => {
+        $crate::assert_formatter!($($macro)::+: &'static str);
+    };
+    ($($macro:ident)::+: $ty:ty) => {
+        const _: () = {
+            struct AssertFormatter;
+
+            $crate::argtea_impl! {
+                {
+                    /// Sets the output file path.
+                    ///
+                    /// Defaults to `a.out`.
+                    #[example("myprog -o out.txt input.c")]
+                    ("--output" | "-o", path) => {}
+
+                    /// Sets the number of jobs.
+                    ("--jobs" | "-j", jobs: u32) => {}
+
+                    /// Sets the optimization level.
+                    ("-O", level in ["0", "1", "2"]) => {}
+
+                    ("--verbose" | "-v") => {}
+
+                    /// Sets a `key` to a `value`.
+                    ("--set", key, value) => {}
+
+                    /// An input file.
+                    (file) => {}
+                }
+
+                impl AssertFormatter {
+                    #[allow(dead_code)]
+                    const FLAGS: $ty = $($macro)::+!(docs!());
+                    #[allow(dead_code)]
+                    const EMPTY: $ty = $($macro)::+!([]);
+                    #[allow(dead_code)]
+                    const EXTENDED: $ty = $($macro)::+!([
+                        {
+                            doc: [" Displays this help message."],
+                            flags: ["--help", "-h"],
+                            params: [],
+                            examples: [],
+                            reserved: []
+                        }
+                    ]);
+                }
+            }
+
+            let _ = (AssertFormatter::FLAGS, AssertFormatter::EMPTY, AssertFormatter::EXTENDED);
+        };
+    };
+}
+
+/// The width of the widest flag in `flags` (or `0` if there aren't any flags) for
+/// `flag_width!()`. See [`Flag::width`].
+pub const fn max_flag_width(flags: &[Flag]) -> usize {
+    let mut max = 0;
+
+    let mut i = 0;
+    while i < flags.len() {
+        let width = flags[i].width();
+        if width > max {
+            max = width;
+        }
+
+        i += 1;
+    }
+
+    max
+}
+
+/// The total length of `parts` in bytes.
+pub const fn concat_len(parts: &[&str]) -> usize {
+    write_parts(Writer::<0>::new(), parts).len
+}
+
+/// Concatenates `parts` for [`simple_format`]s that contain constants. `N` must be [`concat_len`].
+pub const fn concat<const N: usize>(parts: &[&str]) -> [u8; N] {
+    let writer = write_parts(Writer::new(), parts);
+    assert!(writer.len == N);
+
+    writer.buf
+}
+
+const fn write_parts<const N: usize>(mut w: Writer<N>, parts: &[&str]) -> Writer<N> {
+    let mut i = 0;
+    while i < parts.len() {
+        w = w.push_str(parts[i]);
+        i += 1;
+    }
+
+    w
+}
+
+/// Whether `s` contains a `{flag}` placeholder (or an escaped `{{flag}}`). Entries without one
+/// are used as-is instead of going through [`interpolate_flag`].
+pub const fn has_flag_placeholder(s: &str) -> bool {
+    let mut i = 0;
+    while i < s.len() {
+        if starts_with_at(s.as_bytes(), PLACEHOLDER, i) {
+            return true;
+        }
+
+        i += 1;
+    }
+
+    false
+}
+
+/// The length of `s` after [`interpolate_flag`] (`0` if `s` doesn't need to be interpolated).
+pub const fn interpolated_len(s: &str, flag: &str) -> usize {
+    if !has_flag_placeholder(s) {
+        return 0;
+    }
+
+    write_interpolated(Writer::<0>::new(), s, flag).len
+}
+
+/// Replaces each `{flag}` in a flag's documentation with `flag` (its first alias), and each
+/// `{{flag}}` with a literal `{flag}`, for `docs!()` and the compile-time formatters. `N` must be
+/// [`interpolated_len`].
+pub const fn interpolate_flag<const N: usize>(s: &str, flag: &str) -> [u8; N] {
+    if !has_flag_placeholder(s) {
+        return [0; N];
+    }
+
+    let writer = write_interpolated(Writer::new(), s, flag);
+    assert!(writer.len == N);
+
+    writer.buf
+}
+
+const PLACEHOLDER: &[u8] = b"{flag}";
+const ESCAPED_PLACEHOLDER: &[u8] = b"{{flag}}";
+
+const fn write_interpolated<const N: usize>(mut w: Writer<N>, s: &str, flag: &str) -> Writer<N> {
+    let bytes = s.as_bytes();
+
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if starts_with_at(bytes, ESCAPED_PLACEHOLDER, i) {
+            w = w
+                .push_bytes(bytes, start, i)
+                .push_bytes(PLACEHOLDER, 0, PLACEHOLDER.len());
+            i += ESCAPED_PLACEHOLDER.len();
+            start = i;
+        } else if starts_with_at(bytes, PLACEHOLDER, i) {
+            w = w.push_bytes(bytes, start, i).push_str(flag);
+            i += PLACEHOLDER.len();
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+
+    w.push_bytes(bytes, start, bytes.len())
+}
+
+const fn starts_with_at(bytes: &[u8], prefix: &[u8], at: usize) -> bool {
+    if at + prefix.len() > bytes.len() {
+        return false;
+    }
+
+    let mut j = 0;
+    while j < prefix.len() {
+        if bytes[at + j] != prefix[j] {
+            return false;
+        }
+
+        j += 1;
+    }
+
+    true
+}
+
+/// The length of `s` after [`wrap`].
+pub const fn wrapped_len(s: &str, width: usize) -> usize {
+    write_wrapped(Writer::<0>::new(), s, width).len
+}
+
+/// Wraps the lines of `s` that are longer than `width` characters for [`simple_format`]'s
+/// `width` option. `N` must be [`wrapped_len`].
+///
+/// Wrapped lines keep their indentation.
+pub const fn wrap<const N: usize>(s: &str, width: usize) -> [u8; N] {
+    let writer = write_wrapped(Writer::new(), s, width);
+    assert!(writer.len == N);
+
+    writer.buf
+}
+
+const fn write_wrapped<const N: usize>(mut w: Writer<N>, s: &str, width: usize) -> Writer<N> {
+    let bytes = s.as_bytes();
+
+    let mut start = 0;
+    while start <= bytes.len() {
+        let mut end = start;
+        let mut chars = 0;
+        while end < bytes.len() && bytes[end] != b'\n' {
+            if bytes[end] & 0xC0 != 0x80 {
+                chars += 1;
+            }
+            end += 1;
+        }
+
+        if width == 0 || chars <= width {
+            w = w.push_bytes(bytes, start, end);
+        } else {
+            let mut indent = start;
+            while indent < end && bytes[indent] == b' ' {
+                indent += 1;
+            }
+
+            w = w.push_bytes(bytes, start, indent).push_words(
+                bytes,
+                indent,
+                end,
+                indent - start,
+                width,
+            );
+        }
+
+        if end < bytes.len() {
+            w = w.push_byte(b'\n');
+        }
+
+        start = end + 1;
+    }
+
+    w
+}
+
+/// The length of `s` after [`strip_markup`].
+pub const fn stripped_len(s: &str) -> usize {
+    write_stripped(Writer::<0>::new(), s).len
+}
+
+/// Removes Markdown markup from `s` for [`plain_format`]. `N` must be [`stripped_len`].
+pub const fn strip_markup<const N: usize>(s: &str) -> [u8; N] {
+    let writer = write_stripped(Writer::new(), s);
+    assert!(writer.len == N);
+
+    writer.buf
+}
+
+const fn write_stripped<const N: usize>(mut w: Writer<N>, s: &str) -> Writer<N> {
+    let bytes = s.as_bytes();
+    let mut in_code = false;
+
+    // The positions of the closing markers of the emphasis that is currently open
+    let mut closers = [0; 8];
+    let mut open = 0;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+
+        if byte == b'`' {
+            in_code = !in_code;
+            i += 1;
+        } else if byte == b'*' && !in_code {
+            let run = marker_run(bytes, i);
+
+            let closer = if open < closers.len() {
+                find_closer(bytes, i, run)
+            } else {
+                None
+            };
+
+            if open > 0 && closers[open - 1] == i {
+                open -= 1;
+            } else if let Some(closer) = closer {
+                closers[open] = closer;
+                open += 1;
+            } else {
+                w = w.push_bytes(bytes, i, i + run);
+            }
+
+            i += run;
+        } else {
+            w = w.push_byte(byte);
+            i += 1;
+        }
+    }
+
+    w
+}
+
+/// The number of `*`s starting at `i`.
+const fn marker_run(bytes: &[u8], i: usize) -> usize {
+    let mut end = i;
+    while end < bytes.len() && bytes[end] == b'*' {
+        end += 1;
+    }
+
+    end - i
+}
+
+/// Whether `byte` is part of a word (emphasis markers have to be at the start or the end of one).
+const fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80
+}
+
+/// If the `run` asterisks at `i` open an emphasis (at the start of a word), returns the position
+/// of the closing asterisks (as many, at the end of a word on the same line and outside of code
+/// spans).
+const fn find_closer(bytes: &[u8], i: usize, run: usize) -> Option<usize> {
+    let end = i + run;
+    if run > 3 || (i > 0 && is_word_byte(bytes[i - 1])) || end == bytes.len() {
+        return None;
+    }
+
+    if bytes[end].is_ascii_whitespace() || !(is_word_byte(bytes[end]) || bytes[end] == b'`') {
+        return None;
+    }
+
+    let mut in_code = false;
+    let mut j = end;
+    while j < bytes.len() && bytes[j] != b'\n' {
+        if bytes[j] == b'`' {
+            in_code = !in_code;
+        }
+
+        if bytes[j] != b'*' || in_code {
+            j += 1;
+            continue;
+        }
+
+        let closer_run = marker_run(bytes, j);
+        let after = j + closer_run;
+        let closes = !bytes[j - 1].is_ascii_whitespace()
+            && (after == bytes.len() || !is_word_byte(bytes[after]));
+
+        if closer_run == run && closes {
+            return Some(j);
+        }
+
+        j = after;
+    }
+
+    None
+}
+
+/// The length of `s` after [`uppercase_params`].
+pub const fn uppercase_params_len(s: &str) -> usize {
+    write_uppercase_params(Writer::<0>::new(), s).len
+}
+
+/// Converts the parameters of a [`simple_format`] help message with `param_style: caps` to
+/// uppercase. `N` must be [`uppercase_params_len`].
+pub const fn uppercase_params<const N: usize>(s: &str) -> [u8; N] {
+    let writer = write_uppercase_params(Writer::new(), s);
+    assert!(writer.len == N);
+
+    writer.buf
+}
+
+const fn write_uppercase_params<const N: usize>(mut w: Writer<N>, s: &str) -> Writer<N> {
+    let bytes = s.as_bytes();
+    let mut in_param = false;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == 0 {
+            in_param = !in_param;
+        } else if in_param {
+            w = w.push_byte(bytes[i].to_ascii_uppercase());
+        } else {
+            w = w.push_byte(bytes[i]);
+        }
+
+        i += 1;
+    }
+
+    w
+}
+
+/// A help message created by [`table_format`].
+pub struct Table {
+    pub lines: &'static [TableLine],
+    pub gutter: usize,
+    pub max_width: usize,
+    pub param_style: ParamStyle,
+    pub undocumented: Undocumented,
+    /// The maximum width of a line, or `0` if the documentation isn't wrapped.
+    pub width: usize,
+}
+
+/// A line of text or a flag in a [`Table`].
+pub enum TableLine {
+    Text(&'static str),
+    Row(TableRow),
+}
+
+/// A flag in a [`Table`].
+pub struct TableRow {
+    pub doc: &'static [&'static str],
+    pub flags: &'static [&'static str],
+    pub params: &'static [&'static str],
+}
+
+impl Table {
+    /// The length of the help message in bytes.
+    pub const fn rendered_len(&self) -> usize {
+        self.write(Writer::<0>::new()).len
+    }
+
+    /// Renders the help message. `N` must be [`Table::rendered_len`].
+    pub const fn render<const N: usize>(&self) -> [u8; N] {
+        let writer = self.write(Writer::new());
+        assert!(writer.len == N);
+
+        writer.buf
+    }
+
+    const fn write<const N: usize>(&self, mut w: Writer<N>) -> Writer<N> {
+        // The width of the first column, which is shared by every `docs!()`
+        let mut column = 0;
+        let mut i = 0;
+        while i < self.lines.len() {
+            if let TableLine::Row(row) = &self.lines[i] {
+                if row.first_doc().is_none() && matches!(self.undocumented, Undocumented::Skip) {
+                    i += 1;
+                    continue;
+                }
+
+                let width = row.write_header(Writer::<0>::new(), self.param_style).chars;
+                if width > column && width <= self.max_width {
+                    column = width;
+                }
+            }
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < self.lines.len() {
+            let row = match &self.lines[i] {
+                TableLine::Text(line) => {
+                    w = w.push_str(line).push_str("\n");
+                    i += 1;
+                    continue;
+                }
+                TableLine::Row(row) => row,
+            };
+
+            let doc = match (row.first_doc(), self.undocumented) {
+                (None, Undocumented::Skip) => {
+                    i += 1;
+                    continue;
+                }
+                (None, Undocumented::Placeholder(text)) => Some(text),
+                (doc, _) => doc,
+            };
+
+            w = w.push_str("  ");
+            let start = w.chars;
+            w = row.write_header(w, self.param_style);
+            let width = w.chars - start;
+
+            if let Some(doc) = doc {
+                if width <= column {
+                    w = w.push_spaces(column - width + self.gutter);
+                } else {
+                    w = w.push_str("\n").push_spaces(2 + column + self.gutter);
+                }
+
+                if self.width == 0 {
+                    w = w.push_trimmed(doc);
+                } else {
+                    let indent = 2 + column + self.gutter;
+                    w = w.push_words(doc.as_bytes(), 0, doc.len(), indent, self.width);
+                }
+            }
+
+            w = w.push_str("\n");
+            i += 1;
+        }
+
+        w
+    }
+}
+
+impl TableRow {
+    /// Writes the flag's aliases and parameters (eg `--output, -o <path>`).
+    const fn write_header<const N: usize>(&self, mut w: Writer<N>, style: ParamStyle) -> Writer<N> {
+        let mut i = 0;
+        while i < self.flags.len() {
+            if i != 0 {
+                w = w.push_str(", ");
+            }
+            w = w.push_trimmed(self.flags[i]);
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < self.params.len() {
+            if i != 0 || !self.flags.is_empty() {
+                w = w.push_str(" ");
+            }
+            w = match style {
+                ParamStyle::Angle => w.push_str("<").push_str(self.params[i]).push_str(">"),
+                ParamStyle::Square => w.push_str("[").push_str(self.params[i]).push_str("]"),
+                ParamStyle::Caps => w.push_uppercase(self.params[i]),
+            };
+            i += 1;
+        }
+
+        w
+    }
+
+    /// The first non-blank line of the flag's documentation.
+    const fn first_doc(&self) -> Option<&'static str> {
+        let mut i = 0;
+        while i < self.doc.len() {
+            let bytes = self.doc[i].as_bytes();
+
+            let mut j = 0;
+            while j < bytes.len() {
+                if !bytes[j].is_ascii_whitespace() {
+                    return Some(self.doc[i]);
+                }
+                j += 1;
+            }
+
+            i += 1;
+        }
+
+        None
+    }
+}
+
+/// Writes into a fixed-size buffer. Bytes that don't fit are only counted, so a `Writer<0>` can be
+/// used to find the required size.
+struct Writer<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    chars: usize,
+    /// The number of characters since the last newline.
+    column: usize,
+}
+
+impl<const N: usize> Writer<N> {
+    const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+            chars: 0,
+            column: 0,
+        }
+    }
+
+    const fn push_byte(mut self, byte: u8) -> Self {
+        if self.len < N {
+            self.buf[self.len] = byte;
+        }
+        self.len += 1;
+
+        // UTF-8 continuation bytes don't start a new character
+        if byte & 0xC0 != 0x80 {
+            self.chars += 1;
+            self.column += 1;
+        }
+
+        if byte == b'\n' {
+            self.column = 0;
+        }
+
+        self
+    }
+
+    const fn push_str(self, s: &str) -> Self {
+        self.push_bytes(s.as_bytes(), 0, s.len())
+    }
+
+    /// Pushes `s` without leading or trailing whitespace.
+    const fn push_trimmed(self, s: &str) -> Self {
+        let bytes = s.as_bytes();
+
+        let mut start = 0;
+        while start < bytes.len() && bytes[start].is_ascii_whitespace() {
+            start += 1;
+        }
+
+        let mut end = bytes.len();
+        while end > start && bytes[end - 1].is_ascii_whitespace() {
+            end -= 1;
+        }
+
+        self.push_bytes(bytes, start, end)
+    }
+
+    const fn push_bytes(mut self, bytes: &[u8], start: usize, end: usize) -> Self {
+        let mut i = start;
+        while i < end {
+            self = self.push_byte(bytes[i]);
+            i += 1;
+        }
+
+        self
+    }
+
+    const fn push_uppercase(mut self, s: &str) -> Self {
+        let bytes = s.as_bytes();
+
+        let mut i = 0;
+        while i < bytes.len() {
+            self = self.push_byte(bytes[i].to_ascii_uppercase());
+            i += 1;
+        }
+
+        self
+    }
+
+    /// Pushes the words in `bytes[start..end]` separated by single spaces. If a word doesn't fit
+    /// before `width`, it is moved to a new line that is indented by `indent` spaces.
+    const fn push_words(
+        mut self,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+        indent: usize,
+        width: usize,
+    ) -> Self {
+        let mut first = true;
+
+        let mut i = start;
+        while i < end {
+            if bytes[i].is_ascii_whitespace() {
+                i += 1;
+                continue;
+            }
+
+            let word_start = i;
+            let mut word_chars = 0;
+            while i < end && !bytes[i].is_ascii_whitespace() {
+                if bytes[i] & 0xC0 != 0x80 {
+                    word_chars += 1;
+                }
+                i += 1;
+            }
+
+            if !first {
+                if self.column + 1 + word_chars > width {
+                    self = self.push_byte(b'\n').push_spaces(indent);
+                } else {
+                    self = self.push_byte(b' ');
+                }
+            }
+
+            self = self.push_bytes(bytes, word_start, i);
+            first = false;
+        }
+
+        self
+    }
+
+    const fn push_spaces(mut self, count: usize) -> Self {
+        let mut i = 0;
+        while i < count {
+            self = self.push_byte(b' ');
+            i += 1;
+        }
+
+        self
+    }
 }
@@ -1,70 +1,1069 @@
+//! Runtime help message generation.
+
+use core::cmp::Ordering;
+use std::{
+    ffi::OsString,
+    io::{self, IsTerminal, Write},
+    path::Path,
+};
+
 use crate::Flag;
 
 /// Trims, concatenates, performs line wrapping, and indents doc comments.
-fn add_doccoments(buf: &mut String, docs: &[&str], indent_level: usize) {
+///
+/// Lines starting with a list marker (`*`, `-`, or `1.`) begin a new line and are wrapped with a
+/// hanging indent. Lines indented by four or more spaces and lines inside of ```` ``` ```` fences
+/// are copied verbatim. Lines ending with a backslash or two spaces are followed by a line break.
+fn add_doccoments(buf: &mut String, docs: &[&str], indent_level: usize, options: &HelpOptions) {
     let mut chars = 0;
-    for d in docs {
-        let d = d.trim();
+    let mut hanging = 0;
+    let mut fenced = false;
+    let mut verbatim = false;
 
-        if d.is_empty() {
+    for d in docs.iter().flat_map(|d| crate::docs::lines(d)) {
+        // Doc comments are usually written as `/// text`, so the first space isn't indentation
+        let d = d.strip_prefix(' ').unwrap_or(d);
+        let hard_break = d.ends_with("  ") || d.trim_end().ends_with('\\');
+        let d = d.trim_end();
+
+        verbatim = fenced || d.starts_with("    ") || d.trim_start().starts_with("```");
+
+        if d.trim_start().starts_with("```") {
+            fenced = !fenced;
+            end_line(buf, &mut chars);
+            continue;
+        }
+
+        // Both kinds of code blocks are indented by two spaces (like `#[example(...)]`s)
+        if verbatim {
+            end_line(buf, &mut chars);
+            push_indent(buf, indent_level + 2);
+            *buf += if fenced { d } else { &d[4..] };
             buf.push('\n');
+            continue;
+        }
 
-            chars = 0;
+        let mut d = d.trim();
+
+        if hard_break {
+            d = d.strip_suffix('\\').unwrap_or(d).trim_end();
+        }
+
+        let rendered;
+        if options.render_links || options.strip_code_spans {
+            rendered = render_markup(d, options);
+            d = &rendered;
+        }
+
+        // Blank lines end paragraphs, list items, and code blocks without adding an empty line
+        if d.is_empty() {
+            end_line(buf, &mut chars);
+            hanging = 0;
             continue;
         }
 
-        let mut iter = d.split_ascii_whitespace().peekable();
+        let mut words = d.split_ascii_whitespace().peekable();
+
+        if is_list_marker(d) {
+            let marker = words.next().unwrap();
+
+            end_line(buf, &mut chars);
+            push_indent(buf, indent_level);
+            *buf += marker;
+
+            chars = indent_level + marker.len();
+            hanging = marker.len() + 1;
+        }
 
-        while let Some(w) = iter.peek() {
+        let indent_level = indent_level + hanging;
+
+        while let Some(w) = words.peek() {
             if chars == 0 {
-                for _ in 0..indent_level {
-                    buf.push(' ');
-                }
+                push_indent(buf, indent_level);
 
                 *buf += w;
                 chars = w.len() + indent_level;
-                iter.next();
-            } else if chars + w.len() < 80 {
+                words.next();
+            } else if chars + w.len() < options.width {
                 buf.push(' ');
                 *buf += w;
                 chars += 1 + w.len();
-                iter.next();
+                words.next();
             } else {
                 buf.push('\n');
                 chars = 0;
             }
         }
+
+        if hard_break {
+            end_line(buf, &mut chars);
+        }
+    }
+
+    // Every entry is followed by one blank line, even if it doesn't have any docs
+    *buf += if docs.is_empty() || verbatim {
+        "\n"
+    } else {
+        "\n\n"
+    };
+}
+
+/// Adds a flag's `#[example(...)]` command lines under an `Example:` or `Examples:` heading.
+fn add_examples(buf: &mut String, examples: &[&str], indent_level: usize) {
+    if examples.is_empty() {
+        return;
     }
 
-    if !docs.is_empty() {
-        *buf += "\n\n";
+    push_indent(buf, indent_level);
+    *buf += if examples.len() == 1 {
+        "Example:\n"
+    } else {
+        "Examples:\n"
+    };
+
+    for example in examples {
+        push_indent(buf, indent_level + 2);
+        *buf += example;
+        buf.push('\n');
+    }
+}
+
+/// Renders links (`[text](url)` => `text (url)` and `` [`Flag`] `` => `` `Flag` ``) and removes
+/// the backticks around code spans, depending on `options`.
+fn render_markup(line: &str, options: &HelpOptions) -> String {
+    let mut out = String::new();
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                let span = &rest[..end + 2];
+
+                out += if options.strip_code_spans {
+                    &span[1..span.len() - 1]
+                } else {
+                    span
+                };
+
+                rest = &rest[span.len()..];
+                continue;
+            }
+        } else if c == '[' && options.render_links {
+            if let Some(end) = closing_bracket(rest, '[', ']') {
+                out += &render_markup(&rest[1..end], options);
+                rest = &rest[end + 1..];
+
+                if rest.starts_with('(') {
+                    if let Some(end) = closing_bracket(rest, '(', ')') {
+                        out += " ";
+                        out += &rest[..end + 1];
+                        rest = &rest[end + 1..];
+                    }
+                } else if rest.starts_with('[') {
+                    // Reference links (`[text][reference]`)
+                    if let Some(end) = closing_bracket(rest, '[', ']') {
+                        rest = &rest[end + 1..];
+                    }
+                }
+
+                continue;
+            }
+        }
+
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    out
+}
+
+/// Returns the index of the bracket that closes the one at the start of `s`.
+fn closing_bracket(s: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, c) in s.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns true if a (trimmed) doc comment line starts with `* `, `- `, or `1. `
+fn is_list_marker(line: &str) -> bool {
+    let Some((marker, _)) = line.split_once(' ') else {
+        return false;
+    };
+
+    match marker.strip_suffix('.') {
+        Some(number) => !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()),
+        None => marker == "*" || marker == "-",
+    }
+}
+
+fn end_line(buf: &mut String, chars: &mut usize) {
+    if *chars != 0 {
+        buf.push('\n');
+        *chars = 0;
+    }
+}
+
+fn push_indent(buf: &mut String, indent_level: usize) {
+    for _ in 0..indent_level {
+        buf.push(' ');
+    }
+}
+
+/// Where positional arguments are displayed relative to the other flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Before,
+    After,
+}
+
+/// The order that flags are displayed in. Positional arguments are always kept in their own
+/// section.
+#[derive(Debug, Clone, Copy)]
+pub enum SortOrder {
+    /// The order that the flags were declared in.
+    Declaration,
+    /// Sorted case-insensitively by the first long alias (or the first alias if there isn't one).
+    /// Flags with equal names keep their declaration order.
+    Alphabetical,
+    /// Sorted with a custom comparison function. Flags that compare equal keep their declaration
+    /// order.
+    Custom(fn(&Flag, &Flag) -> Ordering),
+}
+
+impl SortOrder {
+    fn sort(self, flags: &mut [&Flag]) {
+        match self {
+            Self::Declaration => {}
+            Self::Alphabetical => flags.sort_by_cached_key(|flag| {
+                let name = flag
+                    .flags
+                    .iter()
+                    .map(|alias| alias.trim())
+                    .find(|alias| alias.starts_with("--"))
+                    .or(flag.flags.first().copied())
+                    .unwrap_or_default();
+
+                name.trim_start_matches('-').to_lowercase()
+            }),
+            Self::Custom(compare) => flags.sort_by(|a, b| compare(a, b)),
+        }
+    }
+}
+
+/// How a flag's aliases are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasStyle {
+    /// Short aliases first, separated by commas (eg `-o, --output <path>`).
+    Conventional,
+    /// In declaration order, separated by spaces (eg `--output -o <path>`).
+    Declaration,
+}
+
+/// How a flag's parameters are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamStyle {
+    /// In angle brackets (eg `<path>` or `<auto|never>`).
+    Angle,
+    /// In square brackets (eg `[path]` or `[auto|never]`).
+    Square,
+    /// In ASCII uppercase without brackets (eg `PATH`). Lists of choices are displayed in curly
+    /// brackets and keep their case (eg `{auto|never}`).
+    Caps,
+}
+
+impl ParamStyle {
+    fn format(self, name: &str, choices: &[&str]) -> String {
+        match (self, choices) {
+            (Self::Angle, []) => format!("<{name}>"),
+            (Self::Square, []) => format!("[{name}]"),
+            (Self::Caps, []) => name.to_ascii_uppercase(),
+            (Self::Angle, _) => format!("<{}>", choices.join("|")),
+            (Self::Square, _) => format!("[{}]", choices.join("|")),
+            (Self::Caps, _) => format!("{{{}}}", choices.join("|")),
+        }
+    }
+}
+
+/// How flags without documentation are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Undocumented {
+    /// Displays the flag without any documentation.
+    Show,
+    /// Doesn't display the flag.
+    Skip,
+    /// Displays the text as the flag's documentation (eg `Placeholder("(undocumented)")`).
+    Placeholder(&'static str),
+}
+
+impl Undocumented {
+    /// The documentation that is displayed for `flag`, or `None` if the flag isn't displayed.
+    fn docs<'a>(&'a self, flag: &'a Flag) -> Option<&'a [&'static str]> {
+        match self {
+            _ if !flag.doc.is_empty() => Some(flag.doc),
+            Self::Show => Some(flag.doc),
+            Self::Skip => None,
+            Self::Placeholder(text) => Some(core::slice::from_ref(text)),
+        }
+    }
+}
+
+/// Options for [`wrapping_format_with`].
+#[derive(Debug, Clone)]
+pub struct HelpOptions {
+    /// A heading displayed above the flags.
+    pub options_heading: Option<&'static str>,
+    /// The heading displayed above positional arguments (see [`Flag::is_positional`]).
+    pub positional_heading: &'static str,
+    pub positional_placement: Placement,
+    /// The order of the flags (excluding positional arguments).
+    pub sort: SortOrder,
+    pub alias_style: AliasStyle,
+    pub param_style: ParamStyle,
+    /// Renders Markdown and intra-doc links as plain text (eg `[the manual](https://example.com)`
+    /// => `the manual (https://example.com)` and `` [`Flag`] `` => `` `Flag` ``).
+    pub render_links: bool,
+    /// Removes the backticks around inline code (eg `` `Flag` `` => `Flag`).
+    pub strip_code_spans: bool,
+    /// The maximum width of a line. Doc comments and long flag headers are wrapped to fit.
+    pub width: usize,
+    /// How flags without any doc comments are displayed.
+    pub undocumented: Undocumented,
+}
+
+impl Default for HelpOptions {
+    fn default() -> Self {
+        Self {
+            options_heading: None,
+            positional_heading: "Arguments:",
+            positional_placement: Placement::After,
+            sort: SortOrder::Declaration,
+            alias_style: AliasStyle::Conventional,
+            param_style: ParamStyle::Angle,
+            render_links: false,
+            strip_code_spans: false,
+            width: 80,
+            undocumented: Undocumented::Show,
+        }
     }
 }
 
 /// More complicated runtime formatting of comandline options.
 ///
-/// This will automatically trim whitespace, indent, and perform line wrapping.
+/// This will automatically trim whitespace, indent, and perform line wrapping. List items and
+/// code blocks in doc comments are kept on their own lines.
+///
+/// Positional arguments are displayed in their own section. See [`wrapping_format_with`] to
+/// customize this.
 pub fn wrapping_format(buf: &mut String, docs: &[Flag]) {
-    for flag in docs {
-        if flag.flags.is_empty() {
+    wrapping_format_with(buf, docs, &HelpOptions::default())
+}
+
+/// [`wrapping_format`] with custom [`HelpOptions`].
+pub fn wrapping_format_with(buf: &mut String, docs: &[Flag], options: &HelpOptions) {
+    *buf += &render_flags(docs, &mut WrappingRenderer::new(options.clone()));
+}
+
+/// Like [`wrapping_format`], but flag headers are bold and section headings are bold and
+/// underlined (using ANSI escape codes).
+///
+/// See [`color_format_auto`] to only use colors when the environment allows it.
+pub fn color_format(buf: &mut String, docs: &[Flag]) {
+    color_format_with(buf, docs, &HelpOptions::default())
+}
+
+/// [`color_format`] with custom [`HelpOptions`].
+pub fn color_format_with(buf: &mut String, docs: &[Flag], options: &HelpOptions) {
+    *buf += &render_flags(docs, &mut ColorRenderer::new(options.clone()));
+}
+
+/// Uses [`color_format`] or [`wrapping_format`] depending on the environment. In order of
+/// precedence:
+/// 1. If `NO_COLOR` is set (and not empty), colors are never used.
+/// 2. If `CLICOLOR_FORCE` or `FORCE_COLOR` is set (and neither empty nor `0`), colors are always
+///    used.
+/// 3. Otherwise, colors are used if stdout is a terminal.
+pub fn color_format_auto(buf: &mut String, docs: &[Flag]) {
+    if use_color(
+        |name| std::env::var_os(name),
+        std::io::stdout().is_terminal(),
+    ) {
+        color_format(buf, docs)
+    } else {
+        wrapping_format(buf, docs)
+    }
+}
+
+/// Decides whether [`color_format_auto`] uses colors, given a function that reads environment
+/// variables.
+pub(crate) fn use_color(var: impl Fn(&str) -> Option<OsString>, is_terminal: bool) -> bool {
+    let is_set = |name| var(name).is_some_and(|value| !value.is_empty());
+    let is_forced = |name| var(name).is_some_and(|value| !value.is_empty() && value != "0");
+
+    if is_set("NO_COLOR") {
+        false
+    } else if is_forced("CLICOLOR_FORCE") || is_forced("FORCE_COLOR") {
+        true
+    } else {
+        is_terminal
+    }
+}
+
+/// A section of a help message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// Flags that aren't positional arguments.
+    Options,
+    /// Positional arguments (see [`Flag::is_positional`]).
+    Positionals,
+}
+
+/// Where a flag is displayed in a help message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlagContext {
+    pub section: Section,
+    /// The index of the flag in its section.
+    pub index: usize,
+    /// True if this is the last flag in its section.
+    pub is_last: bool,
+}
+
+/// Renders help messages with [`render_flags`].
+///
+/// Only [`HelpRenderer::flag`] is required. [`render_flags`] sorts the flags and splits them into
+/// sections, and then calls the other methods in order.
+pub trait HelpRenderer {
+    /// The order of the flags in [`Section::Options`].
+    fn sort_order(&self) -> SortOrder {
+        SortOrder::Declaration
+    }
+
+    /// Where [`Section::Positionals`] is placed relative to [`Section::Options`].
+    fn positional_placement(&self) -> Placement {
+        Placement::After
+    }
+
+    /// Returns false if `flag` shouldn't be rendered. Sections without any rendered flags are
+    /// skipped.
+    fn is_displayed(&self, _flag: &Flag) -> bool {
+        true
+    }
+
+    /// Called before anything else with all flags that will be rendered.
+    fn begin(&mut self, _buf: &mut String, _flags: &[&Flag]) {}
+
+    /// Called at the start of each section that contains at least one flag.
+    fn section(&mut self, _buf: &mut String, _section: Section) {}
+
+    /// Renders a flag.
+    fn flag(&mut self, buf: &mut String, flag: &Flag, context: FlagContext);
+
+    /// Renders a positional argument. By default, this calls [`HelpRenderer::flag`].
+    fn positional(&mut self, buf: &mut String, flag: &Flag, context: FlagContext) {
+        self.flag(buf, flag, context)
+    }
+
+    /// Called after everything else.
+    fn end(&mut self, _buf: &mut String) {}
+}
+
+/// Renders `flags` with a [`HelpRenderer`].
+pub fn render_flags(flags: &[Flag], renderer: &mut impl HelpRenderer) -> String {
+    let (positionals, mut options): (Vec<&Flag>, Vec<&Flag>) = flags
+        .iter()
+        .filter(|flag| renderer.is_displayed(flag))
+        .partition(|flag| flag.is_positional());
+
+    renderer.sort_order().sort(&mut options);
+
+    let mut sections = [
+        (Section::Options, options),
+        (Section::Positionals, positionals),
+    ];
+
+    if renderer.positional_placement() == Placement::Before {
+        sections.reverse();
+    }
+
+    let mut buf = String::new();
+
+    let all: Vec<&Flag> = sections
+        .iter()
+        .flat_map(|(_, flags)| flags)
+        .copied()
+        .collect();
+    renderer.begin(&mut buf, &all);
+
+    for (section, flags) in sections {
+        if flags.is_empty() {
             continue;
         }
 
-        *buf += " ";
+        renderer.section(&mut buf, section);
+
+        for (index, flag) in flags.iter().enumerate() {
+            let context = FlagContext {
+                section,
+                index,
+                is_last: index + 1 == flags.len(),
+            };
 
-        for flag in flag.flags {
-            *buf += " ";
-            *buf += flag.trim();
+            match section {
+                Section::Options => renderer.flag(&mut buf, flag, context),
+                Section::Positionals => renderer.positional(&mut buf, flag, context),
+            }
         }
+    }
 
-        for param in flag.params {
-            *buf += " <";
-            *buf += param;
-            *buf += ">";
+    renderer.end(&mut buf);
+
+    buf
+}
+
+/// The [`HelpRenderer`] used by [`wrapping_format_with`]: each flag's header is followed by its
+/// indented and wrapped documentation.
+#[derive(Debug, Clone)]
+pub struct WrappingRenderer {
+    options: HelpOptions,
+}
+
+impl WrappingRenderer {
+    /// Creates a renderer that formats flags according to `options`.
+    pub fn new(options: HelpOptions) -> Self {
+        Self { options }
+    }
+
+    /// Renders the documentation and examples that follow `flag`'s header.
+    fn flag_body(&self, buf: &mut String, flag: &Flag) {
+        let docs = self.options.undocumented.docs(flag).unwrap_or_default();
+
+        add_doccoments(buf, docs, 4, &self.options);
+
+        if !flag.examples.is_empty() {
+            add_examples(buf, flag.examples, 4);
+            buf.push('\n');
+        }
+    }
+}
+
+impl HelpRenderer for WrappingRenderer {
+    fn sort_order(&self) -> SortOrder {
+        self.options.sort
+    }
+
+    fn positional_placement(&self) -> Placement {
+        self.options.positional_placement
+    }
+
+    fn section(&mut self, buf: &mut String, section: Section) {
+        let heading = match section {
+            Section::Options => self.options.options_heading,
+            Section::Positionals => Some(self.options.positional_heading),
+        };
+
+        if let Some(heading) = heading {
+            *buf += heading;
+            buf.push('\n');
         }
+    }
 
+    fn is_displayed(&self, flag: &Flag) -> bool {
+        self.options.undocumented.docs(flag).is_some()
+    }
+
+    fn flag(&mut self, buf: &mut String, flag: &Flag, _context: FlagContext) {
+        *buf += &wrapped_flag_header(flag, &self.options, 2);
         *buf += "\n";
 
-        add_doccoments(buf, flag.doc, 4);
+        self.flag_body(buf, flag);
     }
 }
+
+/// The [`HelpRenderer`] used by [`color_format_with`]: like [`WrappingRenderer`], but flag headers
+/// and section headings are highlighted with ANSI escape codes.
+#[derive(Debug, Clone)]
+pub struct ColorRenderer {
+    inner: WrappingRenderer,
+}
+
+impl ColorRenderer {
+    const HEADER: &'static str = "\x1b[1m";
+    const HEADING: &'static str = "\x1b[1;4m";
+    const RESET: &'static str = "\x1b[0m";
+
+    /// Creates a renderer that formats flags according to `options` and highlights them.
+    pub fn new(options: HelpOptions) -> Self {
+        Self {
+            inner: WrappingRenderer::new(options),
+        }
+    }
+
+    /// Appends `text` with each line (excluding its indentation) wrapped in `style` and
+    /// [`Self::RESET`].
+    fn push_styled(buf: &mut String, text: &str, style: &str) {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                buf.push('\n');
+            }
+
+            let content = line.trim_start();
+            *buf += &line[..line.len() - content.len()];
+
+            if !content.is_empty() {
+                *buf += style;
+                *buf += content;
+                *buf += Self::RESET;
+            }
+        }
+    }
+}
+
+impl HelpRenderer for ColorRenderer {
+    fn sort_order(&self) -> SortOrder {
+        self.inner.sort_order()
+    }
+
+    fn positional_placement(&self) -> Placement {
+        self.inner.positional_placement()
+    }
+
+    fn is_displayed(&self, flag: &Flag) -> bool {
+        self.inner.is_displayed(flag)
+    }
+
+    fn section(&mut self, buf: &mut String, section: Section) {
+        let mut heading = String::new();
+        self.inner.section(&mut heading, section);
+
+        Self::push_styled(buf, &heading, Self::HEADING);
+    }
+
+    fn flag(&mut self, buf: &mut String, flag: &Flag, _context: FlagContext) {
+        Self::push_styled(
+            buf,
+            &wrapped_flag_header(flag, &self.inner.options, 2),
+            Self::HEADER,
+        );
+        *buf += "\n";
+
+        self.inner.flag_body(buf, flag);
+    }
+}
+
+/// A [`HelpRenderer`] that displays each flag's documentation next to its header:
+/// ```text
+///   -o, --output <path>  Sets the output file path.
+///   -h, --help           Displays this help message.
+/// ```
+/// The documentation is moved to the next line if a header is too long, and headers that don't fit
+/// on one line are wrapped like in [`WrappingRenderer`].
+#[derive(Debug, Clone)]
+pub struct ColumnRenderer {
+    options: HelpOptions,
+    column: usize,
+}
+
+impl ColumnRenderer {
+    /// The maximum indentation of the second column.
+    const MAX_COLUMN: usize = 32;
+
+    pub fn new(options: HelpOptions) -> Self {
+        Self { options, column: 0 }
+    }
+}
+
+impl HelpRenderer for ColumnRenderer {
+    fn sort_order(&self) -> SortOrder {
+        self.options.sort
+    }
+
+    fn positional_placement(&self) -> Placement {
+        self.options.positional_placement
+    }
+
+    fn is_displayed(&self, flag: &Flag) -> bool {
+        self.options.undocumented.docs(flag).is_some()
+    }
+
+    fn begin(&mut self, _buf: &mut String, flags: &[&Flag]) {
+        let widest = flags
+            .iter()
+            .map(|flag| {
+                flag_header(flag, self.options.alias_style, self.options.param_style)
+                    .chars()
+                    .count()
+            })
+            .max()
+            .unwrap_or_default();
+
+        self.column = (widest + 4).min(Self::MAX_COLUMN);
+    }
+
+    fn section(&mut self, buf: &mut String, section: Section) {
+        if section == Section::Positionals || !buf.is_empty() {
+            buf.push('\n');
+        }
+
+        WrappingRenderer::new(self.options.clone()).section(buf, section);
+    }
+
+    fn flag(&mut self, buf: &mut String, flag: &Flag, _context: FlagContext) {
+        let flag_docs = self.options.undocumented.docs(flag).unwrap_or_default();
+        let header = wrapped_flag_header(flag, &self.options, 2);
+
+        let mut docs = String::new();
+        add_doccoments(&mut docs, flag_docs, self.column, &self.options);
+        let docs = docs.trim_end_matches('\n');
+
+        *buf += &header;
+
+        let width = header.chars().count();
+        if !header.contains('\n')
+            && width + 2 <= self.column
+            && docs.starts_with(&" ".repeat(self.column))
+        {
+            push_indent(buf, self.column - width);
+            *buf += &docs[self.column..];
+        } else if !docs.is_empty() {
+            buf.push('\n');
+            *buf += docs;
+        }
+
+        buf.push('\n');
+        add_examples(buf, flag.examples, self.column);
+    }
+}
+
+/// The output stream used by [`print_help`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Options for [`print_help`].
+///
+/// The default follows the GNU conventions for an explicit `--help`: the help message is written
+/// to stdout and the process exits with code 0. For usage messages caused by an error, see
+/// [`PrintHelpOptions::usage_error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrintHelpOptions {
+    pub stream: Stream,
+    pub exit_code: i32,
+}
+
+impl PrintHelpOptions {
+    /// Writes to stderr and exits with code 2.
+    pub const fn usage_error() -> Self {
+        Self {
+            stream: Stream::Stderr,
+            exit_code: 2,
+        }
+    }
+}
+
+impl Default for PrintHelpOptions {
+    fn default() -> Self {
+        Self {
+            stream: Stream::Stdout,
+            exit_code: 0,
+        }
+    }
+}
+
+/// Prints a help message and then exits the process.
+///
+/// Errors while writing (eg a closed pipe in `prog --help | head`) are ignored, so the process
+/// still exits with `options.exit_code`.
+pub fn print_help(help: &str, options: PrintHelpOptions) -> ! {
+    match options.stream {
+        Stream::Stdout => write_help(io::stdout().lock(), help),
+        Stream::Stderr => write_help(io::stderr().lock(), help),
+    }
+
+    std::process::exit(options.exit_code)
+}
+
+/// Writes `help` and a newline to `out` for [`print_help`], ignoring any errors.
+pub(crate) fn write_help(mut out: impl Write, help: &str) {
+    let _ = writeln!(out, "{help}").and_then(|()| out.flush());
+}
+
+/// Returns the name that the program was invoked with (the file name of `argv[0]`).
+///
+/// This falls back to the file name of the current executable, and then to `"program"`.
+pub fn program_name() -> String {
+    let name = |path: &Path| {
+        let name = path.file_name()?.to_string_lossy();
+
+        #[cfg(windows)]
+        let name = name.strip_suffix(".exe").unwrap_or(&name).to_owned();
+
+        Some(name.into())
+    };
+
+    std::env::args_os()
+        .next()
+        .and_then(|arg0| name(Path::new(&arg0)))
+        .or_else(|| std::env::current_exe().ok().and_then(|exe| name(&exe)))
+        .unwrap_or_else(|| "program".to_owned())
+}
+
+/// Replaces each `{bin}` in `template` with `bin`. `{{bin}}` is replaced with a literal `{bin}`.
+///
+/// This is meant for compile-time help messages:
+/// ```rust
+/// # const HELP: &str = "Usage: {bin} [OPTIONS]";
+/// use argtea::help::{program_name, render};
+///
+/// println!("{}", render(HELP, &program_name()));
+/// ```
+pub fn render(template: &str, bin: &str) -> String {
+    let mut buf = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(idx) = rest.find('{') {
+        buf += &rest[..idx];
+        rest = &rest[idx..];
+
+        if let Some(after) = rest.strip_prefix("{{bin}}") {
+            buf += "{bin}";
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("{bin}") {
+            buf += bin;
+            rest = after;
+        } else {
+            buf.push('{');
+            rest = &rest[1..];
+        }
+    }
+
+    buf += rest;
+    buf
+}
+
+/// Generates a usage line like `myprog [OPTIONS] [--output <output_path>] [<file>...]`.
+///
+/// Flags without parameters are collapsed into `[OPTIONS]`, and flags with parameters are listed
+/// individually when there are at most three of them. Positional arguments are listed last. See
+/// [`usage_line_with`] to change the threshold.
+pub fn usage_line(program: &str, flags: &[Flag]) -> String {
+    usage_line_with(program, flags, 3)
+}
+
+/// [`usage_line`] but flags with parameters are only listed individually if there are at most
+/// `max_listed` of them.
+pub fn usage_line_with(program: &str, flags: &[Flag], max_listed: usize) -> String {
+    let (positionals, flags): (Vec<&Flag>, Vec<&Flag>) =
+        flags.iter().partition(|flag| flag.is_positional());
+
+    let value_flags = flags.iter().filter(|flag| !flag.params.is_empty()).count();
+    let listed = |flag: &Flag| !flag.params.is_empty() && value_flags <= max_listed;
+
+    let mut buf = program.to_owned();
+
+    if flags.iter().any(|flag| !listed(flag)) {
+        buf += " [OPTIONS]";
+    }
+
+    for flag in flags.iter().filter(|flag| listed(flag)) {
+        let name = flag
+            .flags
+            .iter()
+            .find(|flag| flag.starts_with("--"))
+            .unwrap_or(&flag.flags[0]);
+
+        buf += " [";
+        buf += name;
+
+        for param in flag.params {
+            buf += " <";
+            buf += param;
+            buf += ">";
+        }
+
+        buf += "]";
+    }
+
+    // Catch-all flags match any number of arguments
+    for flag in positionals {
+        for param in flag.params {
+            buf += " [<";
+            buf += param;
+            buf += ">...]";
+        }
+    }
+
+    buf
+}
+
+/// The layout used by [`to_markdown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkdownStyle {
+    /// A heading for each flag followed by its documentation.
+    Definitions,
+    /// A `| Flag | Description |` table containing the first line of each flag's documentation.
+    Table,
+}
+
+/// Generates Markdown documentation for the provided flags.
+///
+/// Doc comments are assumed to already be Markdown, so they are copied as-is (except for escaping
+/// `|` inside of tables).
+pub fn to_markdown(flags: &[Flag], style: MarkdownStyle) -> String {
+    let mut buf = String::new();
+
+    if style == MarkdownStyle::Table {
+        buf += "| Flag | Description |\n";
+        buf += "| ---- | ----------- |\n";
+    }
+
+    for flag in flags {
+        let lines = flag
+            .doc_lines()
+            .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end());
+
+        match style {
+            MarkdownStyle::Definitions => {
+                buf += "### `";
+                buf += &flag_header(flag, AliasStyle::Conventional, ParamStyle::Angle);
+                buf += "`\n\n";
+
+                let mut empty = true;
+                for line in lines {
+                    if line.is_empty() && empty {
+                        continue;
+                    }
+
+                    empty = line.is_empty();
+
+                    buf += line;
+                    buf.push('\n');
+                }
+
+                if !empty {
+                    buf.push('\n');
+                }
+            }
+            MarkdownStyle::Table => {
+                buf += "| `";
+                buf += &flag_header(flag, AliasStyle::Conventional, ParamStyle::Angle)
+                    .replace('|', "\\|");
+                buf += "` | ";
+                buf += &lines
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .unwrap_or_default()
+                    .replace('|', "\\|");
+                buf += " |\n";
+            }
+        }
+    }
+
+    buf
+}
+
+/// Formats a flag's aliases and parameters (eg `-o, --output <path>` or `--color <auto|never>`).
+pub(crate) fn flag_header(flag: &Flag, alias_style: AliasStyle, param_style: ParamStyle) -> String {
+    let (aliases, params) = header_words(flag, alias_style, param_style);
+
+    aliases
+        .into_iter()
+        .chain(params)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// [`flag_header`] indented by `indent` and wrapped to [`HelpOptions::width`] columns.
+///
+/// Lines are broken after an alias, and continuation lines are aligned with the first alias. If the
+/// parameters don't fit after the last alias, they are moved to their own line.
+fn wrapped_flag_header(flag: &Flag, options: &HelpOptions, indent: usize) -> String {
+    let (aliases, params) = header_words(flag, options.alias_style, options.param_style);
+    let width = options.width;
+
+    let mut buf = String::new();
+    push_indent(&mut buf, indent);
+
+    let mut chars = indent;
+    let mut push_word = |buf: &mut String, word: &str, force_break: bool| {
+        let len = word.chars().count();
+
+        if chars > indent && (force_break || chars + 1 + len > width) {
+            buf.push('\n');
+            push_indent(buf, indent);
+            chars = indent;
+        }
+
+        if chars > indent {
+            buf.push(' ');
+            chars += 1;
+        }
+
+        *buf += word;
+        chars += len;
+    };
+
+    for alias in &aliases {
+        push_word(&mut buf, alias, false);
+    }
+
+    let params_len = params.iter().map(|p| p.chars().count() + 1).sum::<usize>();
+    let params_fit = buf.lines().last().unwrap_or_default().chars().count() + params_len <= width;
+
+    for (i, param) in params.iter().enumerate() {
+        push_word(&mut buf, param, i == 0 && !params_fit);
+    }
+
+    buf
+}
+
+/// Splits a flag header into its aliases (with trailing commas for [`AliasStyle::Conventional`])
+/// and its parameters (eg `<path>`).
+fn header_words(
+    flag: &Flag,
+    alias_style: AliasStyle,
+    param_style: ParamStyle,
+) -> (Vec<String>, Vec<String>) {
+    let mut aliases: Vec<&str> = flag.flags.iter().map(|alias| alias.trim()).collect();
+
+    let aliases = match alias_style {
+        AliasStyle::Conventional => {
+            aliases.sort_by_key(|alias| alias.starts_with("--"));
+
+            let last = aliases.len().saturating_sub(1);
+            aliases
+                .iter()
+                .enumerate()
+                .map(|(i, alias)| {
+                    if i == last {
+                        alias.to_string()
+                    } else {
+                        format!("{alias},")
+                    }
+                })
+                .collect()
+        }
+        AliasStyle::Declaration => aliases.iter().map(|alias| alias.to_string()).collect(),
+    };
+
+    let params = flag
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| param_style.format(param, flag.possible_values(i)))
+        .collect();
+
+    (aliases, params)
+}
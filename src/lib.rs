@@ -17,11 +17,14 @@
 //!
 //! ## Example project:
 //! ```rust
-//! use argtea::{argtea_impl, simple_format};
+//! use argtea::{
+//!     argtea_impl, expect_parsed, expect_value, print_help, simple_format, PrintHelpOptions,
+//! };
 //!
 //! #[derive(Debug)]
 //! pub struct Arguments {
 //!     output_path: String,
+//!     jobs: usize,
 //!     files: Vec<String>,
 //! }
 //!
@@ -30,6 +33,7 @@
 //!
 //!     println!("input files: {:?}", args.files);
 //!     println!("output file: {:?}", args.output_path);
+//!     println!("jobs: {}", args.jobs);
 //!
 //!     Ok(())
 //! }
@@ -38,28 +42,24 @@
 //!     {
 //!         /// Displays this help message.
 //!         ("--help" | "-h") => {
-//!             eprintln!("{}", Self::HELP);
-//!
-//!             std::process::exit(0);
+//!             print_help(Self::HELP, PrintHelpOptions::default());
 //!         }
 //!
 //!         /// Sets the output file path.
 //!         (flag @ "--output" | "-o", output_path) => {
-//!             let Some(output_path) = output_path else {
-//!                 return Err(format!("Expected path after `{flag}`"));
-//!             };
+//!             output_path_ = Some(expect_value(flag, output_path)?);
+//!         }
 //!
-//!             output_path_ = Some(output_path);
+//!         /// Sets the number of parallel jobs.
+//!         (flag @ "--jobs" | "-j", jobs) => {
+//!             jobs_ = expect_parsed(flag, jobs)?;
 //!         }
 //!
 //!         /// Adds a file as an input.
 //!         ///
 //!         /// To input a file that starts with a `-`, prefix it with a `./`
+//!         #[strict_value]
 //!         (file) => {
-//!             if file.starts_with("-") {
-//!                 return Err(format!("invalid flag `{file}`"));
-//!             }
-//!             
 //!             files.push(file);
 //!         }
 //!     }
@@ -78,11 +78,13 @@
 //!         pub fn parse() -> Result<Self, String> {
 //!             let mut files = Vec::new();
 //!             let mut output_path_ = None;
+//!             let mut jobs_ = 1;
 //!
 //!             parse!(std::env::args().skip(1));
 //!
 //!             Ok(Self {
 //!                 files,
+//!                 jobs: jobs_,
 //!                 output_path: output_path_.unwrap_or_else(|| "a.out".to_owned())
 //!             })
 //!         }
@@ -90,13 +92,192 @@
 //! }
 //! ```
 //! ## Functions
-//! Argtea functions are defined with syntax similar to regular Rust functions. Note: unlike Rust
-//! functions, generics cannot be specified.
+//! Argtea functions are defined with syntax similar to regular Rust functions, including generics
+//! and `where` clauses (eg `fn parse_from<I: IntoIterator<Item = String>>(args: I) -> Self`).
+//! They can also have qualifiers like `const`, `unsafe`, `extern "C"`, or `async` (in which case
+//! flags can use `.await`).
+//!
+//! Argtea functions can also be methods (eg `fn apply_args(&mut self, ...)` to amend a config that
+//! was loaded from a file), in which case flags can use `self`.
 //!
 //! Argtea functions can use the `parse!()` macro which takes in a `String` iterator. It will then
 //! parse it using the flags and code defined above.
 //!
-//! NOTE: the `parse!()` macro cannot be used within code blocks.
+//! `parse!()` can be used anywhere in the function, including inside of blocks, `match` arms, and
+//! closures. Everything else is passed through unchanged, so the body is written like the body of
+//! a regular function (eg `if`/`else` and `for` statements don't need semicolons). Because the
+//! whole function is scanned for `parse!()`, very long argtea functions may need a higher
+//! `#![recursion_limit = "..."]`; moving code into regular functions also helps.
+//!
+//! The variables that `parse!()` uses internally are hidden from the function, so they never
+//! collide with or shadow the function's own variables (eg a `flag` or `flag_buf` variable can be
+//! used anywhere). The only identifiers that `parse!()` introduces into the flags' code are:
+//! - the flags' parameters
+//! - the name before an `@` in a flag (eg `flag` in `(flag @ "--output" | "-o", path)`), which is
+//!   the alias that was used (eg for error messages). It isn't part of the documentation, so the
+//!   flag is documented like `("--output" | "-o", path)`
+//! - the variable passed to `parse!(args)`, which refers to the flag's remaining values (eg
+//!   `args.next()` takes the next value like a parameter would)
+//! - the `name` in the `index: name` option (see "Argument indices" below)
+//! - the `'stop_parsing` label (see [`break`](#break) below)
+//!
+//! ## Parsing into a struct
+//! The struct can also be defined inside of [`argtea_impl`] (between the flags and the `impl`).
+//! Each of its fields can optionally have a default value (`Default::default()` if it is omitted).
+//! Then, argtea functions can use the `parse_fields!()` macro, which declares a mutable variable
+//! for each field, calls `parse!()`, and constructs `Self` from the variables:
+//! ```rust
+//! use argtea::argtea_impl;
+//!
+//! argtea_impl! {
+//!     {
+//!         /// Sets the output file path.
+//!         (flag @ "--output" | "-o", path) => {
+//!             output_path = path.ok_or_else(|| format!("Expected path after `{flag}`"))?;
+//!         }
+//!
+//!         /// Adds a file as an input.
+//!         (file) => {
+//!             files.push(file);
+//!         }
+//!     }
+//!
+//!     #[derive(Debug)]
+//!     pub struct Arguments {
+//!         pub output_path: String = "a.out".to_owned(),
+//!         pub files: Vec<String>,
+//!     }
+//!
+//!     impl Arguments {
+//!         pub fn parse() -> Result<Self, String> {
+//!             Ok(parse_fields!(std::env::args().skip(1)))
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Like `parse!()`, `parse_fields!()` can be used anywhere in the function (eg
+//! `Ok(parse_fields!(...))`).
+//!
+//! When the variables are declared by the function instead (eg to validate them before
+//! constructing `Self`), `build!()` constructs `Self` from the variables with the same names as
+//! the fields. A variable can be an `Option` of its field's type, in which case `None` results in
+//! the field's default value. This includes `Option` fields, so a `None` variable for
+//! `color: Option<String> = Some("auto".to_owned())` results in `Some("auto")`. Other variables
+//! are used as-is:
+//! ```rust
+//! # use argtea::argtea_impl;
+//! argtea_impl! {
+//!     {
+//!         /// Sets the output file path.
+//!         ("--output" | "-o", path) => {
+//!             output_path = path;
+//!         }
+//!
+//!         /// Sets the number of jobs.
+//!         ("--jobs" | "-j", count: u32) => {
+//!             jobs = Some(count);
+//!         }
+//!
+//!         /// Adds a file as an input.
+//!         (file) => {
+//!             files.push(file);
+//!         }
+//!     }
+//!
+//!     pub struct Arguments {
+//!         pub output_path: String = "a.out".to_owned(),
+//!         pub jobs: u32 = 1,
+//!         pub files: Vec<String>,
+//!     }
+//!
+//!     impl Arguments {
+//!         pub fn parse_from(args: Vec<String>) -> Result<Self, argtea::ParseError> {
+//!             let mut output_path = None;
+//!             let mut jobs = None;
+//!             let mut files = Vec::new();
+//!
+//!             parse!(args.into_iter());
+//!
+//!             Ok(build!())
+//!         }
+//!     }
+//! }
+//!
+//! let args = Arguments::parse_from(vec!["-j4".to_owned(), "main.c".to_owned()]).unwrap();
+//! assert_eq!((args.output_path.as_str(), args.jobs), ("a.out", 4));
+//! ```
+//!
+//! ## Trait impls
+//! Traits can be implemented with `impl Trait for Type`, and there can be multiple `impl` blocks.
+//! `impl` blocks can also have generics and `where` clauses (eg `impl<T: Backend> Arguments<T>`).
+//! Unlike functions in other `impl` blocks, the functions in trait impls aren't made `pub` (see
+//! [Visibility](#visibility)):
+//! ```rust
+//! use argtea::argtea_impl;
+//!
+//! trait ParseArgs: Sized {
+//!     fn parse_args(args: Vec<String>) -> Self;
+//! }
+//!
+//! argtea_impl! {
+//!     {
+//!         /// Adds a file as an input.
+//!         (file) => {
+//!             files.push(file);
+//!         }
+//!     }
+//!
+//!     pub struct Arguments {
+//!         pub files: Vec<String>,
+//!     }
+//!
+//!     impl ParseArgs for Arguments {
+//!         fn parse_args(args: Vec<String>) -> Self {
+//!             parse_fields!(args.into_iter())
+//!         }
+//!     }
+//!
+//!     impl Arguments {
+//!         fn parse() -> Self {
+//!             Self::parse_args(std::env::args().skip(1).collect())
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## Visibility
+//! Functions keep their visibility (eg `pub(crate) fn parse()`), but functions without one are
+//! currently made `pub`. This default is deprecated and will become private (like in regular
+//! `impl` blocks) in a future version, so functions that should be public should be declared
+//! with `pub`.
+//!
+//! `#![default_visibility(private)]` (before the flags and their attributes) opts into the
+//! new default, and `#![default_visibility(pub)]` keeps the current one:
+//! ```rust,compile_fail,E0624
+//! mod args {
+//!     pub struct Arguments;
+//!
+//!     argtea::argtea_impl! {
+//!         #![default_visibility(private)]
+//!         {
+//!             (_file) => {}
+//!         }
+//!         impl Arguments {
+//!             pub fn parse() {
+//!                 Self::parse_from(std::env::args());
+//!             }
+//!
+//!             fn parse_from(mut args: impl Iterator<Item = String>) {
+//!                 parse!(args);
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! // error: `parse_from` is private
+//! args::Arguments::parse_from(std::env::args());
+//! ```
 //!
 //! ## Constants
 //! There are two types of argtea constants:
@@ -124,13 +305,184 @@
 //!   ```
 //!
 //! The first type of constant generates an [`Flag`] for each non-`#[hidden]` flag. This can
-//! be used to generate help messages and other information at run-time.
+//! be used to generate help messages and other information at run-time. Other flags can be
+//! selected with `docs!(hidden)`, `docs!(all)`, or `docs!(section = "...")` (see
+//! `#[section = "..."]` below).
+//!
+//! A flag constant can also implement the [`Parser`] trait (`impl Parser for Foo { const FLAGS:
+//! &'static [Flag] = docs!(); }`), so generic code can use the flags of any argtea type.
 //!
 //! Macro constants call macros with information about the non-`#[hidden]` flags. These can be used
 //! for compile-time help message generation. This crate provides the [`simple_format`] macro which
 //! provides simple, compile-time help message generation. For more information about formatting
 //! macros, see the "Formatting macros" section below.
 //!
+//! `flag_width!()` can also be used as a constant of type `usize`. It is the width of the widest
+//! non-`#[hidden]` flag's aliases and parameters (eg `-o, --output <path>`, see [`Flag::width`]),
+//! which is useful for aligning custom help messages. It takes the same selectors as `docs!()`.
+//! ```rust
+//! # use argtea::argtea_impl;
+//! # pub struct Foo;
+//! argtea_impl! {
+//!     {
+//!         /// Sets the output file path.
+//!         ("--output" | "-o", path) => {}
+//!
+//!         /// Displays this help message.
+//!         ("--help" | "-h") => {}
+//!     }
+//!     impl Foo {
+//!         pub const MAX_FLAG_WIDTH: usize = flag_width!();
+//!     }
+//! }
+//!
+//! assert_eq!(Foo::MAX_FLAG_WIDTH, "-o, --output <path>".len());
+//! ```
+//!
+//! Constants whose value isn't a macro call (eg `pub const DEFAULT_WIDTH: usize = 80;`), statics,
+//! and type aliases (eg `type Error = String;` in trait impls) are passed through as-is.
+//!
+//! ## `#[help_width(...)]`
+//! The flags can be preceded by a `#[help_width(...)]` attribute, which wraps the help messages
+//! generated by [`simple_format`], [`plain_format`], [`table_format`], and [`standard_help`] to
+//! the given width. This is the same as passing the `width` option to each of them; a `width`
+//! passed to a macro takes precedence. Other formatting macros are called unchanged.
+//! ```rust
+//! # use argtea::{argtea_impl, simple_format};
+//! # struct Foo;
+//! argtea_impl! {
+//!     #[help_width(30)]
+//!     {
+//!         /// Sets the output file path, which is `a.out` by default.
+//!         ("--output" | "-o", path) => {}
+//!     }
+//!     impl Foo {
+//!         const HELP: &'static str = simple_format!(docs!());
+//!     }
+//! }
+//!
+//! assert_eq!(Foo::HELP, "  --output, -o <path> \n    Sets the output file path,\n    which is `a.out` by\n    default.\n\n\n");
+//! ```
+//!
+//! ## `#[about = "..."]`
+//! The flags can also be preceded by an `#[about = "..."]` attribute (before or after
+//! `#[help_width(...)]`) with a description of the program. It is added as an `ABOUT` constant to
+//! the first `impl` block that isn't a trait impl (which is required), so help messages can place
+//! it above the flags:
+//! ```rust
+//! # use argtea::{argtea_impl, simple_format};
+//! # struct Foo;
+//! argtea_impl! {
+//!     #[about = "Compiles C files."]
+//!     {
+//!         /// Sets the output file path.
+//!         ("--output" | "-o", path) => {}
+//!     }
+//!     impl Foo {
+//!         const HELP: &'static str = simple_format!(Self::ABOUT "" "Options:" docs!());
+//!     }
+//! }
+//!
+//! assert_eq!(Foo::ABOUT, "Compiles C files.");
+//! assert!(Foo::HELP.starts_with("Compiles C files.\n\nOptions:\n"));
+//! ```
+//! `ABOUT` has the same visibility as functions without one (see [Visibility](#visibility)).
+//!
+//! ## Short flag clusters
+//! An argument like `-abc` is split into the short flags `-a`, `-b`, and `-c`. Splitting stops at
+//! the first character that can't be a short flag (`-` or `=`). The rest of the cluster after an
+//! `=` is a value, and the rest of the cluster starting at a `-` is split like an argument of its
+//! own. Either is taken by the previous flag if it takes a value, and is parsed like the next
+//! argument otherwise (like the value in `--verbose=x`). For example, `-v-a` is parsed as `-v -a`,
+//! `-v=x` as `-v x`, and `-v--` as `-v --` (so it ends flag parsing like `--` does with the
+//! `trailing: name` option).
+//!
+//! When a short flag that takes a value is reached, the rest of the cluster (after an optional
+//! `=`) becomes its value. This means that `-o file`, `-ofile`, and `-o=file` are always
+//! equivalent, and that `-vofile` is parsed as `-v -o file`. The value is never split, so flags
+//! like gcc's `-W` work as expected: `-Wall` is `-W all` and `-Wno-error` is `-W no-error`.
+//!
+//! [`normalize_args`] splits clusters and `--flag=value` arguments the same way, but without the
+//! flags (so every character is treated as a short flag). This is useful for inspecting or logging
+//! arguments without parsing them.
+//!
+//! ## Typed parameters
+//! A parameter can be given a type that implements [`FromStr`](core::str::FromStr), like
+//! `("--width", width: u32)`. Typed parameters are required: if the value is missing or can't be
+//! parsed, the function returns early with a [`ParseError`] (converted with `From`, so functions
+//! returning `Result<_, String>` work too).
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         /// Sets the width of the output.
+//!         ("--width" | "-w", width: u32) => {
+//!             width_ = width;
+//!         }
+//!         # (_file) => {}
+//!     }
+//!     impl Foo {
+//!       # const a: &[Flag] = docs!();
+//!         fn parse() -> Result<u32, String> {
+//!             let mut width_ = 80;
+//!
+//!             parse!(std::env::args().skip(1));
+//!
+//!             Ok(width_)
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! A parameter typed `Option<T>` (eg `("--count", count: Option<usize>)`) is `None` if the value
+//! is missing instead, and the value is parsed as a `T` otherwise. Untyped parameters are the same
+//! as `Option<String>` parameters. The type has to be written as `Option<T>`,
+//! `std::option::Option<T>`, or `core::option::Option<T>` (a type alias for an `Option` is parsed
+//! like any other type).
+//!
+//! ## Choices
+//! `("--color", color in ["auto", "always", "never"])` only accepts one of the listed values. Like
+//! typed parameters, the value is required, and an error listing the choices is returned
+//! otherwise. `color` is bound to the matching `&'static str`, and the choices are shown in help
+//! messages generated at runtime (eg `--color <auto|always|never>`).
+//!
+//! Adding `ci` after the choices (`color in ["auto", "always", "never"] ci`) makes the comparison
+//! ignore ASCII case, so `--color=Always` is accepted. `color` is still bound to the choice as it
+//! is written in the list (`"always"`).
+//!
+//! ## Default values and config files
+//! `parse!()` only runs the code of the flags that are actually passed, so variables can be
+//! initialized with defaults (eg from a config file) before calling it, and they are only
+//! overwritten by explicit command line flags. When a flag is passed multiple times, the last one
+//! wins.
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # struct Config { output: Option<String> }
+//! # struct Foo { output: String }
+//! argtea_impl! {
+//!     {
+//!         ("--output" | "-o", output) => {
+//!             output_ = output;
+//!         }
+//!         # (_file) => {}
+//!     }
+//!     impl Foo {
+//!       # const a: &[Flag] = docs!();
+//!         fn parse(config: Config) -> Foo {
+//!             // Defaults from the config file
+//!             let mut output_ = config.output.clone();
+//!
+//!             parse!(std::env::args().skip(1));
+//!
+//!             Foo { output: output_.unwrap_or_else(|| "a.out".to_owned()) }
+//!         }
+//!     }
+//! }
+//! ```
+//! Alternatively, the command line values can be collected separately and then combined with
+//! [`merge`], which makes the precedence explicit.
+//!
 //! ## `break`
 //! `break` can be used within a flag's code to immediately stop flag parsing. Additionally, the
 //! label `'stop_parsing` can be used if a nested break is required.
@@ -168,110 +520,1066 @@
 //! }
 //! ```
 //!
-//! ## `#[hidden]` and `#[fake]`
+//! `parse!(args, consumed: n)` assigns the number of arguments that were consumed (including the
+//! values of flags) to `n`, which can be used to find out where parsing stopped. `n` must be a
+//! `usize` variable that was declared before (eg `let n;`).
 //!
-//! Flags can optionally be annotated with `#[hidden]` or `#[fake]`. `#[hidden]` hides a flag from
-//! the documentation while `#[fake]` shows a flag in the documentation that doesn't really exist.
+//! After a `break`, the rest of `args` can be parsed separately (like above), but the rest of the
+//! argument that was being parsed is lost. `parse!(iter, remaining: name)` instead adds everything
+//! that wasn't parsed to `name` (with [`Extend`]), in this order:
+//! 1. The rest of a short flag cluster (eg `-c` if `-b` stopped parsing in `-abc`)
+//! 2. A value that the flag didn't take (eg `value` if `--stop=value` stopped parsing)
+//! 3. The rest of `iter`
 //!
-//! The following is an example where `#[fake]` and `#[hidden]` come in handy:
+//! So if `-b` stops parsing in `-abc val`, `name` receives `-c` and then `val`. If parsing doesn't
+//! stop early, nothing is added to `name`.
+//!
+//! Since `name` is only extended after parsing stops, a flag can add its own argument first. For
+//! example, subcommands (like `git -C dir commit -m x`) can stop parsing at the first positional
+//! argument and keep it for the subcommand's parser:
 //! ```rust
-//! # use argtea::{argtea_impl, Flag};
-//! # struct Foo;
-//! # argtea_impl! {{
-//! /// Enables all warnings
-//! #[fake]
-//! ("-Wall") => {}
+//! # use argtea::argtea_impl;
+//! # struct Foo {dir: Option<String>, command: Vec<String>}
+//! argtea_impl! {
+//!     {
+//!         ("-C", path) => { dir = path }
 //!
-//! // In this example, argtea interprets `-Wall` as `-W all`, so it will be matched to this flag.
-//! // This would be the case even if the above flag wasn't annotated with `#[fake]`
-//! //
-//! // However, the user may still wish to display `-Wall` as a separate flag in their documentation.
-//! ("-W" | "--warning", warning) => { /* ... */ }
+//!         (subcommand) => {
+//!             command.push(subcommand);
+//!             break;
+//!         }
+//!     }
+//!     impl Foo {
+//!         fn parse(args: Vec<String>) -> Foo {
+//!             let mut dir = None;
+//!             let mut command = Vec::new();
 //!
-//! // This flag is just here for error handling (when the user passes in an invalid flag)
-//! // Therefore, this shouldn't be shown in the documentation
-//! #[hidden]
-//! (invalid_flag) => { /* ... */ }
-//! # }
-//! # impl Foo {
-//! # const a: &[Flag] = docs!();
-//! # fn foo() {parse!(None.into_iter())}
-//! # }
-//! # }
+//!             parse!(args.into_iter(), remaining: command);
+//!
+//!             Self { dir, command }
+//!         }
+//!     }
+//! }
+//!
+//! let args = Foo::parse(["-C", "dir", "commit", "-m", "x"].map(String::from).to_vec());
+//! assert_eq!(args.dir.as_deref(), Some("dir"));
+//! assert_eq!(args.command, ["commit", "-m", "x"]);
 //! ```
 //!
-//! ## Formatting macros
-//! Formatting macros are just regular macros that take in the following pattern:
-//! ```text
-//! [
-//!     $({
-//!         doc: [
-//!             $( $doc:literal ),*
-//!         ],
-//!         flags: [
-//!             $( $flag:literal ),*
-//!         ],
-//!         params: [
-//!             $( $param:ident ),*
-//!         ]
-//!     }),*
-//! ]
+//! ## Trailing arguments
+//! `parse!(iter, trailing: name)` stops parsing at `--` and adds the remaining arguments to `name`
+//! as-is (with [`Extend`]), which replaces the `("--") => { break }` flag and the loop above. Eager
+//! flags don't match the arguments after `--` either.
+//! ```rust
+//! # use argtea::argtea_impl;
+//! # struct Foo {output: Option<String>, command: Vec<String>}
+//! argtea_impl! {
+//!     {
+//!         ("--output" | "-o", output_path) => { output = output_path }
+//!
+//!         (arg) => { return Err(format!("unexpected argument `{arg}`")) }
+//!     }
+//!     impl Foo {
+//!         fn parse() -> Result<Foo, String> {
+//!             let mut output = None;
+//!             let mut command = Vec::new();
+//!
+//!             parse!(std::env::args().skip(1), trailing: command);
+//!
+//!             Ok(Self { output, command })
+//!         }
+//!     }
+//! }
 //! ```
-//! `argtea` itself contains the formatting macro [`simple_format`]. This macro isn't special in
-//! any way, and the user can define their own macro that functions similarly as long as the above
-//! criterion is met.
 //!
-//! When the following is written in the [`argtea_impl`] macro, the first
-//! `docs!()` parameter is replaced with the above pattern. Then, the
-//! [`simple_format`] macro is called:
+//! ## Fallible iterators
+//! `parse!(iter, fallible)` parses an iterator over `Result<String, E>` (eg arguments that are read
+//! from a file with [`BufRead::lines`](std::io::BufRead::lines)). Parsing stops at the first
+//! `Err`, and after the arguments before it have been parsed, the error is returned from the
+//! enclosing function (converted with [`From`], like `?` does). `parse!(iter)` without `fallible`
+//! is unaffected.
 //! ```rust
-//! # use argtea::{simple_format, argtea_impl};
-//! # pub struct Foo;
+//! # use argtea::argtea_impl;
+//! # use std::io::{self, BufRead};
+//! # struct Foo;
 //! argtea_impl! {
-//!     { /* ... */ }
+//!     {
+//!         (file) => { files.push(file) }
+//!     }
 //!     impl Foo {
-//!         const HELP: &'static str = simple_format!("a" docs!() "b");
+//!         fn read_args(input: impl BufRead) -> io::Result<Vec<String>> {
+//!             let mut files = Vec::new();
+//!
+//!             parse!(input.lines(), fallible);
+//!
+//!             Ok(files)
+//!         }
 //!     }
 //! }
+//!
+//! assert_eq!(Foo::read_args("a\nb".as_bytes()).unwrap(), ["a", "b"]);
 //! ```
-
-mod docs;
-mod formatters;
-mod help;
-
-#[doc(hidden)]
-pub mod parse;
-
-pub use docs::Flag;
-pub use help::wrapping_format;
-
-#[cfg(test)]
-mod tests;
-
-#[macro_export]
+//!
+//! ## `parse_events!()`
+//! `parse_events!(iter, sink)` works exactly like `parse!(iter)`, but it additionally passes an
+//! [`Event`] to `sink` (a closure) right before a flag's code is run. This can be used to log,
+//! replay, or audit how the arguments were matched. `parse!()` itself is unaffected.
+//! ```rust
+//! # use argtea::{argtea_impl, Event, Flag};
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         ("--output" | "-o", output) => { /* ... */ }
+//!
+//!         (file) => { /* ... */ }
+//!     }
+//!     impl Foo {
+//!       # const a: &[Flag] = docs!();
+//!         fn parse() -> Vec<Event> {
+//!             let mut events = Vec::new();
+//!
+//!             parse_events!(std::env::args().skip(1), &mut |event| events.push(event));
+//!
+//!             events
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## Argument indices
+//! `parse!(iter, index: name)` declares `name: usize` in each flag's code, which is the index of
+//! the argument (in `iter`) that the flag came from. Every argument taken from `iter` counts once,
+//! so:
+//! - the flags in a cluster like `-abc` all have the same index
+//! - `--flag=value` is a single argument, but `--flag value` is two
+//!
+//! `parse_events!()` and `parse_fields!()` accept `index: name` (and the other options, like
+//! `trailing: name`) after their other arguments.
+//! ```rust
+//! # use argtea::argtea_impl;
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         ("--output" | "-o", output) => { /* ... */ }
+//!
+//!         (arg) => {
+//!             return Err(format!("unexpected argument {} (`{arg}`)", arg_index + 1));
+//!         }
+//!     }
+//!     impl Foo {
+//!         fn parse() -> Result<(), String> {
+//!             parse!(std::env::args().skip(1), index: arg_index);
+//!             Ok(())
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## Strict mode
+//! Unused variables in flags (usually parameters that a flag forgets to use) are allowed by
+//! default. `parse!(iter, strict)` doesn't allow them, so the `unused_variables` lint warns about
+//! them as usual. Like `index: name`, `strict` can also be passed to `parse_events!()` and
+//! `parse_fields!()`.
+//! ```rust,compile_fail
+//! #![deny(unused_variables)]
+//! # use argtea::argtea_impl;
+//! # struct Foo;
+//!
+//! argtea_impl! {
+//!     {
+//!         ("--output" | "-o", output_path) => {
+//!             // error: unused variable: `output_path`
+//!             output = Some("a.out".to_owned());
+//!         }
+//!         # (_file) => {}
+//!     }
+//!     impl Foo {
+//!         fn parse() -> Option<String> {
+//!             let mut output = None;
+//!             parse!(std::env::args().skip(1), strict);
+//!             output
+//!         }
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! ## Expression bodies
+//! Like a `match` arm, a flag's code can be an expression followed by a comma instead of a block
+//! (eg `("--verbose" | "-v") => verbose = true,`), and blocks can be followed by a comma too.
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # struct Foo;
+//! # argtea_impl! {{
+//! /// Enables verbose output.
+//! ("--verbose" | "-v") => verbose = true,
+//!
+//! /// Adds a file as an input.
+//! (file) => files.push(file),
+//! # }
+//! # impl Foo {
+//! # const a: &[Flag] = docs!();
+//! # fn foo() -> (bool, Vec<String>) {
+//! # let mut verbose = false;
+//! # let mut files = Vec::new();
+//! # parse!(None.into_iter());
+//! # (verbose, files)
+//! # }
+//! # }
+//! # }
+//! ```
+//!
+//! ## Guards
+//! Like a `match` arm, a flag can have a guard (eg `("--debug") if cfg!(debug_assertions)`). The
+//! guard is checked before the flag's parameters are taken, and if it is false, the argument is
+//! passed to the positional flag (eg `(file)`) as if the flag didn't exist. Guards don't affect the
+//! documentation.
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # struct Foo;
+//! # argtea_impl! {{
+//! /// Prints debug information (only in debug builds).
+//! ("--debug") if cfg!(debug_assertions) => {
+//!     debug = true;
+//! }
+//!
+//! (file) => {
+//!     return Err(format!("unexpected argument `{file}`"));
+//! }
+//! # }
+//! # impl Foo {
+//! # const a: &[Flag] = docs!();
+//! # fn foo() -> Result<bool, String> {
+//! # let mut debug = false;
+//! # parse!(None.into_iter());
+//! # Ok(debug)
+//! # }
+//! # }
+//! # }
+//! ```
+//!
+//! There can also be several positional flags with guards, which are tried in order. The guard of
+//! a positional flag can use its argument (as a `&str`), so arguments can be handled differently
+//! depending on their content. The last positional flag shouldn't have a guard, so that every
+//! argument is handled.
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # struct Foo;
+//! # argtea_impl! {{
+//! /// A C source file to compile.
+//! (src) if src.ends_with(".c") => {
+//!     sources.push(src);
+//! }
+//!
+//! /// An object file to link.
+//! (obj) if obj.ends_with(".o") => {
+//!     objects.push(obj);
+//! }
+//!
+//! (other) => {
+//!     return Err(format!("unexpected argument `{other}`"));
+//! }
+//! # }
+//! # impl Foo {
+//! # const a: &[Flag] = docs!();
+//! # fn foo() -> Result<(), String> {
+//! # let mut sources = Vec::new();
+//! # let mut objects = Vec::new();
+//! # parse!(["a.c", "b.o"].map(String::from).into_iter());
+//! # assert_eq!(sources, ["a.c"]);
+//! # assert_eq!(objects, ["b.o"]);
+//! # Ok(())
+//! # }
+//! # }
+//! # }
+//! # Foo::foo().unwrap();
+//! ```
+//!
+//! ## `#[hidden]` and `#[fake]`
+//!
+//! Flags can optionally be annotated with `#[hidden]` or `#[fake]`. `#[hidden]` hides a flag from
+//! the documentation while `#[fake]` shows a flag in the documentation that doesn't really exist.
+//!
+//! The following is an example where `#[fake]` and `#[hidden]` come in handy:
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # struct Foo;
+//! # argtea_impl! {{
+//! /// Enables all warnings
+//! #[fake]
+//! ("-Wall") => {}
+//!
+//! // In this example, argtea interprets `-Wall` as `-W all`, so it will be matched to this flag.
+//! // This would be the case even if the above flag wasn't annotated with `#[fake]`
+//! //
+//! // However, the user may still wish to display `-Wall` as a separate flag in their documentation.
+//! ("-W" | "--warning", warning) => { /* ... */ }
+//!
+//! // This flag is just here for error handling (when the user passes in an invalid flag)
+//! // Therefore, this shouldn't be shown in the documentation
+//! #[hidden]
+//! (invalid_flag) => { /* ... */ }
+//! # }
+//! # impl Foo {
+//! # const a: &[Flag] = docs!();
+//! # fn foo() {parse!(None.into_iter())}
+//! # }
+//! # }
+//! ```
+//!
+//! ## `#[section = "..."]`
+//!
+//! `docs!()` can be used more than once in a macro constant, and each one can select different
+//! flags:
+//! - `docs!()` selects every flag that isn't `#[hidden]`
+//! - `docs!(hidden)` selects the `#[hidden]` flags
+//! - `docs!(all)` selects every flag, including the `#[hidden]` ones (eg for a `--help-all`
+//!   listing). [`Flag::hidden`] tells them apart
+//! - `docs!(section = "...")` selects the flags that are annotated with the same
+//!   `#[section = "..."]` (and aren't `#[hidden]`)
+//!
+//! Sections don't affect parsing.
+//! ```rust
+//! # use argtea::{argtea_impl, simple_format};
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         /// Displays this help message.
+//!         #[section = "Common"]
+//!         ("--help" | "-h") => {}
+//!
+//!         /// Prints debug information.
+//!         #[section = "Advanced"]
+//!         ("--debug") => {}
+//!     }
+//!     impl Foo {
+//!         const HELP: &'static str = simple_format!(
+//!             "Common options:"
+//!             docs!(section = "Common")
+//!             "Advanced options:"
+//!             docs!(section = "Advanced")
+//!         );
+//!     }
+//! }
+//!
+//! assert!(Foo::HELP.starts_with("Common options:\n  --help, -h"));
+//! ```
+//!
+//! ## `#[example("...")]`
+//! Flags can be annotated with any number of `#[example("...")]` (or `#[example = "..."]`)
+//! attributes, which contain example command lines. [`wrapping_format`] and the other runtime
+//! formatters display them under the flag's documentation, and [`man::render`](crate::man) lists
+//! them in an `EXAMPLES` section. Formatting macros receive them as the `examples` field (see
+//! "Formatting macros" below).
+//! ```rust
+//! # use argtea::{argtea_impl, wrapping_format, Flag};
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         /// Sets the output file path.
+//!         #[example("myprog -o out.txt input.c")]
+//!         ("--output" | "-o", path) => {}
+//!     }
+//!     impl Foo {
+//!         const FLAGS: &'static [Flag] = docs!();
+//!     }
+//! }
+//!
+//! let mut help = String::new();
+//! wrapping_format(&mut help, Foo::FLAGS);
+//!
+//! assert!(help.contains("    Example:\n      myprog -o out.txt input.c\n"));
+//! ```
+//!
+//! ## `#[eager]`
+//! Flags annotated with `#[eager]` run before any other arguments are parsed, so that eg
+//! `myprog --nonsense --help` displays the help message instead of an error about `--nonsense`.
+//! Eager flags are found like other flags (including in clusters like `-vh`), take their values
+//! the same way (`--config a.toml` or `--config=a.toml`), and don't run again during normal
+//! parsing.
+//!
+//! Only the arguments before the first `--` are scanned for eager flags. Eager flags after it run
+//! during normal parsing, like other flags (unless a `("--")` flag stops parsing). The arguments
+//! are split exactly like during normal parsing, so the other flags take their values without
+//! running (eg `--output --help` and `-ohelp` don't run `--help` or `-h` if `--output` takes a
+//! value).
+//!
+//! A `break` in an eager flag's code only stops the scan for eager flags, so the arguments are
+//! still parsed normally afterwards. To stop parsing after an eager flag (eg after printing the
+//! help message), return from the function instead.
+//!
+//! NOTE: if a function has any `#[eager]` flags, `parse!()` collects all of the arguments before
+//! parsing them, so the iterator passed to it is always fully consumed (even after `break`).
+//! ```rust
+//! # use argtea::{argtea_impl, print_help, simple_format, Flag, PrintHelpOptions};
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         /// Displays this help message.
+//!         #[eager]
+//!         ("--help" | "-h") => {
+//!             print_help(Self::HELP, PrintHelpOptions::default());
+//!         }
+//!
+//!         (file) => {
+//!             if file.starts_with('-') {
+//!                 return Err(format!("invalid flag `{file}`"));
+//!             }
+//!         }
+//!     }
+//!     impl Foo {
+//!         const HELP: &'static str = simple_format!(docs!());
+//!
+//!         fn parse() -> Result<(), String> {
+//!             parse!(std::env::args().skip(1));
+//!             Ok(())
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## `#[strict_value]`
+//! By default, a flag's parameters take the following arguments as-is, so `--output --verbose`
+//! sets the output to `--verbose`. When a flag is annotated with `#[strict_value]`, a following
+//! argument that starts with `-` (other than `-` itself) is not taken; the parameter is `None`
+//! instead, and the argument is parsed as a flag. Values in the same argument (eg
+//! `--output=--verbose` or `-o-file`) are always accepted.
+//!
+//! The positional flag (eg `(file)`) accepts any argument that isn't matched by the other flags by
+//! default. When it is annotated with `#[strict_value]`, an argument that starts with `-` (other
+//! than `-` itself) makes the function return a [`ParseError::UnknownFlag`] instead (which
+//! suggests the most similar flag for likely typos, eg `--verbose` for `--verbos`), so the
+//! function must return a `Result` whose error type implements `From<ParseError>` (eg `String`).
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # struct Foo;
+//! # argtea_impl! {{
+//! /// Sets the output file path.
+//! #[strict_value]
+//! (flag @ "--output" | "-o", output) => {
+//!     let Some(output) = output else {
+//!         return Err(format!("Expected path after `{flag}`"));
+//!     };
+//! }
+//! # (_file) => {}
+//! # }
+//! # impl Foo {
+//! # const a: &[Flag] = docs!();
+//! # fn foo() -> Result<(), String> {parse!(None.into_iter()); Ok(())}
+//! # }
+//! # }
+//! ```
+//!
+//! ## `#[optional_value]`
+//! When a flag is annotated with `#[optional_value]`, its parameters only take values from the
+//! same argument (eg `--color=never` or `-cnever`), so `--color never` passes `never` as a separate
+//! argument. The parameters are bound to a [`FlagState`]: `Set` if the flag has no value and
+//! `SetWith(...)` otherwise. Variables that are initialized with `FlagState::Absent` (the default)
+//! can then tell apart all three cases. Typed parameters and choices are converted as usual, so
+//! `("--jobs", jobs: u32)` is bound to a `FlagState<u32>`.
+//! ```rust
+//! # use argtea::{argtea_impl, Flag, FlagState};
+//! # struct Foo;
+//! # argtea_impl! {{
+//! /// Colors the output. `--color` and `--color=always` are the same.
+//! #[optional_value]
+//! ("--color", when in ["auto", "always", "never"]) => {
+//!     color = when;
+//! }
+//! # (_file) => {}
+//! # }
+//! # impl Foo {
+//! # const a: &[Flag] = docs!();
+//! # fn foo() -> Result<(), argtea::ParseError> {
+//! # let mut color = FlagState::Absent;
+//! # parse!(None.into_iter());
+//! # let _ = color;
+//! # Ok(())
+//! # }
+//! # }
+//! # }
+//! ```
+//!
+//! ## `#[key_value]`
+//! Only flags that start with `--` are split at `=` (eg `--output=file`), so the positional flag
+//! always gets arguments like `file=name` as-is. When the positional flag is annotated with
+//! `#[key_value]`, its argument is split at the first `=` instead (like `make VAR=value`), and it
+//! is bound to a `(String, Option<String>)` (the value is `None` if there isn't an `=`).
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # use std::collections::HashMap;
+//! # struct Foo;
+//! # argtea_impl! {{
+//! #[key_value]
+//! (variable) => {
+//!     match variable {
+//!         (name, Some(value)) => { variables.insert(name, value); }
+//!         (target, None) => targets.push(target),
+//!     }
+//! }
+//! # }
+//! # impl Foo {
+//! # const a: &[Flag] = docs!();
+//! # fn foo() {
+//! # let mut variables = HashMap::new();
+//! # let mut targets = Vec::new();
+//! # parse!(None.into_iter());
+//! # let _ = (variables, targets);
+//! # }
+//! # }
+//! # }
+//! ```
+//!
+//! ## Doc attributes
+//! Besides doc comments, flags can be documented with doc attributes whose value is a constant
+//! expression, like `#[doc = include_str!("docs/output.md")]` for long descriptions that are kept
+//! in separate files. Each line of the text is handled like a line of a doc comment (eg blank lines
+//! separate paragraphs) by the help messages generated at runtime, while formatting macros like
+//! [`simple_format`] insert the text as-is.
+//!
+//! ## `{flag}` in docs
+//! `{flag}` in a flag's documentation is replaced with the flag's first alias by `docs!()` and by
+//! formatting macros like [`simple_format`], so the documentation stays correct when the flag is
+//! renamed. `{{flag}}` is written as a literal `{flag}`, and lines without either are left as-is.
+//! ```rust
+//! # use argtea::{argtea_impl, simple_format, Flag};
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         /// Adds an include path. `{flag}` can be used multiple times.
+//!         ///
+//!         /// Paths can't contain `{{flag}}`.
+//!         ("--include" | "-I", path) => {}
+//!         # (_file) => {}
+//!     }
+//!     impl Foo {
+//!         const FLAGS: &'static [Flag] = docs!();
+//!         const HELP: &'static str = simple_format!(docs!());
+//!       # fn foo() {parse!(None.into_iter());}
+//!     }
+//! }
+//!
+//! assert_eq!(Foo::FLAGS[0].doc[0], " Adds an include path. `--include` can be used multiple times.");
+//! assert_eq!(Foo::FLAGS[0].doc[2], " Paths can't contain `{flag}`.");
+//! assert!(Foo::HELP.contains("`--include` can be used multiple times."));
+//! ```
+//!
+//! ## `#[cfg(...)]`
+//! Flags can be conditionally compiled with `#[cfg(...)]`. This applies to both the flag's code
+//! and its documentation, so a flag that isn't compiled doesn't appear in `docs!()` or in help
+//! messages.
+//! ```rust
+//! # use argtea::{argtea_impl, Flag};
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         /// Generates a man page.
+//!         #[cfg(feature = "man")]
+//!         ("--man") => { /* ... */ }
+//!         # (_file) => {}
+//!     }
+//!     impl Foo {
+//!         const FLAGS: &'static [Flag] = docs!();
+//!       # fn foo() {parse!(None.into_iter());}
+//!     }
+//! }
+//! ```
+//!
+//! `#[cfg_attr(predicate, cfg(...))]` is also applied to both (eg
+//! `#[cfg_attr(windows, cfg(feature = "wsl"))]`), but other attributes inside of `#[cfg_attr(...)]`
+//! only apply to the flag's code.
+//!
+//! NOTE: formatting macros are expanded once with and once without each conditionally compiled
+//! flag, so using many `#[cfg(...)]` attributes may slow down compilation.
+//!
+//! ## Other attributes
+//! Lint attributes (`#[allow(...)]`, `#[expect(...)]`, `#[warn(...)]`, `#[deny(...)]`, and
+//! `#[forbid(...)]`) and `#[cfg_attr(...)]` are placed on the flag's generated code and are removed
+//! from the documentation (any other attribute is a compile error):
+//! ```rust
+//! # use argtea::argtea_impl;
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         /// Sets the output file path (ignored for now).
+//!         #[allow(unused_variables)]
+//!         ("--output", path) => {}
+//!         # (_file) => {}
+//!     }
+//!     impl Foo {
+//!       # fn foo() {parse!(None.into_iter());}
+//!     }
+//! }
+//! ```
+//!
+//! Since parameters are bound to variables with the same names, lint attributes like
+//! `#[deny(unused_variables)]` can be used to catch parameters that a flag forgets to use
+//! (unused variables in flags are allowed by default, see "Strict mode" above):
+//! ```rust,compile_fail
+//! # use argtea::argtea_impl;
+//! # struct Foo;
+//! argtea_impl! {
+//!     {
+//!         /// Sets the output file path.
+//!         #[deny(unused_variables)]
+//!         ("--output", output_path) => {
+//!             // error: unused variable: `output_path`
+//!             output = Some("a.out".to_owned());
+//!         }
+//!         # (_file) => {}
+//!     }
+//!     impl Foo {
+//!       # fn foo() {
+//!       # let mut output = None;
+//!       # parse!(None.into_iter());
+//!       # let _ = output;
+//!       # }
+//!     }
+//! }
+//! ```
+//!
+//! | Attribute                                                                 | Documentation | Parsing |
+//! |---------------------------------------------------------------------------|---------------|---------|
+//! | `#[hidden]`, `#[section = "..."]`, `#[example("...")]`                    | yes           | no      |
+//! | `#[fake]`, `#[cfg(...)]`                                                  | yes           | yes     |
+//! | `#[eager]`, `#[strict_value]`, `#[optional_value]`, `#[key_value]`, lints | no            | yes     |
+//!
+//! ## Flag sets
+//! Groups of related flags can be declared in other modules with [`argtea_flags`], and then added
+//! to the flags with `use_flags!(path::to::flag_set(variables...));`. The `use_flags!(...)`
+//! statements go at the start of the flags, and the flag sets are added in the same order
+//! (before the other flags), so both parsing and the documentation use the combined flags.
+//!
+//! ## Runtime parsing
+//! For simple or dynamic command line interfaces (eg when plugins register their own flags),
+//! [`parse_dynamic`] parses arguments using flags that are defined at runtime as [`FlagSpec`]s.
+//! It follows the same rules as `parse!()` and returns the matched flags and values.
+//!
+//! ## Formatting macros
+//! Formatting macros are just regular macros that take in the following pattern:
+//! ```text
+//! [
+//!     $({
+//!         doc: [
+//!             $( $doc:expr ),*
+//!         ],
+//!         flags: [
+//!             $( $flag:literal ),*
+//!         ],
+//!         params: [
+//!             $( $param:ident ),*
+//!         ],
+//!         examples: [
+//!             $( $example:literal ),*
+//!         ]
+//!         $(, $($rest:tt)*)?
+//!     }),*
+//! ]
+//! ```
+//! `argtea` itself contains the formatting macros [`simple_format`], [`plain_format`],
+//! [`table_format`], and [`standard_help`]. Apart from receiving `#[help_width(...)]`'s `width`
+//! option, these macros aren't special in any way, and the user can define their own
+//! macro that functions similarly as long as the above criterion is met.
+//!
+//! This pattern is part of `argtea`'s public interface, and it only changes in semver-incompatible
+//! releases, with one exception: new fields may be added after `params` in any release. Formatting
+//! macros must accept these with `$(, $($rest:tt)*)?` (as above), and they may ignore them.
+//! [`assert_formatter`] checks that a formatting macro accepts the current pattern, including
+//! added fields.
+//!
+//! Formatting macros written for earlier versions, which end each group with `params: [...]`,
+//! need to add the `$(, $($rest:tt)*)?` line after `params`. No other changes are required.
+//!
+//! Doc comments are passed as literals, so formatting macros that match `$doc:literal` still work,
+//! but they don't accept flags with doc attributes like `#[doc = include_str!("...")]` (which are
+//! passed as expressions).
+//!
+//! When the following is written in the [`argtea_impl`] macro, each
+//! `docs!()` parameter is replaced with the above pattern. Then, the
+//! [`simple_format`] macro is called:
+//! ```rust
+//! # use argtea::{simple_format, argtea_impl};
+//! # pub struct Foo;
+//! argtea_impl! {
+//!     { /* ... */ }
+//!     impl Foo {
+//!         const HELP: &'static str = simple_format!("a" docs!() "b");
+//!     }
+//! }
+//! ```
+//! The other arguments are passed through unchanged, so this also works with
+//! [`simple_format`]'s options:
+//! ```rust
+//! # use argtea::{simple_format, argtea_impl};
+//! # pub struct Foo;
+//! argtea_impl! {
+//!     { /* ... */ }
+//!     impl Foo {
+//!         const HELP: &'static str = simple_format!(
+//!             { flag_indent: "    ", doc_indent: "        ", doc_prefix: "•" }
+//!             "a"
+//!             docs!()
+//!             "b"
+//!         );
+//!     }
+//! }
+//! ```
+//!
+//! ## Testing
+//! The [`testing`] module has helpers for testing parsers and help messages: [`testing::args`],
+//! [`assert_parse!`], and [`assert_help_snapshot!`].
+//!
+//! ## Cargo features
+//! - `man`: enables the [`man`](crate::man) module for generating man pages.
+
+mod docs;
+mod dynamic;
+mod error;
+mod flag_sets;
+
+pub mod completions;
+pub mod help;
+pub mod testing;
+pub mod value;
+
+#[cfg(feature = "man")]
+pub mod man;
+
+#[doc(hidden)]
+pub mod formatters;
+#[doc(hidden)]
+pub mod parse;
+
+pub use docs::{Flag, Parser};
+pub use dynamic::{parse_dynamic, FlagSpec, Match, Matches};
+pub use error::ParseError;
+pub use help::{
+    color_format, color_format_auto, color_format_with, print_help, render_flags, wrapping_format,
+    wrapping_format_with, AliasStyle, HelpOptions, HelpRenderer, ParamStyle, Placement,
+    PrintHelpOptions, SortOrder, Stream, Undocumented,
+};
+pub use parse::{normalize_args, Event, FlagState};
+pub use value::{expect_parsed, expect_value, merge};
+
+#[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
+mod tests;
+
+#[macro_export]
 macro_rules! argtea_impl {
     {
+        #![default_visibility(pub)]
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@attrs [pub] [] [] $($rem)*}
+    };
+    {
+        #![default_visibility(private)]
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@attrs [private] [] [] $($rem)*}
+    };
+    {
+        #![default_visibility $($args:tt)*]
+        $($rem:tt)*
+    } => {
+        compile_error!("Expected `#![default_visibility(pub)]` or `#![default_visibility(private)]`");
+    };
+
+    // `#[help_width(...)]` and `#[about = "..."]` can be written in any order before the flags
+    {
+        @attrs $default_vis:tt $help_width:tt $about:tt
+        #[help_width($new_help_width:literal)]
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@attrs $default_vis [$new_help_width] $about $($rem)*}
+    };
+    {
+        @attrs $default_vis:tt $help_width:tt $about:tt
+        #[about = $new_about:literal]
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@attrs $default_vis $help_width [$new_about] $($rem)*}
+    };
+    {
+        @attrs $default_vis:tt $help_width:tt $about:tt
+        {use_flags! $($flags:tt)*}
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@use_flags [$default_vis $help_width $about] {} {use_flags! $($flags)*} $($rem)*}
+    };
+    {
+        @attrs $default_vis:tt $help_width:tt $about:tt
+        $flags:tt
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@impl $default_vis $help_width $about $flags $($rem)*}
+    };
+
+    // Each `use_flags!(...)` at the start of the flags calls the flag set's macro (see
+    // `argtea_flags!`), which calls this back with the set's flags added to `$done`
+    {
+        @use_flags $state:tt $done:tt
+        {use_flags!($($set:ident)::+ $(($($shared:ident),* $(,)?))?); $($flags:tt)*}
+        $($rem:tt)*
+    } => {
+        $($set)::+!{@argtea_use_flags [$($($shared),*)?] $state $done {$($flags)*} $($rem)*}
+    };
+    {
+        @use_flags $state:tt $done:tt
+        {use_flags! $($flags:tt)*}
+        $($rem:tt)*
+    } => {
+        compile_error!("Expected `use_flags!(path::to::flag_set(variables...));`");
+    };
+    {
+        @use_flags [$default_vis:tt $help_width:tt $about:tt] {$($done:tt)*}
+        {$($flags:tt)*}
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@impl $default_vis $help_width $about {$($done)* $($flags)*} $($rem)*}
+    };
+
+    {
+        @impl $default_vis:tt $help_width:tt $about:tt
+        $flags:tt
+        $(#[$struct_attr:meta])*
+        $struct_vis:vis struct $struct_ty:ident {
+            $(
+                $(#[$field_attr:meta])*
+                $field_vis:vis $field:ident: $field_ty:ty $(= $init:expr)?
+            ),* $(,)?
+        }
+        $($impls:tt)+
+    } => {
+        $(#[$struct_attr])*
+        $struct_vis struct $struct_ty {
+            $(
+                $(#[$field_attr])*
+                $field_vis $field: $field_ty,
+            )*
+        }
+
+        $crate::argtea_impl!{@impls $default_vis $help_width $about $flags {$($field: $field_ty $(= $init)?),*} $($impls)+}
+    };
+    {
+        @impl $default_vis:tt $help_width:tt $about:tt
         $flags:tt
-        impl $ty:ident {$($items:tt)*}
+        $($impls:tt)+
+    } => {
+        $crate::argtea_impl!{@impls $default_vis $help_width $about $flags {} $($impls)+}
+    };
+
+    {
+        @impls $default_vis:tt $help_width:tt [$about:literal] $flags:tt $fields:tt
+    } => {
+        compile_error!("`#[about = \"...\"]` needs an `impl` block that isn't a trait impl to add the `ABOUT` constant to");
+    };
+    {
+        @impls $default_vis:tt $help_width:tt $about:tt $flags:tt $fields:tt
+    } => {};
+    {
+        @impls $default_vis:tt $help_width:tt $about:tt $flags:tt $fields:tt
+        impl $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@header $default_vis $help_width $about $flags $fields $default_vis {} $($rem)*}
+    };
+    {
+        @impls $default_vis:tt $help_width:tt $about:tt $flags:tt $fields:tt
+        $($rem:tt)*
+    } => {
+        compile_error!("Expected an `impl` block after the flags (and the optional struct)");
+    };
+
+    // The header of an `impl` block (eg `<T: Backend> Arguments<T> where ...`) is copied until its
+    // body is found. Functions in trait impls (headers with `for` before the `where` clause) can't
+    // have a visibility, so `$fn_vis` becomes `[]`. `for<...>` (a higher-ranked trait bound)
+    // doesn't make the impl a trait impl.
+    //
+    // The `ABOUT` constant from `#[about = "..."]` is added to the first impl block that isn't a
+    // trait impl.
+    {
+        @header $default_vis:tt $help_width:tt [$about:literal] $flags:tt $fields:tt [$fn_vis:ident] {$($header:tt)*}
+        {$($items:tt)*}
+        $($impls:tt)*
     } => {
+        impl $($header)* {
+            $crate::_about_constant!{[$fn_vis] $about}
+            $crate::_parse_items!{$flags $fields $help_width [$fn_vis] {} $($items)*}
+        }
 
-        impl $ty {
-            $crate::_parse_items!{$flags {} $($items)*}
+        $crate::argtea_impl!{@impls $default_vis $help_width [] $flags $fields $($impls)*}
+    };
+    {
+        @header $default_vis:tt $help_width:tt $about:tt $flags:tt $fields:tt $fn_vis:tt {$($header:tt)*}
+        {$($items:tt)*}
+        $($impls:tt)*
+    } => {
+        impl $($header)* {
+            $crate::_parse_items!{$flags $fields $help_width $fn_vis {} $($items)*}
         }
+
+        $crate::argtea_impl!{@impls $default_vis $help_width $about $flags $fields $($impls)*}
+    };
+    {
+        @header $default_vis:tt $help_width:tt $about:tt $flags:tt $fields:tt $fn_vis:tt {$($header:tt)*}
+        where
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@where_clause $default_vis $help_width $about $flags $fields $fn_vis {$($header)* where} $($rem)*}
+    };
+    {
+        @header $default_vis:tt $help_width:tt $about:tt $flags:tt $fields:tt $fn_vis:tt {$($header:tt)*}
+        for <
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@header $default_vis $help_width $about $flags $fields $fn_vis {$($header)* for <} $($rem)*}
+    };
+    {
+        @header $default_vis:tt $help_width:tt $about:tt $flags:tt $fields:tt $fn_vis:tt {$($header:tt)*}
+        for
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@header $default_vis $help_width $about $flags $fields [] {$($header)* for} $($rem)*}
+    };
+    {
+        @header $default_vis:tt $help_width:tt $about:tt $flags:tt $fields:tt $fn_vis:tt {$($header:tt)*}
+        $token:tt
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@header $default_vis $help_width $about $flags $fields $fn_vis {$($header)* $token} $($rem)*}
+    };
+
+    // The `where` clause is copied as-is, since its `for`s are higher-ranked trait bounds
+    {
+        @where_clause $default_vis:tt $help_width:tt $about:tt $flags:tt $fields:tt $fn_vis:tt {$($header:tt)*}
+        {$($items:tt)*}
+        $($impls:tt)*
+    } => {
+        $crate::argtea_impl!{@header $default_vis $help_width $about $flags $fields $fn_vis {$($header)*} {$($items)*} $($impls)*}
+    };
+    {
+        @where_clause $default_vis:tt $help_width:tt $about:tt $flags:tt $fields:tt $fn_vis:tt {$($header:tt)*}
+        $token:tt
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@where_clause $default_vis $help_width $about $flags $fields $fn_vis {$($header)* $token} $($rem)*}
+    };
+
+    {
+        {use_flags! $($flags:tt)*}
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@use_flags [[pub] [] []] {} {use_flags! $($flags)*} $($rem)*}
+    };
+    {
+        {$($flags:tt)*}
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@impl [pub] [] [] {$($flags)*} $($rem)*}
+    };
+    {
+        $($rem:tt)*
+    } => {
+        $crate::argtea_impl!{@attrs [pub] [] [] $($rem)*}
+    };
+}
+
+/// Helper macro; expands to the `ABOUT` constant for `#[about = "..."]` with the default
+/// visibility of functions.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _about_constant {
+    ([pub] $about:literal) => {
+        /// The description of the program from `#[about = "..."]`.
+        pub const ABOUT: &'static str = $about;
+    };
+    ([private] $about:literal) => {
+        /// The description of the program from `#[about = "..."]`.
+        #[allow(dead_code)]
+        const ABOUT: &'static str = $about;
     };
 }
 
+/// Helper macro; expands the items of an `impl` block given to [`argtea_impl`].
+///
+/// Unsupported items and flags are reported with `compile_error!()` (see also the cases in
+/// `src/tests/ui`):
+/// ```rust,compile_fail
+/// # struct Foo;
+/// argtea::argtea_impl! {
+///     {
+///         // error: Expected a block or an expression after `("-v") =>`
+///         ("-v") => ,
+///         (_file) => {}
+///     }
+///     impl Foo {
+///         fn parse() {
+///             parse!(std::env::args());
+///         }
+///     }
+/// }
+/// ```
+/// ```rust,compile_fail
+/// # struct Foo;
+/// argtea::argtea_impl! {
+///     {
+///         // error: Expected a flag like `("--flag" | "-f", param) => { ... }`, found `"-v"`
+///         "-v" => {}
+///         (_file) => {}
+///     }
+///     impl Foo {
+///         fn parse() {
+///             parse!(std::env::args());
+///         }
+///     }
+/// }
+/// ```
+/// ```rust,compile_fail
+/// # struct Foo;
+/// argtea::argtea_impl! {
+///     {}
+///     impl Foo {
+///         // error: Expected a function or a constant in argtea `impl` block, found `struct`
+///         struct Bar;
+///     }
+/// }
+/// ```
+/// ```rust,compile_fail
+/// # struct Foo;
+/// argtea::argtea_impl! {
+///     {}
+///     impl Foo {
+///         // error: Expected a function body, argtea functions can't be declared without one
+///         fn parse();
+///     }
+/// }
+/// ```
+/// ```rust,compile_fail
+/// # struct Foo;
+/// argtea::argtea_impl! {
+///     {
+///         (_file) => {}
+///     }
+///     impl Foo {
+///         fn parse() {
+///             // error: Invalid options `bogus: 1`, expected ...
+///             parse!(std::env::args(), bogus: 1);
+///         }
+///     }
+/// }
+/// ```
 #[doc(hidden)]
 #[macro_export]
 macro_rules! _parse_items {
     {
-        $flags:tt {$($prev:tt)*}
+        $flags:tt $fields:tt $help_width:tt $fn_vis:tt {$($prev:tt)*}
     } => {
         $($prev)*
     };
 
     {
-        $flags:tt {$($prev:tt)*}
+        $flags:tt $fields:tt $help_width:tt $fn_vis:tt {$($prev:tt)*}
 
         $(#[$attr:meta])*
         $vis:vis const $constant_name:ident: $constant_type:ty = $($macro:ident)::+ ! $mac_args:tt;
@@ -279,32 +1587,201 @@ macro_rules! _parse_items {
         $($rem:tt)*
     } => {
         $crate::_parse_items!{
-            $flags {
+            $flags $fields $help_width $fn_vis {
                 $($prev)*
                 $(#[$attr])*
-                $vis const $constant_name: $constant_type = $crate::_filter_hidden_flags!($flags _constant_expression!($($macro)::+ ! $mac_args));
+                $vis const $constant_name: $constant_type = $crate::_normalize_doc_flags!($flags _constant_expression!($help_width $($macro)::+ ! $mac_args));
             }
             $($rem)*
         }
     };
 
+    // Other items are passed through as-is
     {
-        $flags:tt {$($prev:tt)*}
+        $flags:tt $fields:tt $help_width:tt $fn_vis:tt {$($prev:tt)*}
 
         $(#[$attr:meta])*
-        $vis:vis $(extern $abi:literal)? fn $fn_name:ident $args:tt $(-> $ret_ty:ty)? {$($body:tt)*}
+        $vis:vis const $constant_name:ident: $constant_type:ty = $value:expr;
 
         $($rem:tt)*
     } => {
-        $crate::_parse_items! {
-            $flags {
+        $crate::_parse_items!{
+            $flags $fields $help_width $fn_vis {
+                $($prev)*
+                $(#[$attr])*
+                $vis const $constant_name: $constant_type = $value;
+            }
+            $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $help_width:tt $fn_vis:tt {$($prev:tt)*}
+
+        $(#[$attr:meta])*
+        $vis:vis static $static_name:ident: $static_type:ty = $value:expr;
+
+        $($rem:tt)*
+    } => {
+        $crate::_parse_items!{
+            $flags $fields $help_width $fn_vis {
+                $($prev)*
+                $(#[$attr])*
+                $vis static $static_name: $static_type = $value;
+            }
+            $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $help_width:tt $fn_vis:tt {$($prev:tt)*}
+
+        $(#[$attr:meta])*
+        $vis:vis static mut $static_name:ident: $static_type:ty = $value:expr;
+
+        $($rem:tt)*
+    } => {
+        $crate::_parse_items!{
+            $flags $fields $help_width $fn_vis {
+                $($prev)*
+                $(#[$attr])*
+                $vis static mut $static_name: $static_type = $value;
+            }
+            $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $help_width:tt $fn_vis:tt {$($prev:tt)*}
+
+        $(#[$attr:meta])*
+        $vis:vis type $type_name:ident = $ty:ty;
+
+        $($rem:tt)*
+    } => {
+        $crate::_parse_items!{
+            $flags $fields $help_width $fn_vis {
                 $($prev)*
                 $(#[$attr])*
-                pub $(extern $abi)? fn $fn_name $args $(-> $ret_ty)? {
+                $vis type $type_name = $ty;
+            }
+            $($rem)*
+        }
+    };
+
+    // Functions keep their visibility (except in trait impls, where `$fn_vis` is `[]`), and
+    // functions without one are `pub` unless `#![default_visibility(private)]` is used
+    {
+        $flags:tt $fields:tt $help_width:tt [] {$($prev:tt)*}
+
+        $(#[$attr:meta])*
+        $vis:vis $first:ident $($rem:tt)*
+    } => {
+        $crate::_parse_items! {
+            @qualifiers $flags $fields $help_width [] {$($prev)*}
+            {$(#[$attr])*}
+            $first $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $help_width:tt $fn_vis:tt {$($prev:tt)*}
+
+        $(#[$attr:meta])*
+        pub $(($($restriction:tt)*))? $first:ident $($rem:tt)*
+    } => {
+        $crate::_parse_items! {
+            @qualifiers $flags $fields $help_width $fn_vis {$($prev)*}
+            {$(#[$attr])* pub $(($($restriction)*))?}
+            $first $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $help_width:tt [pub] {$($prev:tt)*}
+
+        $(#[$attr:meta])*
+        $first:ident $($rem:tt)*
+    } => {
+        $crate::_parse_items! {
+            @qualifiers $flags $fields $help_width [pub] {$($prev)*}
+            {$(#[$attr])* pub}
+            $first $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $help_width:tt [private] {$($prev:tt)*}
+
+        $(#[$attr:meta])*
+        $first:ident $($rem:tt)*
+    } => {
+        $crate::_parse_items! {
+            @qualifiers $flags $fields $help_width [private] {$($prev)*}
+            {$(#[$attr])*}
+            $first $($rem)*
+        }
+    };
+
+    // Function qualifiers (eg `const`, `async`, `unsafe`, and `extern "C"`) are copied until `fn`
+    // is found
+    {
+        @qualifiers $flags:tt $fields:tt $help_width:tt $fn_vis:tt $prev:tt {$($signature:tt)*}
+        fn $fn_name:ident $($rem:tt)*
+    } => {
+        $crate::_parse_items! {
+            @fn $flags $fields $help_width $fn_vis $prev {$($signature)* fn $fn_name}
+            $($rem)*
+        }
+    };
+    {
+        @qualifiers $flags:tt $fields:tt $help_width:tt $fn_vis:tt $prev:tt {$($signature:tt)*}
+        extern $abi:literal $($rem:tt)*
+    } => {
+        $crate::_parse_items! {
+            @qualifiers $flags $fields $help_width $fn_vis $prev {$($signature)* extern $abi}
+            $($rem)*
+        }
+    };
+    {
+        @qualifiers $flags:tt $fields:tt $help_width:tt $fn_vis:tt $prev:tt {$($signature:tt)*}
+        $qualifier:ident $($rem:tt)*
+    } => {
+        $crate::_parse_items! {
+            @qualifier $qualifier
+            @qualifiers $flags $fields $help_width $fn_vis $prev {$($signature)* $qualifier}
+            $($rem)*
+        }
+    };
+    {
+        @qualifiers $flags:tt $fields:tt $help_width:tt $fn_vis:tt $prev:tt $signature:tt
+        $($rem:tt)*
+    } => {
+        compile_error!("Expected a function or a constant in argtea `impl` block");
+    };
+
+    {@qualifier const @qualifiers $($rem:tt)*} => {$crate::_parse_items!{@qualifiers $($rem)*}};
+    {@qualifier async @qualifiers $($rem:tt)*} => {$crate::_parse_items!{@qualifiers $($rem)*}};
+    {@qualifier unsafe @qualifiers $($rem:tt)*} => {$crate::_parse_items!{@qualifiers $($rem)*}};
+    {@qualifier extern @qualifiers $($rem:tt)*} => {$crate::_parse_items!{@qualifiers $($rem)*}};
+    {@qualifier $qualifier:ident @qualifiers $($rem:tt)*} => {
+        compile_error!(::core::concat!(
+            "Expected a function or a constant in argtea `impl` block, found `",
+            ::core::stringify!($qualifier),
+            "`"
+        ));
+    };
+
+    // The signature (generics, parameters, return type, and `where` clause) is copied until the
+    // body is found
+    {
+        @fn $flags:tt $fields:tt $help_width:tt $fn_vis:tt {$($prev:tt)*} {$($signature:tt)*}
+        {$($body:tt)*}
+
+        $($rem:tt)*
+    } => {
+        $crate::_parse_items! {
+            $flags $fields $help_width $fn_vis {
+                $($prev)*
+                $($signature)* {
                     $crate::_filter_fake_flags!{
                         $flags
                         _scan_body!(
-                            {}
+                            $fields [] {}
                             $($body)*
                         )
                     }
@@ -313,4 +1790,30 @@ macro_rules! _parse_items {
             $($rem)*
         }
     };
+    {
+        @fn $flags:tt $fields:tt $help_width:tt $fn_vis:tt $prev:tt {$($signature:tt)*}
+        $(; $($rem:tt)*)?
+    } => {
+        compile_error!("Expected a function body, argtea functions can't be declared without one");
+    };
+    {
+        @fn $flags:tt $fields:tt $help_width:tt $fn_vis:tt $prev:tt {$($signature:tt)*}
+        $token:tt
+        $($rem:tt)*
+    } => {
+        $crate::_parse_items! {
+            @fn $flags $fields $help_width $fn_vis $prev {$($signature)* $token}
+            $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $help_width:tt $fn_vis:tt $prev:tt
+        $first:tt $($rem:tt)*
+    } => {
+        compile_error!(::core::concat!(
+            "Expected a function or a constant in argtea `impl` block, found `",
+            ::core::stringify!($first),
+            "`"
+        ));
+    };
 }
@@ -0,0 +1,163 @@
+//! roff man page generation (requires the `man` feature).
+
+use crate::Flag;
+
+/// Information about the program displayed in a man page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ManMeta<'a> {
+    pub name: &'a str,
+    /// The manual section, usually `"1"` for programs.
+    pub section: &'a str,
+    pub version: &'a str,
+    pub date: &'a str,
+    /// A short description of the program.
+    pub about: &'a str,
+}
+
+/// Renders a `man(7)` page for a program with the provided flags.
+///
+/// Flags are listed in an `OPTIONS` section and positional arguments in an `ARGUMENTS` section.
+/// Flags with a `#[section = "..."]` attribute are listed under a subsection with that name
+/// (after the flags without one). The flags' `#[example(...)]` command lines are listed in an
+/// `EXAMPLES` section.
+pub fn render(meta: &ManMeta, flags: &[Flag]) -> String {
+    let mut buf = String::new();
+
+    buf += ".TH ";
+    push_quoted(&mut buf, &meta.name.to_uppercase());
+    buf += " ";
+    push_quoted(&mut buf, meta.section);
+    buf += " ";
+    push_quoted(&mut buf, meta.date);
+    buf += " ";
+    push_quoted(&mut buf, &format!("{} {}", meta.name, meta.version));
+    buf += "\n";
+
+    buf += ".SH NAME\n";
+    buf += &escape(meta.name);
+    buf += " \\- ";
+    buf += &escape(meta.about);
+    buf += "\n";
+
+    let examples: Vec<&str> = flags
+        .iter()
+        .flat_map(|flag| flag.examples)
+        .copied()
+        .collect();
+    let (positionals, flags): (Vec<&Flag>, Vec<&Flag>) =
+        flags.iter().partition(|flag| flag.is_positional());
+
+    for (heading, flags) in [("OPTIONS", flags), ("ARGUMENTS", positionals)] {
+        if flags.is_empty() {
+            continue;
+        }
+
+        buf += ".SH ";
+        buf += heading;
+        buf += "\n";
+
+        // The sections in the order that they first appear in, after the flags without one
+        let mut sections: Vec<Option<&str>> = Vec::new();
+        for flag in &flags {
+            if !sections.contains(&flag.section) {
+                sections.push(flag.section);
+            }
+        }
+        sections.sort_by_key(|section| section.is_some());
+
+        for section in sections {
+            if let Some(section) = section {
+                buf += ".SS ";
+                push_quoted(&mut buf, section);
+                buf += "\n";
+            }
+
+            for flag in flags.iter().filter(|flag| flag.section == section) {
+                push_flag(&mut buf, flag);
+            }
+        }
+    }
+
+    if !examples.is_empty() {
+        buf += ".SH EXAMPLES\n";
+        buf += ".nf\n";
+
+        for example in examples {
+            if example.starts_with(['.', '\'']) {
+                buf += "\\&";
+            }
+
+            buf += &escape(example);
+            buf.push('\n');
+        }
+
+        buf += ".fi\n";
+    }
+
+    buf
+}
+
+fn push_flag(buf: &mut String, flag: &Flag) {
+    *buf += ".TP\n";
+
+    for (i, alias) in flag.flags.iter().enumerate() {
+        if i != 0 {
+            *buf += ", ";
+        }
+
+        *buf += "\\fB";
+        *buf += &escape(alias.trim());
+        *buf += "\\fR";
+    }
+
+    for (i, param) in flag.params.iter().enumerate() {
+        if i != 0 || !flag.flags.is_empty() {
+            buf.push(' ');
+        }
+
+        *buf += "\\fI";
+        *buf += &escape(param);
+        *buf += "\\fR";
+    }
+
+    buf.push('\n');
+
+    let mut first_paragraph = true;
+    let mut paragraph_start = true;
+
+    for line in flag.doc_lines() {
+        let line = line.trim();
+
+        if line.is_empty() {
+            paragraph_start = true;
+            continue;
+        }
+
+        if paragraph_start && !first_paragraph {
+            *buf += ".IP\n";
+        }
+
+        first_paragraph = false;
+        paragraph_start = false;
+
+        if line.starts_with(['.', '\'']) {
+            // Lines starting with these would otherwise be interpreted as requests
+            *buf += "\\&";
+        }
+
+        *buf += &escape(line);
+        buf.push('\n');
+    }
+}
+
+/// Escapes backslashes, and writes dashes as minus signs (`\-`) so flags in the text (eg
+/// `--output`) can be copied from the rendered page.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e").replace('-', "\\-")
+}
+
+fn push_quoted(buf: &mut String, text: &str) {
+    buf.push('"');
+    *buf += &text.replace('\\', "\\e").replace('"', "\\(dq");
+    buf.push('"');
+}
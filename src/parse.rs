@@ -1,4 +1,289 @@
+use core::{marker::PhantomData, str::FromStr};
+
+use crate::{error::suggest, ParseError};
+
+/// An event passed to the sink of `parse_events!()`.
+///
+/// Events are emitted right before the corresponding flag's code is run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A flag was matched.
+    Flag { name: String },
+    /// An argument was matched by a catch-all branch.
+    Positional(String),
+    /// A value was taken for one of a flag's parameters.
+    Value { flag: String, value: String },
+}
+
+/// The state of an `#[optional_value]` flag's parameter.
+///
+/// The parameter is bound to `Set` or `SetWith(...)`, so `Absent` (the default) is the initial
+/// value of the variable it is assigned to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FlagState<T = String> {
+    /// The flag wasn't passed.
+    #[default]
+    Absent,
+    /// The flag was passed without a value (eg `--color`).
+    Set,
+    /// The flag was passed with a value (eg `--color=never`).
+    SetWith(T),
+}
+
+impl<T> FlagState<T> {
+    /// Returns `true` unless the flag wasn't passed.
+    pub fn is_present(&self) -> bool {
+        !matches!(self, Self::Absent)
+    }
+
+    /// Returns the flag's value, if it was passed with one.
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Self::SetWith(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<T> From<Option<T>> for FlagState<T> {
+    /// `None` becomes `Set`, and `Some(value)` becomes `SetWith(value)`.
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Self::SetWith(value),
+            None => Self::Set,
+        }
+    }
+}
+
+/// The iterator that flags take their values from.
+///
+/// This yields the value in the current argument (eg `file` in `-ofile` or `--output=file`)
+/// before any of the remaining arguments.
+pub struct Values<'a, I> {
+    pub args: &'a mut NormalizeArgs<I>,
+    /// The aliases of every flag (used to suggest a flag in [`ParseError::UnknownFlag`]).
+    pub flags: &'a [&'a [&'static str]],
+}
+
+impl<I: Iterator<Item = String>> Values<'_, I> {
+    /// Like [`Iterator::next`], but a following argument that looks like a flag is not consumed
+    /// (used by `#[strict_value]`).
+    ///
+    /// Values in the current argument (eg `--output=--verbose`) are always accepted.
+    pub fn next_strict(&mut self) -> Option<String> {
+        if self.args.has_inline_value() {
+            return self.args.inline_value();
+        }
+
+        let value = self.args.next_raw()?;
+
+        if value.starts_with('-') && value != "-" {
+            self.args.put_back(value);
+            return None;
+        }
+
+        Some(value)
+    }
+
+    /// Like [`Iterator::next`], but only values in the current argument (eg `--color=never` or
+    /// `-cnever`) are taken (used by `#[optional_value]`).
+    pub fn next_inline(&mut self) -> Option<String> {
+        self.args.inline_value()
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for Values<'_, I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.args.inline_value().or_else(|| self.args.next_raw())
+    }
+}
+
+/// Yields the `Ok` values of `iter` until the first `Err`, which is stored in `error` (used by
+/// `parse!(iter, fallible)`).
+pub struct UntilError<'a, I, E> {
+    pub iter: I,
+    pub error: &'a mut Option<E>,
+}
+
+impl<I: Iterator<Item = Result<String, E>>, E> Iterator for UntilError<'_, I, E> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        match self.iter.next()? {
+            Ok(arg) => Some(arg),
+            Err(err) => {
+                *self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+/// Adds the arguments that weren't parsed when `parse!()` stopped to `remaining` (used by
+/// `parse!(iter, remaining: name)`).
+///
+/// The rest of a short flag cluster comes first (eg `-c` if parsing stopped at `-b` in `-abc`),
+/// then a value that wasn't taken from the current argument (eg `value` in `--flag=value`), then
+/// an argument that was rejected by a `#[strict_value]` flag, and then the rest of the arguments.
+pub fn extend_remaining<I: Iterator<Item = String>>(
+    remaining: &mut impl Extend<String>,
+    args: NormalizeArgs<I>,
+) {
+    let NormalizeArgs {
+        iter,
+        cluster,
+        value,
+        pending,
+        ..
+    } = args;
+
+    let cluster = match cluster {
+        cluster if cluster.is_empty() => None,
+        // Like in `parse!()`, the rest of a cluster like `-a=b` or `-a-b` is an argument of its
+        // own (`b` or `-b`)
+        cluster if cluster.starts_with('=') => Some(cluster[1..].to_owned()),
+        cluster if cluster.starts_with('-') => Some(cluster),
+        cluster => Some(format!("-{cluster}")),
+    };
+
+    remaining.extend(cluster.into_iter().chain(value).chain(pending).chain(iter));
+}
+
+/// Converts a variable into the value of a field for `build!()`. A variable of the field's type
+/// is used as-is, and an `Option` of it falls back to the field's default value if it is `None`.
+pub trait IntoField<T> {
+    fn into_field(self, default: impl FnOnce() -> T) -> T;
+}
+
+impl<T> IntoField<T> for T {
+    fn into_field(self, _default: impl FnOnce() -> T) -> T {
+        self
+    }
+}
+
+impl<T> IntoField<T> for Option<T> {
+    fn into_field(self, default: impl FnOnce() -> T) -> T {
+        self.unwrap_or_else(default)
+    }
+}
+
+/// The variable for a field of type `T` in `build!()`.
+///
+/// If the field is an `Option` and the variable has the same type, the inherent
+/// [`into_field`](Self::into_field) is used (since inherent methods take precedence over trait
+/// methods), so `None` also falls back to the field's default value. Other variables are converted
+/// with [`IntoField`].
+pub struct Field<T, V>(pub V, pub PhantomData<T>);
+
+impl<T> Field<Option<T>, Option<T>> {
+    pub fn into_field(self, default: impl FnOnce() -> Option<T>) -> Option<T> {
+        self.0.or_else(default)
+    }
+}
+
+impl<T, V: IntoField<T>> IntoField<T> for Field<T, V> {
+    fn into_field(self, default: impl FnOnce() -> T) -> T {
+        self.0.into_field(default)
+    }
+}
+
+/// Creates the error for a positional argument that looks like a flag (used by
+/// `#[strict_value]`), suggesting the most similar flag in `flags`.
+pub fn unknown_flag(flag: String, flags: &[&[&'static str]]) -> ParseError {
+    let suggestion = suggest(&flag, flags.iter().copied().flatten().copied());
+
+    ParseError::UnknownFlag { flag, suggestion }
+}
+
+/// Splits a positional argument at its first `=` (used by `#[key_value]`), so `VAR=a=b` becomes
+/// `("VAR", Some("a=b"))` and `file` becomes `("file", None)`.
+pub fn split_key_value(mut arg: String) -> (String, Option<String>) {
+    match arg.find('=') {
+        Some(idx) => {
+            let value = arg.split_off(idx + 1);
+            arg.pop();
+
+            (arg, Some(value))
+        }
+        None => (arg, None),
+    }
+}
+
+/// Converts the value of a typed parameter (eg `width: u32`).
+///
+/// `ty` is the name of the type that is used in error messages.
+pub fn parse_value<T: FromStr>(
+    flag: &str,
+    param: &'static str,
+    ty: &'static str,
+    value: Option<String>,
+) -> Result<T, ParseError> {
+    let Some(value) = value else {
+        return Err(ParseError::MissingValue {
+            flag: flag.to_owned(),
+            param,
+        });
+    };
+
+    value.parse().map_err(|_| ParseError::InvalidValue {
+        flag: flag.to_owned(),
+        value,
+        reason: format!("expected {ty}"),
+    })
+}
+
+/// Checks the value of a choice parameter (eg `color in ["auto", "never"]`) and returns the
+/// matching choice. When `ignore_case` is true, the value is compared ignoring ASCII case (like in
+/// [`value::one_of`](crate::value::one_of)), but the choice is still returned with its original
+/// spelling.
+pub fn parse_choice(
+    flag: &str,
+    param: &'static str,
+    choices: &[&'static str],
+    ignore_case: bool,
+    value: Option<String>,
+) -> Result<&'static str, ParseError> {
+    let Some(value) = value else {
+        return Err(ParseError::MissingValue {
+            flag: flag.to_owned(),
+            param,
+        });
+    };
+
+    let found = choices.iter().find(|choice| {
+        if ignore_case {
+            choice.eq_ignore_ascii_case(&value)
+        } else {
+            **choice == value
+        }
+    });
+
+    match found {
+        Some(choice) => Ok(choice),
+        None => Err(ParseError::InvalidValue {
+            flag: flag.to_owned(),
+            value,
+            reason: format!(
+                "expected one of {}",
+                choices
+                    .iter()
+                    .map(|choice| format!("`{choice}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }),
+    }
+}
+
+/// Turns a `char` into a short flag (eg `'a'` => `"-a"`) without allocating.
 pub struct FlagView {
+    /// `-` followed by up to four bytes (the maximum length of a UTF-8 encoded `char`)
     buf: [u8; 5],
 }
 
@@ -11,18 +296,237 @@ impl FlagView {
         self.buf[0] = b'-';
         let len = flag.encode_utf8(&mut self.buf[1..]).len();
 
+        // SAFETY: `encode_utf8` wrote a valid UTF-8 encoded `char` into `buf[1..len + 1]` (it
+        // would have panicked if it didn't fit), and `buf[0]` is the ASCII `-`.
         unsafe { core::str::from_utf8_unchecked_mut(&mut self.buf[0..len + 1]) }
     }
 }
 
+impl Default for FlagView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Splits short flag clusters and `--flag=value` arguments like `parse!()` does, without knowing
+/// which flags take values.
+///
+/// Every character of a cluster is treated as a short flag, except that the rest of the cluster
+/// after an `=` becomes a value (`-o=file` => `-o file`), and the rest of the cluster starting at a
+/// `-` is split like an argument of its own (`-v-ab` => `-v -a -b`, and `-v--` => `-v --`). The
+/// arguments after `--` are never split.
+///
+/// ```rust
+/// let args = ["-vo=file", "--width=80", "-", "--", "-ab"].map(String::from);
+///
+/// assert_eq!(
+///     argtea::normalize_args(args).collect::<Vec<_>>(),
+///     ["-v", "-o", "file", "--width", "80", "-", "--", "-ab"]
+/// );
+/// ```
+pub fn normalize_args<I: IntoIterator<Item = String>>(args: I) -> impl Iterator<Item = String> {
+    NormalizeArgs::new(args.into_iter())
+}
+
+/// The iterator returned by [`normalize_args`].
+///
+/// `parse!()`, [`parse_dynamic`](crate::parse_dynamic), and the dynamic completions split
+/// arguments with this as well: a flag that takes a value takes it with
+/// [`inline_value`](Self::inline_value) (eg `file` in `-ofile`) and then with
+/// [`next_raw`](Self::next_raw), and a value that isn't taken is yielded as the next argument.
+pub struct NormalizeArgs<I> {
+    iter: I,
+    /// The rest of a short flag cluster (without the leading `-`)
+    cluster: String,
+    /// The value in `--flag=value`
+    value: Option<String>,
+    /// An argument that was put back (see [`put_back`](Self::put_back))
+    pending: Option<String>,
+    /// Whether `--` was reached
+    trailing: bool,
+    charview: FlagView,
+}
+
+impl<I: Iterator<Item = String>> NormalizeArgs<I> {
+    pub fn new(iter: I) -> Self {
+        Self {
+            iter,
+            cluster: String::new(),
+            value: None,
+            pending: None,
+            trailing: false,
+            charview: FlagView::new(),
+        }
+    }
+
+    /// Returns `true` if the current argument contains a value (see
+    /// [`inline_value`](Self::inline_value)).
+    pub fn has_inline_value(&self) -> bool {
+        !self.cluster.is_empty() || self.value.is_some()
+    }
+
+    /// Takes the value in the current argument: the rest of a short flag cluster (eg `file` in
+    /// `-ofile` or `-o=file`) or the value in `--flag=value`.
+    pub fn inline_value(&mut self) -> Option<String> {
+        if !self.cluster.is_empty() {
+            let mut value = core::mem::take(&mut self.cluster);
+
+            if value.starts_with('=') {
+                value.remove(0);
+            }
+
+            return Some(value);
+        }
+
+        self.value.take()
+    }
+
+    /// Takes the next argument without splitting it (eg the value in `-o -x`).
+    pub fn next_raw(&mut self) -> Option<String> {
+        self.pending.take().or_else(|| self.iter.next())
+    }
+
+    /// Puts back an argument that was taken with [`next_raw`](Self::next_raw), so that it is
+    /// split by the next call to `next` (used by `#[strict_value]`).
+    pub fn put_back(&mut self, arg: String) {
+        self.pending = Some(arg);
+    }
+
+    /// Returns `true` if an argument was put back and hasn't been taken again.
+    pub fn has_put_back(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Splits a whole argument, and returns its first part.
+    fn split(&mut self, mut arg: String) -> String {
+        if self.trailing {
+            return arg;
+        }
+
+        if arg == "--" {
+            self.trailing = true;
+        } else if arg.starts_with("--") {
+            if let Some(idx) = arg.find('=') {
+                self.value = Some(arg.split_off(idx + 1));
+                arg.pop();
+            }
+        } else if arg.starts_with('-') && !arg.starts_with("-=") && arg.chars().count() > 2 {
+            // An argument like `-=x` isn't a cluster, since `=` can't be a short flag
+            arg.remove(0);
+            self.cluster = arg;
+            return self.next_flag();
+        }
+
+        arg
+    }
+
+    /// Splits the next short flag off of `cluster` (which can't be empty).
+    fn next_flag(&mut self) -> String {
+        // `String::remove` removes a whole `char`, so multibyte flags like `-ä` work
+        let flag = self.cluster.remove(0);
+        self.charview.get(flag).to_owned()
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for NormalizeArgs<I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if let Some(value) = self.value.take() {
+            return Some(value);
+        }
+
+        if self.cluster.starts_with('=') {
+            self.cluster.remove(0);
+            return Some(core::mem::take(&mut self.cluster));
+        }
+
+        if self.cluster.starts_with('-') {
+            let arg = core::mem::take(&mut self.cluster);
+            return Some(self.split(arg));
+        }
+
+        if !self.cluster.is_empty() {
+            return Some(self.next_flag());
+        }
+
+        let arg = self.next_raw()?;
+        Some(self.split(arg))
+    }
+}
+
+/// Parses arguments using the flags of an [`argtea_impl`](crate::argtea_impl) (see the
+/// [crate documentation](crate#functions)).
+///
+/// `parse!()` is replaced by [`argtea_impl`](crate::argtea_impl) in the functions that are defined
+/// inside of it, so using it anywhere else is an error:
+/// ```rust,compile_fail
+/// # use argtea::parse;
+/// fn parse_args() {
+///     // error: `parse!()` can only be used in functions inside of `argtea_impl!`
+///     parse!(std::env::args());
+/// }
+/// ```
+#[macro_export]
+macro_rules! parse {
+    ($($args:tt)*) => {
+        compile_error!("`parse!()` can only be used in functions inside of `argtea_impl!`")
+    };
+}
+
+/// Parses arguments into the fields of the struct that is defined inside of an
+/// [`argtea_impl`](crate::argtea_impl) (see the
+/// [crate documentation](crate#parsing-into-a-struct)).
+///
+/// Like [`parse!()`](crate::parse!), this can only be used in functions inside of
+/// [`argtea_impl`](crate::argtea_impl).
+#[macro_export]
+macro_rules! parse_fields {
+    ($($args:tt)*) => {
+        compile_error!("`parse_fields!()` can only be used in functions inside of `argtea_impl!`")
+    };
+}
+
+/// Constructs `Self` from variables named like the fields of the struct that is defined inside of
+/// an [`argtea_impl`](crate::argtea_impl), where a variable that is `None` results in the
+/// field's default value (see the [crate documentation](crate#parsing-into-a-struct)).
+///
+/// Like [`parse!()`](crate::parse!), this can only be used in functions inside of
+/// [`argtea_impl`](crate::argtea_impl).
+#[macro_export]
+macro_rules! build {
+    ($($args:tt)*) => {
+        compile_error!("`build!()` can only be used in functions inside of `argtea_impl!`")
+    };
+}
+
+/// Like [`parse!()`](crate::parse!), but additionally passes an [`Event`] to a sink (see the
+/// [crate documentation](crate#parse_events)).
+///
+/// Like [`parse!()`](crate::parse!), this can only be used in functions inside of
+/// [`argtea_impl`](crate::argtea_impl).
+#[macro_export]
+macro_rules! parse_events {
+    ($($args:tt)*) => {
+        compile_error!("`parse_events!()` can only be used in functions inside of `argtea_impl!`")
+    };
+}
+
 /// Helper macro: removes all `#[fake]` flags and then calls the provided
 /// macro with the filtered flags as the first argument.
+///
+/// Doc comments, `#[hidden]`, `#[section]`, and `#[example]` are removed, and argtea's other flag
+/// attributes (eg `#[strict_value]` and `#[eager]`) are kept. `#[cfg(...)]`, `#[cfg_attr(...)]`,
+/// and lint attributes (eg `#[allow(...)]`) are moved before argtea's attributes as `pass #[...]`,
+/// and are forwarded to the flag's match arm. Any other attribute is a `compile_error!()`.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! _filter_fake_flags {
     {
         $(@{
             pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
             fake: $($fake:ident)?
         })?
         {}
@@ -34,6 +538,8 @@ macro_rules! _filter_fake_flags {
     {
         $(@{
             pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
             fake: $($fake:ident)?
         })?
         {
@@ -45,6 +551,8 @@ macro_rules! _filter_fake_flags {
         $crate::_filter_fake_flags! {
             @{
                 pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)?}
+                attrs: {$($($attrs)*)?}
                 fake: true
             }
             {$($remaining)*}
@@ -55,6 +563,8 @@ macro_rules! _filter_fake_flags {
     {
         $(@{
             pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
             fake: $($fake:ident)?
         })?
         {
@@ -66,6 +576,8 @@ macro_rules! _filter_fake_flags {
         $crate::_filter_fake_flags! {
             @{
                 pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)?}
+                attrs: {$($($attrs)*)?}
                 fake: $($($fake)?)?
             }
             {$($remaining)*}
@@ -76,10 +588,12 @@ macro_rules! _filter_fake_flags {
     {
         $(@{
             pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
             fake: $($fake:ident)?
         })?
         {
-            #[doc = $cmt:literal]
+            #[section = $name:tt]
             $($remaining:tt)*
         }
         $local_macro_to_call:ident!($($other_args:tt)*)
@@ -87,6 +601,8 @@ macro_rules! _filter_fake_flags {
         $crate::_filter_fake_flags! {
             @{
                 pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)?}
+                attrs: {$($($attrs)*)?}
                 fake: $($($fake)?)?
             }
             {$($remaining)*}
@@ -97,24 +613,37 @@ macro_rules! _filter_fake_flags {
     {
         $(@{
             pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
             fake: $($fake:ident)?
         })?
         {
-            #[$($attr:tt)*]
+            #[example $($example:tt)*]
             $($remaining:tt)*
         }
         $local_macro_to_call:ident!($($other_args:tt)*)
     } => {
-        compile_error!(::core::concat!("Invalid flag attribute #[", ::core::stringify!($($attr)*), "]"))
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)?}
+                attrs: {$($($attrs)*)?}
+                fake: $($($fake)?)?
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
     };
 
     {
         $(@{
             pre_flags: {$($pre_flags:tt)*}
-            fake: $fake:ident
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
         })?
         {
-            ($($lhs:tt)*) => $rhs:tt
+            #[doc = $cmt:expr]
             $($remaining:tt)*
         }
         $local_macro_to_call:ident!($($other_args:tt)*)
@@ -122,7 +651,9 @@ macro_rules! _filter_fake_flags {
         $crate::_filter_fake_flags! {
             @{
                 pre_flags: {$($($pre_flags)*)?}
-                fake:
+                passed: {$($($passed)*)?}
+                attrs: {$($($attrs)*)?}
+                fake: $($($fake)?)?
             }
             {$($remaining)*}
             $local_macro_to_call!($($other_args)*)
@@ -132,155 +663,1193 @@ macro_rules! _filter_fake_flags {
     {
         $(@{
             pre_flags: {$($pre_flags:tt)*}
-            fake:
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
         })?
         {
-            ($($lhs:tt)*) => $rhs:tt
+            #[strict_value]
             $($remaining:tt)*
         }
         $local_macro_to_call:ident!($($other_args:tt)*)
     } => {
         $crate::_filter_fake_flags! {
             @{
-                pre_flags: {$($($pre_flags)*)? ($($lhs)*) => $rhs}
-                fake:
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)?}
+                attrs: {$($($attrs)*)? #[strict_value]}
+                fake: $($($fake)?)?
             }
             {$($remaining)*}
             $local_macro_to_call!($($other_args)*)
         }
     };
-}
 
-/// Helper macro; corresponds to `parse!(iter)`.
-///
-/// The `=> {...}` argument corresponds to the flags given to [`argtea_impl`]
-/// (before the `impl`)
-#[doc(hidden)]
-#[macro_export]
-macro_rules! _parse {
     {
-        $iter:ident => {
-            $(
-                $(#[doc = $doc:literal])*
-                $(#[hidden])?
-                ($($pat:tt)+) => $block:block
-            )*
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
+        })?
+        {
+            #[optional_value]
+            $($remaining:tt)*
         }
+        $local_macro_to_call:ident!($($other_args:tt)*)
     } => {
-        #[allow(unused_variables)]
-        {
-            // For splitting flags like '-sw 80' => '-s -w 80'
-            let mut flag_buf = String::new();
-            let mut charview = $crate::parse::FlagView::new();
-
-            // Stores the value in `--flag=value`
-            let mut stashed_value = None;
-
-            #[allow(unused_labels)]
-            'stop_parsing:
-            while let Some(mut flag) = if flag_buf.is_empty() {
-                    $iter.next().map(::std::borrow::Cow::from)
-                } else {
-                    Some(::std::borrow::Cow::from(&*charview.get(flag_buf.remove(0))))
-                }
-            {
-                if flag.starts_with("-") && !flag.starts_with("--") && flag.chars().count() > 2 {
-                    flag_buf = flag.into_owned();
-                    flag_buf.remove(0);
-                    continue;
-                }
-
-                if flag.starts_with("--") {
-                    if let Some(idx) = flag.find('=') {
-                        let flag = flag.to_mut();
-                        stashed_value = Some(flag.split_off(idx + 1));
-                        flag.pop();
-                    }
-                }
-
-                let mut $iter = ::core::iter::from_fn(|| (!flag_buf.is_empty()).then_some(::core::mem::take(&mut flag_buf)))
-                    .chain(
-                        stashed_value.take().into_iter().chain(&mut $iter)
-                    );
-
-                match &*flag {
-                    $(
-                        $crate::_create_branch_pat!(($($pat)+)) => $crate::_create_branch!($iter flag ($($pat)+) => $block),
-                    )*
-                }
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)?}
+                attrs: {$($($attrs)*)? #[optional_value]}
+                fake: $($($fake)?)?
             }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
         }
     };
-}
 
-/// Recursive helper macro. This replaces occurances of `parse!()` with
-/// `$crate::_parse` and provides it the additional required arguments
-///
-/// The `flags` argument corresponds to the flags given to [`argtea_impl`] (before
-/// the `impl`)
-#[doc(hidden)]
-#[macro_export]
-macro_rules! _scan_body {
     {
-        $flags:tt
-        {$($already_parsed:tt)*}
-        parse!($iter:ident)
-        $($rem:tt)*
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
+        })?
+        {
+            #[key_value]
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
     } => {
-        $crate::_scan_body!{
-            $flags
-            {
-                $($already_parsed)*
-                $crate::_parse!{
-                    $iter => $flags
-                }
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)?}
+                attrs: {$($($attrs)*)? #[key_value]}
+                fake: $($($fake)?)?
             }
-            $($rem)*
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
         }
     };
+
     {
-        $flags:tt
-        {$($already_parsed:tt)*}
-        parse!($expr:expr)
-        $($rem:tt)*
-    } => {
-        $crate::_scan_body!{
-            $flags
-            {
-                $($already_parsed)*
-                {
-                    let mut args = $expr;
-                    $crate::_parse!{
-                        args => $flags
-                    }
-                }
-            }
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
+        })?
+        {
+            #[eager]
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)?}
+                attrs: {$($($attrs)*)? #[eager]}
+                fake: $($($fake)?)?
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
+    };
+
+    // `#[cfg(...)]`, `#[cfg_attr(...)]`, and lint attributes are forwarded to the flag's match arm
+    {
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
+        })?
+        {
+            #[cfg $args:tt]
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)? pass #[cfg $args]}
+                attrs: {$($($attrs)*)?}
+                fake: $($($fake)?)?
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
+    };
+
+    {
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
+        })?
+        {
+            #[cfg_attr $args:tt]
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)? pass #[cfg_attr $args]}
+                attrs: {$($($attrs)*)?}
+                fake: $($($fake)?)?
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
+    };
+
+    {
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
+        })?
+        {
+            #[allow $args:tt]
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)? pass #[allow $args]}
+                attrs: {$($($attrs)*)?}
+                fake: $($($fake)?)?
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
+    };
+
+    {
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
+        })?
+        {
+            #[expect $args:tt]
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)? pass #[expect $args]}
+                attrs: {$($($attrs)*)?}
+                fake: $($($fake)?)?
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
+    };
+
+    {
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
+        })?
+        {
+            #[warn $args:tt]
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)? pass #[warn $args]}
+                attrs: {$($($attrs)*)?}
+                fake: $($($fake)?)?
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
+    };
+
+    {
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
+        })?
+        {
+            #[deny $args:tt]
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)? pass #[deny $args]}
+                attrs: {$($($attrs)*)?}
+                fake: $($($fake)?)?
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
+    };
+
+    {
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $($fake:ident)?
+        })?
+        {
+            #[forbid $args:tt]
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)?}
+                passed: {$($($passed)*)? pass #[forbid $args]}
+                attrs: {$($($attrs)*)?}
+                fake: $($($fake)?)?
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
+    };
+
+    {
+        $(@$state:tt)?
+        {
+            #[$($attr:tt)*]
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        compile_error!(::core::concat!("Invalid flag attribute #[", ::core::stringify!($($attr)*), "]"))
+    };
+
+    // A comma after a block is optional (like in `match` expressions)
+    {
+        $(@$state:tt)?
+        {
+            ($($lhs:tt)*) $(if $guard:expr)? => {$($rhs:tt)*},
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        $crate::_filter_fake_flags! {
+            $(@$state)?
+            {($($lhs)*) $(if $guard)? => {$($rhs)*} $($remaining)*}
+            $($call)*
+        }
+    };
+
+    // `#[fake]` flags are dropped. The state isn't optional here, since a first flag without any
+    // attributes doesn't have one (and isn't fake)
+    {
+        @{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake: $fake:ident
+        }
+        {
+            ($($lhs:tt)*) $(if $guard:expr)? => {$($rhs:tt)*}
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($pre_flags)*}
+                passed: {}
+                attrs: {}
+                fake:
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
+    };
+
+    {
+        $(@{
+            pre_flags: {$($pre_flags:tt)*}
+            passed: {$($passed:tt)*}
+            attrs: {$($attrs:tt)*}
+            fake:
+        })?
+        {
+            ($($lhs:tt)*) $(if $guard:expr)? => {$($rhs:tt)*}
+            $($remaining:tt)*
+        }
+        $local_macro_to_call:ident!($($other_args:tt)*)
+    } => {
+        $crate::_filter_fake_flags! {
+            @{
+                pre_flags: {$($($pre_flags)*)? $($($passed)*)? $($($attrs)*)? ($($lhs)*) $(if $guard)? => {$($rhs)*}}
+                passed: {}
+                attrs: {}
+                fake:
+            }
+            {$($remaining)*}
+            $local_macro_to_call!($($other_args)*)
+        }
+    };
+
+    // Expression bodies (`("-v") => verbose = true,`) are wrapped in a block
+    {
+        $(@$state:tt)?
+        {
+            ($($lhs:tt)*) $(if $guard:expr)? => $body:expr $(, $($remaining:tt)*)?
+        }
+        $($call:tt)*
+    } => {
+        $crate::_filter_fake_flags! {
+            $(@$state)?
+            {($($lhs)*) $(if $guard)? => {$body} $($($remaining)*)?}
+            $($call)*
+        }
+    };
+    {
+        $(@$state:tt)?
+        {
+            ($($lhs:tt)*) $(if $guard:expr)? => $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        compile_error!(::core::concat!(
+            "Expected a block or an expression after `(",
+            ::core::stringify!($($lhs)*),
+            ") =>`, flags are written like `(\"--flag\" | \"-f\", param) => { ... }`"
+        ));
+    };
+    {
+        $(@$state:tt)?
+        {
+            $first:tt
+            $($remaining:tt)*
+        }
+        $($call:tt)*
+    } => {
+        compile_error!(::core::concat!(
+            "Expected a flag like `(\"--flag\" | \"-f\", param) => { ... }`, found `",
+            ::core::stringify!($first),
+            "`"
+        ));
+    };
+}
+
+/// Helper macro; corresponds to `parse!(iter)` and `parse_events!(iter, sink)`.
+///
+/// The `=> {...}` argument corresponds to the flags given to [`argtea_impl`]
+/// (before the `impl`). `let name = expr` can be used instead of `iter` to parse an expression.
+///
+/// This is invoked with parentheses so that `parse!()` is always parsed as an expression (even at
+/// the start of a statement or at the end of a block).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _parse {
+    {
+        $options:tt let $iter:ident = $expr:expr, $sink:expr => $flags:tt
+    } => {{
+        let mut $iter = $expr;
+        $crate::_parse!{$options $iter, $sink => $flags}
+    }};
+    {
+        $options:tt let $iter:ident = $expr:expr => $flags:tt
+    } => {{
+        let mut $iter = $expr;
+        $crate::_parse!{$options $iter => $flags}
+    }};
+    {
+        $options:tt $iter:ident, $sink:expr => $flags:tt
+    } => {{
+        #[allow(unused_mut)]
+        let mut __argtea_sink = $sink;
+        $crate::_parse!{@options {__argtea_sink} $options $iter => $flags}
+    }};
+    {
+        $options:tt $iter:ident => $flags:tt
+    } => {
+        $crate::_parse!{@options {} $options $iter => $flags}
+    };
+
+    // The options are `index: $ident`, `trailing: $ident`, `remaining: $ident`, `consumed: $ident`,
+    // `fallible`, and options that are checked by `_lint_options` (eg `strict`)
+    {@options $sink:tt $options:tt $($rest:tt)*} => {
+        $crate::_parse!{@option $sink {} {} {} {} {} [] $options $($rest)*}
+    };
+    {@option $sink:tt $index:tt $trailing:tt $remaining:tt $consumed:tt $fallible:tt $lint:tt {} $($rest:tt)*} => {
+        $crate::_parse!{@sink $sink $index $trailing $remaining $consumed $fallible $lint $($rest)*}
+    };
+    {
+        @option $sink:tt $index:tt $trailing:tt $remaining:tt $consumed:tt $fallible:tt $lint:tt
+        {index: $new_index:ident $(, $($options:tt)*)?} $($rest:tt)*
+    } => {
+        $crate::_parse!{@option $sink {$new_index} $trailing $remaining $consumed $fallible $lint {$($($options)*)?} $($rest)*}
+    };
+    {
+        @option $sink:tt $index:tt $trailing:tt $remaining:tt $consumed:tt $fallible:tt $lint:tt
+        {trailing: $new_trailing:ident $(, $($options:tt)*)?} $($rest:tt)*
+    } => {
+        $crate::_parse!{@option $sink $index {$new_trailing} $remaining $consumed $fallible $lint {$($($options)*)?} $($rest)*}
+    };
+    {
+        @option $sink:tt $index:tt $trailing:tt $remaining:tt $consumed:tt $fallible:tt $lint:tt
+        {remaining: $new_remaining:ident $(, $($options:tt)*)?} $($rest:tt)*
+    } => {
+        $crate::_parse!{@option $sink $index $trailing {$new_remaining} $consumed $fallible $lint {$($($options)*)?} $($rest)*}
+    };
+    {
+        @option $sink:tt $index:tt $trailing:tt $remaining:tt $consumed:tt $fallible:tt $lint:tt
+        {consumed: $new_consumed:ident $(, $($options:tt)*)?} $($rest:tt)*
+    } => {
+        $crate::_parse!{@option $sink $index $trailing $remaining {$new_consumed} $fallible $lint {$($($options)*)?} $($rest)*}
+    };
+    {
+        @option $sink:tt $index:tt $trailing:tt $remaining:tt $consumed:tt $fallible:tt $lint:tt
+        {fallible $(, $($options:tt)*)?} $($rest:tt)*
+    } => {
+        $crate::_parse!{@option $sink $index $trailing $remaining $consumed {fallible} $lint {$($($options)*)?} $($rest)*}
+    };
+    {
+        @option $sink:tt $index:tt $trailing:tt $remaining:tt $consumed:tt $fallible:tt $lint:tt
+        {$option:ident $(, $($options:tt)*)?} $($rest:tt)*
+    } => {
+        $crate::_parse!{@option $sink $index $trailing $remaining $consumed $fallible [$option] {$($($options)*)?} $($rest)*}
+    };
+    {@option $sink:tt $index:tt $trailing:tt $remaining:tt $consumed:tt $fallible:tt $lint:tt {$($options:tt)*} $($rest:tt)*} => {
+        compile_error!(::core::concat!(
+            "Invalid options `",
+            ::core::stringify!($($options)*),
+            "`, expected `index: $ident`, `trailing: $ident`, `remaining: $ident`, `consumed: $ident`, `fallible`, and/or `strict`"
+        ));
+    };
+
+    {
+        @sink $sink:tt {$($index:ident)?} {$($trailing:ident)?} {$($remaining:ident)?} {$($consumed:ident)?} $fallible:tt $options:tt
+        $iter:ident => {
+            $(
+                $(pass #[$pass:meta])*
+                $(#[$attr:ident])*
+                ($($pat:tt)+) $(if $guard:expr)? => {$($block:tt)*}
+            )*
+        }
+    } => {{
+        $crate::_lint_options!{$options
+        let __argtea_consumed = {
+            // The aliases of every flag, for suggestions in `ParseError::UnknownFlag`
+            let __argtea_flags: &[&[&str]] = &[$($crate::_flag_aliases!(($($pat)+)),)*];
+
+            // With `fallible`, `$iter` yields `Result`s and parsing stops at the first error
+            $crate::_fallible!{$fallible $iter __argtea_error}
+
+            $crate::_eager_prescan!{
+                $iter $sink {$($index)?} [__argtea_flags __argtea_eager_end 'stop_parsing] [$($(#[$attr])*)*] {
+                    $(
+                        [$(#[$attr])*] [$(pass #[$pass])*] [$(#[$attr])*]
+                        ($($pat)+) [$(if $guard)?] => {$crate::_relabel!{'stop_parsing [] {} $($block)*}}
+                    )*
+                }
+            }
+
+            // The number of arguments taken from `$iter`
+            let __argtea_consumed = ::core::cell::Cell::new(0);
+
+            // Splits flags like '-sw 80' => '-s -w 80' and '--width=80' => '--width 80'
+            let mut __argtea_args = $crate::parse::NormalizeArgs::new(::core::iter::Iterator::inspect(
+                &mut $iter,
+                |_| __argtea_consumed.set(__argtea_consumed.get() + 1),
+            ));
+
+            // The index of the argument that `flag` came from (unused without `index: ...`)
+            #[allow(unused_variables)]
+            let mut __argtea_arg_index;
+
+            #[allow(unused_labels)]
+            'stop_parsing:
+            while let Some(flag) = ::core::iter::Iterator::next(&mut __argtea_args) {
+                // The parts of a cluster or `--flag=value` share the index of their argument, and
+                // an argument that was put back is always the last argument that was taken
+                __argtea_arg_index = __argtea_consumed.get() - 1;
+                let flag = ::std::borrow::Cow::<str>::from(flag);
+
+                // With `trailing: name`, the arguments after `--` (or a cluster like `-v--`) are
+                // added to `name` as-is
+                $(
+                    if flag == "--" {
+                        ::core::iter::Extend::extend(&mut $trailing, &mut __argtea_args);
+                        break;
+                    }
+                )?
+
+                // Unused if all flags that take parameters are `#[eager]`
+                #[allow(unused_mut, unused_variables)]
+                let mut $iter = $crate::parse::Values {
+                    args: &mut __argtea_args,
+                    flags: __argtea_flags,
+                };
+
+                $(let $index: usize = __argtea_arg_index;)?
+
+                // Flags match the first element, and the positional flag binds the second one
+                // (so its guard can use the argument)
+                match (&*flag, &*flag) {
+                    $(
+                        $(#[$pass])*
+                        $crate::_create_branch_pat!(($($pat)+)) $(if $guard)? => $crate::_skip_eager!([$(#[$attr])*] [__argtea_arg_index __argtea_eager_end] $iter flag $sink [$(#[$attr])*] ($($pat)+) => {$crate::_relabel!{'stop_parsing [] {} $($block)*}}),
+                    )*
+                }
+            }
+
+            // An argument rejected by a `#[strict_value]` flag wasn't processed
+            let __argtea_consumed = __argtea_consumed.get() - usize::from(__argtea_args.has_put_back());
+
+            // With `remaining: name`, the arguments that weren't parsed (eg after a `break`) are
+            // added to `name`
+            $(
+                $crate::parse::extend_remaining(&mut $remaining, __argtea_args);
+            )?
+
+            $crate::_fallible!{$fallible @check __argtea_error}
+
+            __argtea_consumed
+        };
+        }
+
+        // With `consumed: name`, the number of arguments taken from `$iter` is assigned to `name`
+        $($consumed = __argtea_consumed;)?
+    }};
+}
+
+/// Recursive helper macro; replaces the `'stop_parsing` labels in a flag's code with `$label`.
+///
+/// Labels are hygienic, so a `'stop_parsing` label that is declared by `parse!()` can't be used by
+/// the flags' code directly. Instead, `parse!()` passes the same `'stop_parsing` token that it
+/// declares the label with. The `frames` argument is a stack of the blocks that are being scanned
+/// (like in [`_scan_body`]). Only blocks (`{..}`) are scanned, and up to 4 tokens are moved at a
+/// time, which keeps the recursion depth low.
+///
+/// A nested `parse!()` in a flag's code is also reported here, since it would otherwise fail with
+/// "cannot find macro `parse`".
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _relabel {
+    ($label:lifetime $frames:tt {$($done:tt)*} parse! $($rem:tt)*) => {
+        compile_error!("`parse!()` can't be used in the code of a flag, only in the functions of the `impl` block");
+    };
+    ($label:lifetime $frames:tt {$($done:tt)*} 'stop_parsing $($rem:tt)*) => {
+        $crate::_relabel!{$label $frames {$($done)* $label} $($rem)*}
+    };
+    ($label:lifetime [$($frames:tt)*] {$($done:tt)*} {$($group:tt)*} $($rem:tt)*) => {
+        $crate::_relabel!{$label [(brace {$($done)*} {$($rem)*}) $($frames)*] {} $($group)*}
+    };
+    ($label:lifetime $frames:tt {$($done:tt)*} $t0:tt 'stop_parsing $($rem:tt)*) => {
+        $crate::_relabel!{$label $frames {$($done)* $t0 $label} $($rem)*}
+    };
+    ($label:lifetime [$($frames:tt)*] {$($done:tt)*} $t0:tt {$($group:tt)*} $($rem:tt)*) => {
+        $crate::_relabel!{$label [(brace {$($done)* $t0} {$($rem)*}) $($frames)*] {} $($group)*}
+    };
+    ($label:lifetime $frames:tt {$($done:tt)*} $t0:tt $name:ident! $($rem:tt)*) => {
+        $crate::_relabel!{$label $frames {$($done)* $t0} $name! $($rem)*}
+    };
+    ($label:lifetime $frames:tt {$($done:tt)*} $t0:tt $t1:tt 'stop_parsing $($rem:tt)*) => {
+        $crate::_relabel!{$label $frames {$($done)* $t0 $t1 $label} $($rem)*}
+    };
+    ($label:lifetime [$($frames:tt)*] {$($done:tt)*} $t0:tt $t1:tt {$($group:tt)*} $($rem:tt)*) => {
+        $crate::_relabel!{$label [(brace {$($done)* $t0 $t1} {$($rem)*}) $($frames)*] {} $($group)*}
+    };
+    ($label:lifetime $frames:tt {$($done:tt)*} $t0:tt $t1:tt $name:ident! $($rem:tt)*) => {
+        $crate::_relabel!{$label $frames {$($done)* $t0 $t1} $name! $($rem)*}
+    };
+    ($label:lifetime $frames:tt {$($done:tt)*} $t0:tt $t1:tt $t2:tt 'stop_parsing $($rem:tt)*) => {
+        $crate::_relabel!{$label $frames {$($done)* $t0 $t1 $t2 $label} $($rem)*}
+    };
+    ($label:lifetime [$($frames:tt)*] {$($done:tt)*} $t0:tt $t1:tt $t2:tt {$($group:tt)*} $($rem:tt)*) => {
+        $crate::_relabel!{$label [(brace {$($done)* $t0 $t1 $t2} {$($rem)*}) $($frames)*] {} $($group)*}
+    };
+    ($label:lifetime $frames:tt {$($done:tt)*} $t0:tt $t1:tt $t2:tt $name:ident! $($rem:tt)*) => {
+        $crate::_relabel!{$label $frames {$($done)* $t0 $t1 $t2} $name! $($rem)*}
+    };
+
+    // None of the next 4 tokens is a `'stop_parsing` label, a block, or a macro call
+    ($label:lifetime $frames:tt {$($done:tt)*} $t0:tt $t1:tt $t2:tt $t3:tt $($rem:tt)*) => {
+        $crate::_relabel!{$label $frames {$($done)* $t0 $t1 $t2 $t3} $($rem)*}
+    };
+    ($label:lifetime $frames:tt {$($done:tt)*} $token:tt $($rem:tt)*) => {
+        $crate::_relabel!{$label $frames {$($done)* $token} $($rem)*}
+    };
+
+    // The end of a block
+    ($label:lifetime [(brace {$($before:tt)*} {$($rem:tt)*}) $($frames:tt)*] {$($done:tt)*}) => {
+        $crate::_relabel!{$label [$($frames)*] {$($before)* {$($done)*}} $($rem)*}
+    };
+
+    ($label:lifetime [] {$($done:tt)*}) => {
+        $($done)*
+    };
+}
+
+/// Helper macro; corresponds to the `fallible` option of `parse!()`.
+///
+/// With `{fallible}`, `$iter` is shadowed by an iterator that yields the `Ok` values of `$iter`
+/// until the first `Err`, which is stored in `$error`. `@check` then returns the error from the
+/// enclosing function (converting it with `From` like `?`).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _fallible {
+    ({} $iter:ident $error:ident) => {};
+    ({fallible} $iter:ident $error:ident) => {
+        let mut $error = ::core::option::Option::None;
+        let mut $iter = $crate::parse::UntilError {
+            iter: &mut $iter,
+            error: &mut $error,
+        };
+    };
+
+    ({} @check $error:ident) => {};
+    ({fallible} @check $error:ident) => {
+        if let ::core::option::Option::Some(err) = $error {
+            return ::core::result::Result::Err(::core::convert::From::from(err));
+        }
+    };
+}
+
+/// Recursive helper macro. This replaces occurances of `parse!()` with
+/// `$crate::_parse` and provides it the additional required arguments
+///
+/// The `flags` argument corresponds to the flags given to [`argtea_impl`] (before
+/// the `impl`), and the `fields` argument corresponds to the fields of the struct given to
+/// [`argtea_impl`] (`{}` if there isn't one). The `frames` argument is a stack of the groups
+/// that are being scanned (`[]` at the top level of the function).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _scan_body {
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        parse!($iter:ident $(, $($options:tt)*)?)
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {
+                $($already_parsed)*
+                $crate::_parse!(
+                    {$($($options)*)?} $iter => $flags
+                )
+            }
+            $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        parse!($expr:expr $(, $($options:tt)*)?)
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {
+                $($already_parsed)*
+                $crate::_parse!(
+                    {$($($options)*)?} let __argtea_args = $expr => $flags
+                )
+            }
+            $($rem)*
+        }
+    };
+
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        parse! $args:tt
+        $($rem:tt)*
+    } => {
+        compile_error!("Invalid arguments to `parse!()` expected `parse!($expr)`, optionally followed by options (eg `, index: $ident`)")
+    };
+
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        parse_fields!($expr:expr $(, $($options:tt)*)?)
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {
+                $($already_parsed)*
+                $crate::_parse_fields!($flags $fields $expr $(, $($options)*)?)
+            }
+            $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        parse_fields! $args:tt
+        $($rem:tt)*
+    } => {
+        compile_error!("Invalid arguments to `parse_fields!()` expected `parse_fields!($expr)`, optionally followed by options (eg `, index: $ident`)")
+    };
+
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        build!()
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {
+                $($already_parsed)*
+                $crate::_build!($fields)
+            }
+            $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        build! $args:tt
+        $($rem:tt)*
+    } => {
+        compile_error!("`build!()` doesn't take any arguments")
+    };
+
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        parse_events!($iter:ident, $sink:expr $(, $($options:tt)*)?)
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {
+                $($already_parsed)*
+                $crate::_parse!(
+                    {$($($options)*)?} $iter, $sink => $flags
+                )
+            }
+            $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        parse_events!($expr:expr, $sink:expr $(, $($options:tt)*)?)
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {
+                $($already_parsed)*
+                $crate::_parse!(
+                    {$($($options)*)?} let __argtea_args = $expr, $sink => $flags
+                )
+            }
             $($rem)*
         }
     };
 
     {
-        $flags:tt
+        $flags:tt $fields:tt $frames:tt
         {$($already_parsed:tt)*}
-        parse! $args:tt
+        parse_events! $args:tt
+        $($rem:tt)*
+    } => {
+        compile_error!("Invalid arguments to `parse_events!()` expected `parse_events!($expr, $sink)`, optionally followed by options (eg `, index: $ident`)")
+    };
+
+    // Groups are scanned after saving the tokens around them in a frame, so `parse!()` can be used
+    // anywhere (eg in `if` blocks, `match` arms, or closures)
+    {
+        $flags:tt $fields:tt [$($frames:tt)*]
+        {$($already_parsed:tt)*}
+        {$($group:tt)*}
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields [(brace {$($already_parsed)*} {$($rem)*}) $($frames)*]
+            {}
+            $($group)*
+        }
+    };
+    {
+        $flags:tt $fields:tt [$($frames:tt)*]
+        {$($already_parsed:tt)*}
+        ($($group:tt)*)
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields [(paren {$($already_parsed)*} {$($rem)*}) $($frames)*]
+            {}
+            $($group)*
+        }
+    };
+    {
+        $flags:tt $fields:tt [$($frames:tt)*]
+        {$($already_parsed:tt)*}
+        [$($group:tt)*]
         $($rem:tt)*
     } => {
-        compile_error!("Invalid arguments to `parse!()` expected `parse!($expr)`")
+        $crate::_scan_body!{
+            $flags $fields [(bracket {$($already_parsed)*} {$($rem)*}) $($frames)*]
+            {}
+            $($group)*
+        }
     };
 
+    // Other tokens are moved two at a time (unless the second one needs to be scanned), which
+    // halves the recursion depth that long functions need
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        $expr:tt $name:ident!
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {$($already_parsed)* $expr}
+            $name! $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        $expr:tt {$($group:tt)*}
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {$($already_parsed)* $expr}
+            {$($group)*} $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        $expr:tt ($($group:tt)*)
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {$($already_parsed)* $expr}
+            ($($group)*) $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        $expr:tt [$($group:tt)*]
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {$($already_parsed)* $expr}
+            [$($group)*] $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt $frames:tt
+        {$($already_parsed:tt)*}
+        $first:tt $second:tt
+        $($rem:tt)*
+    } => {
+        $crate::_scan_body!{
+            $flags $fields $frames
+            {$($already_parsed)* $first $second}
+            $($rem)*
+        }
+    };
     {
-        $flags:tt
+        $flags:tt $fields:tt $frames:tt
         {$($already_parsed:tt)*}
         $expr:tt
         $($rem:tt)*
     } => {
         $crate::_scan_body!{
-            $flags
+            $flags $fields $frames
             {$($already_parsed)* $expr }
             $($rem)*
         }
     };
-    {$flags:tt {$($already_parsed:tt)*}} => {$($already_parsed)*};
+
+    // The end of a group
+    {
+        $flags:tt $fields:tt [(brace {$($before:tt)*} {$($rem:tt)*}) $($frames:tt)*]
+        {$($already_parsed:tt)*}
+    } => {
+        $crate::_scan_body!{
+            $flags $fields [$($frames)*]
+            {$($before)* {$($already_parsed)*}}
+            $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt [(paren {$($before:tt)*} {$($rem:tt)*}) $($frames:tt)*]
+        {$($already_parsed:tt)*}
+    } => {
+        $crate::_scan_body!{
+            $flags $fields [$($frames)*]
+            {$($before)* ($($already_parsed)*)}
+            $($rem)*
+        }
+    };
+    {
+        $flags:tt $fields:tt [(bracket {$($before:tt)*} {$($rem:tt)*}) $($frames:tt)*]
+        {$($already_parsed:tt)*}
+    } => {
+        $crate::_scan_body!{
+            $flags $fields [$($frames)*]
+            {$($before)* [$($already_parsed)*]}
+            $($rem)*
+        }
+    };
+    {$flags:tt $fields:tt [] {$($already_parsed:tt)*}} => {$($already_parsed)*};
+}
+
+/// Helper macro; applies the options of `parse!()` (currently only `strict`) to the
+/// statements that parse the arguments.
+///
+/// Unused variables are allowed unless `strict` is given.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _lint_options {
+    ([] $($stmts:tt)*) => {
+        #[allow(unused_variables)]
+        $($stmts)*
+    };
+    ([strict] $($stmts:tt)*) => {
+        $($stmts)*
+    };
+    ([$option:ident] $($stmts:tt)*) => {
+        compile_error!(::core::concat!(
+            "Unknown option `",
+            ::core::stringify!($option),
+            "`, expected `strict`"
+        ));
+    };
+}
+
+/// Helper macro; corresponds to `parse_fields!(iter)`.
+///
+/// Declares a variable for each field, parses `iter`, and then constructs `Self` from the
+/// variables.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _parse_fields {
+    {
+        $flags:tt {} $($args:tt)*
+    } => {
+        compile_error!("`parse_fields!()` can only be used if the struct is defined inside of `argtea_impl!`")
+    };
+    {
+        $flags:tt {$($field:ident: $field_ty:ty $(= $init:expr)?),*} $expr:expr $(, $($options:tt)*)?
+    } => {{
+        $(
+            #[allow(unused_mut)]
+            let mut $field: $field_ty = $crate::_parse_fields!(@init $($init)?);
+        )*
+
+        let mut __argtea_args = $expr;
+        $crate::_parse!{{$($($options)*)?} __argtea_args => $flags};
+
+        Self {
+            $($field),*
+        }
+    }};
+
+    (@init) => { ::core::default::Default::default() };
+    (@init $init:expr) => { $init };
+}
+
+/// Helper macro; corresponds to `build!()`.
+///
+/// Constructs `Self` from the variables with the same names as the fields. Each variable is either
+/// of the field's type or an `Option` of it, in which case `None` becomes the field's default
+/// value (see [`Field`]).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _build {
+    {
+        {}
+    } => {
+        compile_error!("`build!()` can only be used if the struct is defined inside of `argtea_impl!`")
+    };
+    {
+        {$($field:ident: $field_ty:ty $(= $init:expr)?),*}
+    } => {
+        {
+            #[allow(unused_imports)]
+            use $crate::parse::IntoField as _;
+
+            Self {
+                $(
+                    $field: $crate::parse::Field::<$field_ty, _>($field, ::core::marker::PhantomData)
+                        .into_field(|| $crate::_parse_fields!(@init $($init)?)),
+                )*
+            }
+        }
+    };
+}
+
+/// Helper macro; runs the `#[eager]` flags before anything else is parsed.
+///
+/// If there are any `#[eager]` flags, `$iter` is collected and the arguments before the first
+/// `--` are scanned for eager flags. The arguments are split exactly like `parse!()` splits them:
+/// the other flags take their values without running (so `-ohelp` doesn't run `-h` if `-o` takes
+/// a value), and eager flags take theirs from the collected arguments. Afterwards, `$iter` is
+/// shadowed by an iterator over the collected arguments, and `$end` is the index of the first
+/// argument that wasn't scanned (see [`_skip_eager`]).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _eager_prescan {
+    // Checks whether any flag is eager. The first argument is the list of all of the flags'
+    // attributes, and each flag starts with its own list of attributes (see `@branch`).
+    {$iter:ident $sink:tt $index:tt $names:tt [#[eager] $($attrs:tt)*] $flags:tt} => {
+        $crate::_eager_prescan!{@scan $iter $sink $index $names $flags}
+    };
+    {$iter:ident $sink:tt $index:tt $names:tt [#[$other:ident] $($attrs:tt)*] $flags:tt} => {
+        $crate::_eager_prescan!{$iter $sink $index $names [$($attrs)*] $flags}
+    };
+    {$iter:ident $sink:tt $index:tt $names:tt [] $flags:tt} => {};
+
+    (@match $iter:ident $flag:ident $sink:tt $($attrs:tt [$(pass #[$pass:meta])*] $all_attrs:tt ($($pat:tt)+) [$(if $guard:expr)?] => $block:block)*) => {
+        #[allow(unreachable_patterns)]
+        match (&*$flag, &*$flag) {
+            $(
+                $(#[$pass])*
+                $crate::_create_branch_pat!(($($pat)+)) $(if $guard)? => $crate::_eager_prescan!(@branch $attrs $iter $flag $sink $all_attrs ($($pat)+) => $block),
+            )*
+            _ => {}
+        }
+    };
+
+    // Eager flags run, and the other flags only take their values. The first argument is the list
+    // of attributes that haven't been checked yet.
+    (@branch [#[eager] $($attrs:tt)*] $iter:ident $flag:ident $sink:tt $all_attrs:tt $pat:tt => $block:block) => {
+        $crate::_create_branch!($iter $flag $sink $all_attrs $pat => $block)
+    };
+    (@branch [#[$other:ident] $($attrs:tt)*] $($branch:tt)*) => {
+        $crate::_eager_prescan!(@branch [$($attrs)*] $($branch)*)
+    };
+    (@branch [] $iter:ident $flag:ident $sink:tt $all_attrs:tt $pat:tt => $block:block) => {
+        $crate::_skip_values!($all_attrs $iter $pat)
+    };
+
+    {@scan $iter:ident $sink:tt {$($index:ident)?} [$aliases:ident $end:ident $label:lifetime] {$($flags:tt)*}} => {
+        let __argtea_eager_args: ::std::vec::Vec<::std::string::String> =
+            ::core::iter::Iterator::collect(::core::iter::Iterator::by_ref(&mut $iter));
+
+        // The index of the argument after the first `--` (the arguments after it are only parsed
+        // normally)
+        let mut $end = usize::MAX;
+
+        {
+            let __argtea_scanned = ::core::cell::Cell::new(0);
+            let mut __argtea_args = $crate::parse::NormalizeArgs::new(::core::iter::Iterator::inspect(
+                ::core::iter::Iterator::cloned(__argtea_eager_args.iter()),
+                |_| __argtea_scanned.set(__argtea_scanned.get() + 1),
+            ));
+
+            // Eager flags can use `'stop_parsing` too (which only stops the scan, like `break`)
+            #[allow(unused_labels)]
+            $label:
+            while let Some(flag) = ::core::iter::Iterator::next(&mut __argtea_args) {
+                #[allow(unused_variables)]
+                let __argtea_arg_index = __argtea_scanned.get() - 1;
+                let flag = ::std::borrow::Cow::<str>::from(flag);
+
+                // A `--` from a cluster (eg `-v--`) shares its argument with flags that were
+                // already scanned
+                if flag == "--" {
+                    $end = __argtea_arg_index + 1;
+                    break;
+                }
+
+                // Unused if no flags take parameters
+                #[allow(unused_mut, unused_variables)]
+                let mut $iter = $crate::parse::Values {
+                    args: &mut __argtea_args,
+                    flags: $aliases,
+                };
+
+                $(let $index: usize = __argtea_arg_index;)?
+
+                $crate::_eager_prescan!(@match $iter flag $sink $($flags)*);
+            }
+        }
+
+        let mut $iter = ::core::iter::IntoIterator::into_iter(__argtea_eager_args);
+    };
+}
+
+/// Helper macro; expands to `{}` for `#[eager]` flags (which already ran
+/// before parsing) and to [`_create_branch`] otherwise.
+///
+/// The first argument is the list of attributes that haven't been checked yet. Eager flags before
+/// the first `--` (`$index < $end`) only take their values, which the prescan already used, and
+/// eager flags after it run like other flags.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _skip_eager {
+    ([#[eager] $($attrs:tt)*] [$index:ident $end:ident] $iter:ident $string:ident $sink:tt $all_attrs:tt $pat:tt => $block:block) => {
+        if $index < $end {
+            $crate::_skip_values!($all_attrs $iter $pat)
+        } else {
+            $crate::_create_branch!($iter $string $sink $all_attrs $pat => $block)
+        }
+    };
+    ([#[$attr:ident] $($attrs:tt)*] $($branch:tt)*) => {
+        $crate::_skip_eager!([$($attrs)*] $($branch)*)
+    };
+    ([] $indices:tt $($branch:tt)*) => {
+        $crate::_create_branch!($($branch)*)
+    };
+}
+
+/// Helper macro; takes the values of an eager flag's parameters from `$iter` without using them
+/// (used by [`_skip_eager`]).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _skip_values {
+    // The bindings of the branch's pattern are used, so they don't cause `unused_variables`
+    // warnings (even if the branch has `#[deny(unused_variables)]`)
+    ($attrs:tt $iter:ident ($ident:ident)) => {{
+        let _ = $ident;
+    }};
+    ($attrs:tt $iter:ident ($($flag_var:ident @)? $($flag:literal)|+ $(, $($params:tt)*)?)) => {{
+        $(let _ = $flag_var;)?
+        $crate::_skip_values!(@params $attrs $iter $($($params)*)?);
+    }};
+    (@params $attrs:tt $iter:ident) => {};
+    (
+        @params $attrs:tt $iter:ident
+        $param:tt $(in [$($choice:literal),* $(,)?] $($ci:ident)?)? $(: $ty:ty)? $(, $($rem:tt)*)?
+    ) => {
+        let _ = $crate::_next_value!($attrs $iter);
+        $crate::_skip_values!(@params $attrs $iter $($($rem)*)?);
+    };
 }
 
 #[doc(hidden)]
@@ -289,33 +1858,248 @@ macro_rules! _create_branch_pat {
     {
         ($ident:ident)
     } => {
-        _
+        (_, $ident)
     };
     {
-        ($flag_var:ident @ $($flag:literal)|+ $(, $param:ident)* $(,)? )
+        ($flag_var:ident @ $($flag:literal)|+ $(, $param:ident $(in $choices:tt $($ci:ident)?)? $(: $ty:ty)?)* $(,)? )
     } => {
-        $($flag_var @ $flag)|+
+        ($($flag_var @ $flag)|+, _)
     };
     {
-        ($($flag:literal)|+ $(, $param:ident)* $(,)? )
+        ($($flag:literal)|+ $(, $param:ident $(in $choices:tt $($ci:ident)?)? $(: $ty:ty)?)* $(,)? )
     } => {
-        $($flag)|+
+        ($($flag)|+, _)
+    };
+}
+
+/// Helper macro; expands to the aliases of a flag (eg `&["--output", "-o"]`), or `&[]` for the
+/// positional flag.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _flag_aliases {
+    (($ident:ident)) => {
+        &[]
+    };
+    (($($flag_var:ident @)? $($flag:literal)|+ $(, $($params:tt)*)?)) => {
+        &[$($flag),+]
     };
 }
 
 #[doc(hidden)]
 #[macro_export]
 macro_rules! _create_branch{
+    // Parameters are captured as `tt`s and bound only once, so lints like `unused_variables`
+    // point to the parameter in the user's code (and aren't ignored as coming from a macro)
     {
-        $iter:ident $string:ident ($ident:ident) => $block:block
+        $iter:ident $string:ident $sink:tt $attrs:tt ($($flag_var:ident @)? $($flag:literal)|+ $(, $($params:tt)*)?) => $block:block
     } => {{
-        let $ident = $string.into_owned();
-        $block
+        $crate::_emit_event!($sink $crate::Event::Flag { name: ::std::string::ToString::to_string(&*$string) });
+        $crate::_create_branch!(@params $iter $string $sink $attrs $block $($($params)*)?)
     }};
     {
-        $iter:ident $string:ident ($($flag_var:ident @)? $($flag:literal)|+ $(, $param:ident)* $(,)? ) => $block:block
+        $iter:ident $string:ident $sink:tt $attrs:tt ($ident:tt) => $block:block
     } => {{
-        $(let $param = $iter.next();)*
+        // `$ident` is also bound to the argument by the branch's pattern (for its guard)
+        let _: &str = $ident;
+        let value = $string.into_owned();
+        $crate::_check_positional!($attrs $iter value);
+        $crate::_emit_event!($sink $crate::Event::Positional(::std::clone::Clone::clone(&value)));
+        let $ident = $crate::_positional_value!($attrs value);
+        $block
+    }};
+
+    // Binds the parameters one at a time, so `param: Option<T>` can be told apart from other types
+    // (`std::option::Option<T>` and `core::option::Option<T>` are written as `Option<T>`)
+    {@params $iter:ident $string:ident $sink:tt $attrs:tt $block:block} => {
         $block
+    };
+    {
+        @params $iter:ident $string:ident $sink:tt $attrs:tt $block:block
+        $param:tt : Option<$ty:ty> $(, $($rem:tt)*)?
+    } => {{
+        let value = $crate::_next_value!($attrs $iter);
+        $crate::_emit_event!($sink @value $string value);
+        let $param = $crate::_convert_param!($attrs $string $param value [] [optional $ty]);
+        $crate::_create_branch!(@params $iter $string $sink $attrs $block $($($rem)*)?)
+    }};
+    {
+        @params $iter:ident $string:ident $sink:tt $attrs:tt $block:block
+        $param:tt : $(::)? std::option::Option<$ty:ty> $(, $($rem:tt)*)?
+    } => {
+        $crate::_create_branch!(@params $iter $string $sink $attrs $block $param: Option<$ty> $(, $($rem)*)?)
+    };
+    {
+        @params $iter:ident $string:ident $sink:tt $attrs:tt $block:block
+        $param:tt : $(::)? core::option::Option<$ty:ty> $(, $($rem:tt)*)?
+    } => {
+        $crate::_create_branch!(@params $iter $string $sink $attrs $block $param: Option<$ty> $(, $($rem)*)?)
+    };
+    {
+        @params $iter:ident $string:ident $sink:tt $attrs:tt $block:block
+        $param:tt $(in [$($choice:literal),* $(,)?] $($ci:ident)?)? $(: $ty:ty)? $(, $($rem:tt)*)?
+    } => {{
+        let value = $crate::_next_value!($attrs $iter);
+        $crate::_emit_event!($sink @value $string value);
+        let $param = $crate::_convert_param!($attrs $string $param value [$(in [$($choice),*] $($ci)?)?] [$($ty)?]);
+        $crate::_create_branch!(@params $iter $string $sink $attrs $block $($($rem)*)?)
     }};
 }
+
+/// Helper macro; converts `value` (the value of the parameter `param`) if the
+/// parameter is typed (`width: u32`) or has choices (`color in ["auto", "never"]`). Parameters
+/// typed `Option<T>` are passed `[optional T]` as their type, and are `None` if the value is
+/// missing. On failure, the enclosing function returns the [`ParseError`](crate::ParseError).
+///
+/// The first argument is the list of the flag's attributes. The parameters of
+/// `#[optional_value]` flags are wrapped in a [`FlagState`](crate::FlagState).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _convert_param {
+    ([#[optional_value] $($attrs:tt)*] $string:ident $param:tt $value:ident [] []) => {
+        $crate::FlagState::from($value)
+    };
+    ([#[optional_value] $($attrs:tt)*] $string:ident $param:tt $value:ident [] [optional $ty:ty]) => {
+        compile_error!(::core::concat!(
+            "The parameters of `#[optional_value]` flags are already optional, use `",
+            ::core::stringify!($param: $ty),
+            "` instead"
+        ))
+    };
+    ([#[optional_value] $($attrs:tt)*] $string:ident $param:tt $value:ident $choices:tt $ty:tt) => {
+        match $value {
+            ::core::option::Option::Some(value) => {
+                let value = ::core::option::Option::Some(value);
+                $crate::FlagState::SetWith($crate::_convert_param!([] $string $param value $choices $ty))
+            }
+            ::core::option::Option::None => $crate::FlagState::Set,
+        }
+    };
+    ([#[$attr:ident] $($attrs:tt)*] $($rem:tt)*) => {
+        $crate::_convert_param!([$($attrs)*] $($rem)*)
+    };
+    ([] $string:ident $param:tt $value:ident [] []) => {
+        $value
+    };
+    ([] $string:ident $param:tt $value:ident [in [$($choice:literal),*] $($ci:ident)?] []) => {
+        match $crate::parse::parse_choice(&$string, ::core::stringify!($param), &[$($choice),*], $crate::_ignore_case!($($ci)?), $value) {
+            ::core::result::Result::Ok(value) => value,
+            ::core::result::Result::Err(err) => return ::core::result::Result::Err(::core::convert::From::from(err)),
+        }
+    };
+    ([] $string:ident $param:tt $value:ident [] [optional $ty:ty]) => {
+        match $value {
+            ::core::option::Option::Some(value) => {
+                let value = ::core::option::Option::Some(value);
+                ::core::option::Option::Some($crate::_convert_param!([] $string $param value [] [$ty]))
+            }
+            ::core::option::Option::None => ::core::option::Option::None,
+        }
+    };
+    ([] $string:ident $param:tt $value:ident [] [$ty:ty]) => {
+        match $crate::parse::parse_value::<$ty>(&$string, ::core::stringify!($param), ::core::stringify!($ty), $value) {
+            ::core::result::Result::Ok(value) => value,
+            ::core::result::Result::Err(err) => return ::core::result::Result::Err(::core::convert::From::from(err)),
+        }
+    };
+    ([] $string:ident $param:tt $value:ident [in $choices:tt] [$ty:ty]) => {
+        compile_error!(::core::concat!("Parameter `", ::core::stringify!($param), "` cannot have both a type and a list of choices"))
+    };
+}
+
+/// Helper macro; handles the `ci` (case-insensitive) modifier of choice
+/// parameters (`color in ["auto", "never"] ci`).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _ignore_case {
+    () => {
+        false
+    };
+    (ci) => {
+        true
+    };
+    ($modifier:ident) => {
+        compile_error!(::core::concat!(
+            "Unknown modifier `",
+            ::core::stringify!($modifier),
+            "`, expected `ci`"
+        ))
+    };
+}
+
+/// Helper macro; takes the value of a flag's parameter from `$iter`.
+///
+/// The first argument is the list of the flag's attributes.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _next_value {
+    ([] $iter:ident) => {
+        $iter.next()
+    };
+    ([#[strict_value] $($attrs:tt)*] $iter:ident) => {
+        $iter.next_strict()
+    };
+    ([#[optional_value] $($attrs:tt)*] $iter:ident) => {
+        $iter.next_inline()
+    };
+    ([#[$attr:ident] $($attrs:tt)*] $iter:ident) => {
+        $crate::_next_value!([$($attrs)*] $iter)
+    };
+}
+
+/// Helper macro; rejects a positional `value` that looks like a flag if the positional flag is
+/// annotated with `#[strict_value]`.
+///
+/// The first argument is the list of the flag's attributes.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _check_positional {
+    ([] $iter:ident $value:ident) => {};
+    ([#[strict_value] $($attrs:tt)*] $iter:ident $value:ident) => {
+        if $value.starts_with('-') && $value != "-" {
+            return ::core::result::Result::Err(::core::convert::From::from(
+                $crate::parse::unknown_flag($value, $iter.flags),
+            ));
+        }
+    };
+    ([#[$attr:ident] $($attrs:tt)*] $iter:ident $value:ident) => {
+        $crate::_check_positional!([$($attrs)*] $iter $value)
+    };
+}
+
+/// Helper macro; splits a positional `value` into a key and a value if the positional flag is
+/// annotated with `#[key_value]`.
+///
+/// The first argument is the list of the flag's attributes.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _positional_value {
+    ([] $value:ident) => {
+        $value
+    };
+    ([#[key_value] $($attrs:tt)*] $value:ident) => {
+        $crate::parse::split_key_value($value)
+    };
+    ([#[$attr:ident] $($attrs:tt)*] $value:ident) => {
+        $crate::_positional_value!([$($attrs)*] $value)
+    };
+}
+
+/// Helper macro; passes an [`Event`] to the sink given to `parse_events!()`.
+///
+/// Does nothing when `parse!()` is used.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _emit_event {
+    ({} $($tt:tt)*) => {};
+    ({$sink:ident} @value $string:ident $param:ident) => {
+        if let ::core::option::Option::Some(value) = &$param {
+            $sink($crate::Event::Value {
+                flag: ::std::string::ToString::to_string(&*$string),
+                value: ::std::clone::Clone::clone(value),
+            });
+        }
+    };
+    ({$sink:ident} $event:expr) => {
+        $sink($event);
+    };
+}
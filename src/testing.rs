@@ -0,0 +1,174 @@
+//! Helpers for testing argtea parsers.
+//!
+//! [`args`] turns a list of `&str`s into arguments, [`assert_parse!`](crate::assert_parse) parses
+//! them and checks the result (printing the arguments if the check fails), and
+//! [`assert_help_snapshot!`](crate::assert_help_snapshot) compares a help message with a file:
+//! ```rust
+//! # use argtea::{argtea_impl, assert_parse};
+//! # struct Arguments { output: Option<String> }
+//! argtea_impl! {
+//!     {
+//!         /// Sets the output file.
+//!         ("--output" | "-o", path) => {
+//!             output = path;
+//!         }
+//!         # (_file) => {}
+//!     }
+//!     impl Arguments {
+//!         fn parse(args: Vec<String>) -> Self {
+//!             let mut output = None;
+//!             parse!(args.into_iter());
+//!
+//!             Self { output }
+//!         }
+//!     }
+//! }
+//!
+//! assert_parse!(Arguments::parse, ["-o", "out"], |args: Arguments| {
+//!     assert_eq!(args.output.as_deref(), Some("out"));
+//! });
+//! ```
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// Converts a list of arguments (eg `&["-W", "all"]`) to the `String`s that argtea functions
+/// parse.
+/// ```rust
+/// assert_eq!(argtea::testing::args(&["-W", "all"]), ["-W", "all"]);
+/// ```
+pub fn args<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> Vec<String> {
+    args.into_iter()
+        .map(|arg| arg.as_ref().to_owned())
+        .collect()
+}
+
+/// Parses `args` (see [`testing::args`](crate::testing::args)) with a function that takes a
+/// `Vec<String>` (eg `TestA::parse`), and then calls `check` with the result.
+///
+/// If parsing or `check` panics (eg because of a failed assertion), the arguments are printed, so
+/// failures in tables of test cases can be told apart:
+/// ```rust,should_panic
+/// # use argtea::assert_parse;
+/// fn parse(args: Vec<String>) -> usize {
+///     args.len()
+/// }
+///
+/// // The panic message is followed by `note: the arguments were ["-W", "all"]`
+/// assert_parse!(parse, ["-W", "all"], |count| assert_eq!(count, 1));
+/// ```
+#[macro_export]
+macro_rules! assert_parse {
+    ($parse:expr, $args:expr, $check:expr $(,)?) => {{
+        let __argtea_args = $crate::testing::args($args);
+        let __argtea_guard = $crate::testing::ArgsGuard(&__argtea_args);
+
+        ($check)(($parse)(::core::clone::Clone::clone(&__argtea_args)));
+
+        ::core::mem::drop(__argtea_guard);
+    }};
+}
+
+/// Compares a help message (or any other generated text) with the contents of a file, whose path
+/// is relative to the current file (like [`include_str!`]).
+///
+/// Instead of printing both versions, a mismatch only shows the first line that differs. When the
+/// `UPDATE_SNAPSHOTS` environment variable is set, the actual output is also written next to the
+/// file (eg `tests/help.txt.new` for `tests/help.txt`), so it can be compared with a diff tool
+/// and then moved over the old snapshot.
+#[macro_export]
+macro_rules! assert_help_snapshot {
+    ($actual:expr, $path:literal $(,)?) => {
+        $crate::testing::check_snapshot(
+            ::core::convert::AsRef::<str>::as_ref(&$actual),
+            ::core::include_str!($path),
+            $crate::testing::snapshot_path(
+                ::core::env!("CARGO_MANIFEST_DIR"),
+                ::core::file!(),
+                $path,
+            ),
+        )
+    };
+}
+
+/// Prints the arguments of an [`assert_parse!`](crate::assert_parse) when it is dropped during a
+/// panic.
+#[doc(hidden)]
+pub struct ArgsGuard<'a>(pub &'a [String]);
+
+impl Drop for ArgsGuard<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            eprintln!("note: the arguments were {:?}", self.0);
+        }
+    }
+}
+
+/// Finds the file at `path` relative to `file` (from `file!()`) for
+/// [`assert_help_snapshot!`](crate::assert_help_snapshot).
+///
+/// `file!()` is relative to the workspace's directory, which is the package's directory or one of
+/// its ancestors.
+#[doc(hidden)]
+pub fn snapshot_path(manifest_dir: &str, file: &str, path: &str) -> PathBuf {
+    let file = Path::new(manifest_dir)
+        .ancestors()
+        .map(|dir| dir.join(file))
+        .find(|file| file.exists())
+        .unwrap_or_else(|| PathBuf::from(file));
+
+    file.parent().unwrap_or(Path::new("")).join(path)
+}
+
+/// The implementation of [`assert_help_snapshot!`](crate::assert_help_snapshot).
+#[doc(hidden)]
+#[track_caller]
+pub fn check_snapshot(actual: &str, expected: &str, path: PathBuf) {
+    if actual == expected {
+        return;
+    }
+
+    let mut actual_lines = actual.split('\n');
+    let mut expected_lines = expected.split('\n');
+
+    let mut line = 1;
+    let (actual_line, expected_line) = loop {
+        match (actual_lines.next(), expected_lines.next()) {
+            (Some(actual), Some(expected)) if actual == expected => line += 1,
+            lines => break lines,
+        }
+    };
+
+    let update = if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        let mut new_path = path.clone().into_os_string();
+        new_path.push(".new");
+        let new_path = PathBuf::from(new_path);
+
+        match std::fs::write(&new_path, actual) {
+            Ok(()) => format!("the actual output was written to `{}`", new_path.display()),
+            Err(err) => format!("couldn't write `{}`: {err}", new_path.display()),
+        }
+    } else {
+        "set `UPDATE_SNAPSHOTS` to write the actual output next to it".to_owned()
+    };
+
+    panic!(
+        "snapshot `{}` doesn't match (first difference on line {line})\n  \
+         expected: {}\n  \
+         actual:   {}\n\
+         {update}",
+        path.display(),
+        describe_line(expected_line),
+        describe_line(actual_line),
+    );
+}
+
+/// Formats a line for [`check_snapshot`]'s panic message.
+fn describe_line(line: Option<&str>) -> String {
+    match line {
+        Some(line) => format!("{line:?}"),
+        None => "<end of file>".to_owned(),
+    }
+}
@@ -1,4 +1,19 @@
-use crate::argtea_impl;
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use crate::{
+    argtea_impl, color_format_with, completions,
+    help::{
+        self, to_markdown, usage_line, usage_line_with, use_color, write_help, ColumnRenderer,
+        FlagContext, MarkdownStyle, Section, WrappingRenderer,
+    },
+    parse::FlagView,
+    parse_dynamic, render_flags, testing, wrapping_format, wrapping_format_with, AliasStyle, Event,
+    Flag, FlagSpec, FlagState, HelpOptions, HelpRenderer, Matches, ParamStyle, ParseError,
+    Placement, SortOrder, Undocumented,
+};
 
 struct TestA {
     pub warning: Option<String>,
@@ -49,6 +64,8 @@ argtea_impl! {
     }
 
     impl TestA {
+        pub const FLAGS: &'static [Flag] = docs!();
+
         #[allow(unused)]
         pub const DOCS: &'static str = crate::simple_format!(
             "argtea test A"
@@ -56,6 +73,14 @@ argtea_impl! {
             "end"
         );
 
+        #[allow(unused)]
+        pub const STYLED_DOCS: &'static str = crate::simple_format!(
+            { flag_indent: "    ", doc_indent: "        ", doc_prefix: "•" }
+            "argtea test A"
+            docs!()
+            "end"
+        );
+
         /// H
         #[export_name = "TestA_parse"]
         extern "Rust" fn parse(params: Vec<String>) -> TestA {
@@ -81,17 +106,105 @@ fn test_a() {
         (&["--warning=all"], "all"),
         (&["-Wall"], "all"),
         (&["-aWall"], "all"),
+        (&["-Wno-error"], "no-error"),
+        (&["-aWno-error"], "no-error"),
         (&["-f", "abc"], "-f abc"),
         (&["-fabc"], "-f abc"),
         (&["--weird-flag", "def"], "--weird-flag def"),
     ];
 
-    for (test, expected) in tests {
-        let args: Vec<String> = test.iter().map(|a| a.to_string()).collect();
+    for &(test, expected) in tests {
+        crate::assert_parse!(TestA::parse, test, |result: TestA| {
+            assert_eq!(result.warning.as_deref(), Some(expected));
+        });
+    }
+}
+
+trait Backend {
+    const NAME: &'static str;
+}
+
+struct Native;
+struct Sandbox;
+
+impl Backend for Native {
+    const NAME: &'static str = "native";
+}
+
+impl Backend for Sandbox {
+    const NAME: &'static str = "sandbox";
+}
+
+struct TestGenericImpl<T> {
+    warning: Option<String>,
+    backend: std::marker::PhantomData<T>,
+}
+
+argtea_impl! {
+    {
+        ("--warning" | "-W", warning) => {
+            warning_ = warning;
+        }
+
+        (flag_name @ "--weird-flag" | "-f", p) => {
+            warning_ = p.map(|p| format!("{flag_name} {p}"));
+        }
+
+        (other) => {
+            panic!("unexpected parameter `{other}`");
+        }
+    }
+
+    impl<T: Backend> TestGenericImpl<T>
+    where
+        T: 'static,
+    {
+        const FLAGS: &'static [Flag] = docs!();
+
+        fn parse(params: Vec<String>) -> Self {
+            let mut warning_ = None;
+
+            parse!(params.into_iter());
+
+            Self {
+                warning: warning_,
+                backend: std::marker::PhantomData,
+            }
+        }
+
+        fn backend(&self) -> &'static str {
+            T::NAME
+        }
+    }
+}
+
+#[test]
+fn test_generic_impl() {
+    fn check<T: Backend + 'static>(name: &str) {
+        let tests: &[(&[&str], &str)] = &[
+            (&["-W", "all"], "all"),
+            (&["--warning=all"], "all"),
+            (&["-Wall"], "all"),
+            (&["-fabc"], "-f abc"),
+            (&["--weird-flag", "def"], "--weird-flag def"),
+        ];
+
+        for &(test, expected) in tests {
+            crate::assert_parse!(
+                TestGenericImpl::<T>::parse,
+                test,
+                |result: TestGenericImpl<T>| {
+                    assert_eq!(result.warning.as_deref(), Some(expected));
+                    assert_eq!(result.backend(), name);
+                }
+            );
+        }
 
-        let result = TestA::parse(args);
-        assert_eq!(result.warning.as_deref(), Some(*expected));
+        assert_eq!(TestGenericImpl::<T>::FLAGS.len(), 3);
     }
+
+    check::<Native>("native");
+    check::<Sandbox>("sandbox");
 }
 
 #[test]
@@ -100,3 +213,5151 @@ fn test_a_docs() {
 
     assert_eq!(TestA::DOCS, EXPECTED);
 }
+
+#[test]
+fn test_a_styled_docs() {
+    crate::assert_help_snapshot!(TestA::STYLED_DOCS, "tests/test_a_styled.txt");
+}
+
+struct TestPlainFirstFlag;
+
+// The first flag has no attributes, so `_filter_fake_flags` starts without any state
+argtea_impl! {
+    {
+        ("--first") => {
+            log.push("first".to_owned());
+        }
+
+        #[fake]
+        ("--fake") => {}
+
+        (arg) => {
+            log.push(arg);
+        }
+    }
+
+    impl TestPlainFirstFlag {
+        const FLAGS: &'static [Flag] = docs!();
+
+        fn parse(args: &[&str]) -> Vec<String> {
+            let mut log = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            log
+        }
+    }
+}
+
+#[test]
+fn test_plain_first_flag() {
+    let flags: Vec<_> = TestPlainFirstFlag::FLAGS
+        .iter()
+        .map(|flag| flag.flags)
+        .collect();
+    assert_eq!(flags, [&["--first"][..], &["--fake"], &[]]);
+
+    assert_eq!(
+        TestPlainFirstFlag::parse(&["--first", "--fake"]),
+        ["first", "--fake"]
+    );
+}
+
+struct TestEvents;
+
+argtea_impl! {
+    {
+        ("--verbose" | "-v") => {}
+
+        ("--output" | "-o", output) => {}
+
+        #[strict_value]
+        ("--message" | "-m", message) => {}
+
+        ("-x") => {}
+
+        ("-😀") => {}
+
+        ("-ä") => {}
+
+        (file) => {}
+    }
+
+    impl TestEvents {
+        fn parse(params: Vec<String>) -> Vec<Event> {
+            let mut events = Vec::new();
+
+            parse_events!(params.into_iter(), &mut |event| events.push(event));
+
+            events
+        }
+    }
+}
+
+#[test]
+fn test_events() {
+    let args = testing::args(["-vo", "out", "file", "--output=a"]);
+
+    let flag = |name: &str| Event::Flag {
+        name: name.to_owned(),
+    };
+    let value = |flag: &str, value: &str| Event::Value {
+        flag: flag.to_owned(),
+        value: value.to_owned(),
+    };
+
+    assert_eq!(
+        TestEvents::parse(args),
+        [
+            flag("-v"),
+            flag("-o"),
+            value("-o", "out"),
+            Event::Positional("file".to_owned()),
+            flag("--output"),
+            value("--output", "a"),
+        ]
+    );
+}
+
+struct TestWrapping;
+
+argtea_impl! {
+    {
+        /// Sets the optimization level. This paragraph is long enough that it has to be wrapped
+        /// onto a second line.
+        ///
+        /// Higher levels take longer to compile:\
+        #[doc = " `-O0` is the default.  "]
+        /// The following levels are available:
+        /// * `0`: no optimizations at all. This list item is long enough that it has to be
+        ///   wrapped with a hanging indent.
+        /// * `1`: some optimizations
+        /// - `2`: dashes work too
+        /// 1. numbered items work too
+        ///
+        /// Example:
+        ///
+        ///     argtea_test -O 1 file.c
+        ///
+        /// ```
+        /// argtea_test -O0 file.c
+        ///   (indented)
+        /// ```
+        /// Prose continues after the example.
+        ("-O", level) => {}
+
+        (file) => {}
+    }
+
+    impl TestWrapping {
+        const FLAGS: &'static [Flag] = docs!();
+    }
+}
+
+#[test]
+fn test_wrapping_lists_and_code() {
+    let mut buf = String::new();
+    wrapping_format(&mut buf, TestWrapping::FLAGS);
+
+    crate::assert_help_snapshot!(buf, "tests/wrapping_lists.txt");
+}
+
+struct TestFlagless;
+
+argtea_impl! {
+    {
+        (file) => {
+            files.push(file);
+        }
+    }
+
+    impl TestFlagless {
+        const FLAGS: &'static [Flag] = docs!();
+        const HELP: &'static str = crate::simple_format!("Usage: test [FILES]" docs!());
+
+        fn parse(args: &[&str]) -> Vec<String> {
+            let mut files = Vec::new();
+            parse!(testing::args(args).into_iter());
+            files
+        }
+    }
+}
+
+#[test]
+fn test_flagless() {
+    assert_eq!(TestFlagless::FLAGS.len(), 1);
+    assert!(TestFlagless::FLAGS[0].flags.is_empty());
+    assert_eq!(TestFlagless::FLAGS[0].params, ["file"]);
+
+    // Only the positional heading is shown, and nothing at all without any flags
+    let mut buf = String::new();
+    wrapping_format(&mut buf, TestFlagless::FLAGS);
+    assert_eq!(buf, "Arguments:\n  <file>\n\n");
+
+    let mut buf = String::new();
+    wrapping_format(&mut buf, &[]);
+    assert_eq!(buf, "");
+
+    assert_eq!(TestFlagless::HELP, "Usage: test [FILES]\n  <file> \n\n\n");
+    assert_eq!(usage_line("test", TestFlagless::FLAGS), "test [<file>...]");
+
+    assert_eq!(TestFlagless::parse(&["a", "-b", "--c"]), ["a", "-b", "--c"]);
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct TestPositionalGuards {
+    sources: Vec<String>,
+    objects: Vec<String>,
+    other: Vec<String>,
+    verbose: bool,
+}
+
+argtea_impl! {
+    {
+        ("--verbose" | "-v") => {
+            parsed.verbose = true;
+        }
+
+        /// A C source file.
+        (src) if src.ends_with(".c") => {
+            parsed.sources.push(src);
+        }
+
+        /// An object file.
+        (obj) if obj.ends_with(".o") && !parsed.sources.is_empty() => {
+            parsed.objects.push(obj);
+        }
+
+        /// Any other argument.
+        (other) => {
+            parsed.other.push(other);
+        }
+    }
+
+    impl TestPositionalGuards {
+        const FLAGS: &'static [Flag] = docs!();
+
+        fn parse(args: &[&str]) -> Self {
+            let mut parsed = Self::default();
+            parse!(testing::args(args).into_iter());
+            parsed
+        }
+    }
+}
+
+#[test]
+fn test_positional_guards() {
+    let strings = |strs: &[&str]| strs.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        TestPositionalGuards::parse(&[
+            "early.o",
+            "a.c",
+            "-v",
+            "b.o",
+            "b.c",
+            "c.h",
+            "--bogus.c",
+            "--",
+            "d.o"
+        ]),
+        TestPositionalGuards {
+            sources: strings(&["a.c", "b.c", "--bogus.c"]),
+            objects: strings(&["b.o", "d.o"]),
+            other: strings(&["early.o", "c.h", "--"]),
+            verbose: true,
+        }
+    );
+
+    let params = TestPositionalGuards::FLAGS
+        .iter()
+        .map(|flag| (flag.params, flag.doc))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        params,
+        [
+            (&[][..], &[][..]),
+            (&["src"], &[" A C source file."]),
+            (&["obj"], &[" An object file."]),
+            (&["other"], &[" Any other argument."]),
+        ]
+    );
+}
+
+struct TestIncludedDocs;
+
+argtea_impl! {
+    {
+        /// Sets the output file.
+        ///
+        #[doc = include_str!("tests/included_docs.md")]
+        ("--output", path) => {}
+
+        #[doc = concat!(" Sets the ", "width.")]
+        ("--width", width) => {}
+    }
+
+    impl TestIncludedDocs {
+        const FLAGS: &'static [Flag] = docs!();
+        const HELP: &'static str = crate::simple_format!(docs!());
+    }
+}
+
+#[test]
+fn test_included_docs() {
+    let mut buf = String::new();
+    wrapping_format(&mut buf, TestIncludedDocs::FLAGS);
+    crate::assert_help_snapshot!(buf, "tests/included_docs.txt");
+
+    assert_eq!(
+        TestIncludedDocs::HELP,
+        concat!(
+            "  --output <path> \n    Sets the output file.\n   \n   ",
+            include_str!("tests/included_docs.md"),
+            "\n\n\n  --width <width> \n    Sets the width.\n\n\n"
+        )
+    );
+    assert_eq!(
+        TestIncludedDocs::FLAGS[1].first_sentence(),
+        "Sets the width"
+    );
+    assert_eq!(TestIncludedDocs::FLAGS[0].doc_lines().count(), 7);
+}
+
+struct TestTable;
+
+argtea_impl! {
+    {
+        /// Sets the output file path.
+        ///
+        /// This line isn't displayed by `table_format!`.
+        ("--output" | "-o", path) => {}
+
+        /// Sets the compression level of the output archive.
+        ("--compression-level" | "--compress" | "--level" | "-c" | "-l", algorithm, level) => {}
+
+        ("--quiet" | "-q") => {}
+
+        /// Displays this help message.
+        ("--help" | "-h") => {}
+
+        /// An input file.
+        (file) => {}
+    }
+
+    impl TestTable {
+        const HELP: &'static str = crate::table_format!(
+            "Usage: test [OPTIONS] [FILES]"
+            ""
+            docs!()
+            ""
+            concat!("test ", "1.0")
+        );
+
+        const NARROW_HELP: &'static str = crate::table_format!(
+            { gutter: 4, max_width: 16 }
+            docs!()
+        );
+    }
+}
+
+#[test]
+fn test_table_format() {
+    crate::assert_help_snapshot!(TestTable::HELP, "tests/table.txt");
+    crate::assert_help_snapshot!(TestTable::NARROW_HELP, "tests/table_narrow.txt");
+}
+
+struct TestDocSections;
+
+argtea_impl! {
+    {
+        /// Displays this help message.
+        #[section = "Common"]
+        ("--help" | "-h") => {}
+
+        /// Sets the output file path.
+        #[section = "Common"]
+        ("--output" | "-o", path) => {}
+
+        /// Prints debug information.
+        #[section = "Advanced"]
+        ("--debug") => {}
+
+        /// Sets the number of threads.
+        #[section = "Advanced"]
+        #[cfg(any())]
+        ("--threads", threads) => {}
+
+        /// Dumps the internal state.
+        #[hidden]
+        #[section = "Advanced"]
+        ("--dump") => {}
+
+        /// An input file.
+        (file) => {}
+    }
+
+    impl TestDocSections {
+        const HELP: &'static str = crate::table_format!(
+            "Usage: test [OPTIONS] [FILES]"
+            ""
+            "Common options:"
+            docs!(section = "Common")
+            ""
+            "Advanced options:"
+            docs!(section = "Advanced")
+            ""
+            concat!("test ", "1.0")
+        );
+
+        const HIDDEN_HELP: &'static str = crate::simple_format!("Hidden options:" docs!(hidden));
+
+        const ADVANCED: &'static [crate::Flag] = docs!(section = "Advanced");
+
+        const VISIBLE: &'static [crate::Flag] = docs!();
+        const ALL: &'static [crate::Flag] = docs!(all);
+    }
+}
+
+#[test]
+fn test_doc_sections() {
+    crate::assert_help_snapshot!(TestDocSections::HELP, "tests/sections_help.txt");
+    assert_eq!(
+        TestDocSections::HIDDEN_HELP,
+        "Hidden options:\n  --dump \n    Dumps the internal state.\n\n\n"
+    );
+
+    let advanced = TestDocSections::ADVANCED
+        .iter()
+        .map(|flag| (flag.flags, flag.section))
+        .collect::<Vec<_>>();
+    assert_eq!(advanced, [(&["--debug"][..], Some("Advanced"))]);
+
+    assert!(TestDocSections::VISIBLE
+        .iter()
+        .all(|flag| flag.flags != ["--dump"] && !flag.hidden));
+
+    let all = TestDocSections::ALL
+        .iter()
+        .map(|flag| (flag.flags, flag.hidden))
+        .collect::<Vec<_>>();
+    assert_eq!(all.len(), TestDocSections::VISIBLE.len() + 1);
+    assert!(all.contains(&(&["--dump"], true)));
+}
+
+#[test]
+fn test_flag_new() {
+    let dump = Flag::new(&[" Dumps the state."], &["--dump"], &["path"]);
+    assert!(!dump.hidden);
+    assert!(dump.possible_values.is_empty() && dump.examples.is_empty());
+
+    let hidden_dump = Flag::new(dump.doc, dump.flags, dump.params).with_hidden(true);
+    assert!(hidden_dump.hidden);
+    assert_eq!(hidden_dump.to_string(), dump.to_string());
+}
+
+struct TestStandardHelp;
+
+argtea_impl! {
+    {
+        /// Displays the version.
+        ("--version" | "-V") => {}
+    }
+
+    impl TestStandardHelp {
+        const HELP: &'static str = crate::standard_help!(usage: "argtea [OPTIONS]" docs!());
+
+        const INDENTED_HELP: &'static str = crate::standard_help!(
+            { flag_indent: "    " }
+            usage: "argtea [OPTIONS]"
+            docs!()
+            ""
+            "See the README for more information."
+        );
+    }
+}
+
+#[test]
+fn test_standard_help() {
+    let preamble = format!(
+        "argtea {}\nDeclarative macro commandline parser (inspired by argwerk)\n\n\
+         Usage: argtea [OPTIONS]\n\nOptions:\n",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    assert_eq!(
+        TestStandardHelp::HELP,
+        format!("{preamble}  --version, -V \n    Displays the version.\n\n\n")
+    );
+    assert_eq!(
+        TestStandardHelp::INDENTED_HELP,
+        format!(
+            "{preamble}    --version, -V \n    Displays the version.\n\n\n\n\
+             See the README for more information.\n"
+        )
+    );
+    assert_eq!(
+        crate::version_string!(),
+        concat!("argtea ", env!("CARGO_PKG_VERSION"))
+    );
+}
+
+const HEADER: &str = "Usage: test [OPTIONS]";
+
+struct TestConstLines;
+
+impl TestConstLines {
+    const FOOTER: &'static str = "See the README for more information.";
+}
+
+argtea_impl! {
+    {
+        /// Displays this help message.
+        ("--help" | "-h") => {}
+    }
+
+    impl TestConstLines {
+        const HELP: &'static str = crate::simple_format!(
+            include_str!("tests/const_lines_header.txt")
+            concat!("Version ", env!("CARGO_PKG_VERSION"))
+            HEADER
+            ""
+            docs!()
+            Self::FOOTER
+            (crate::tests::HEADER)
+        );
+
+        const CAPS_HELP: &'static str = crate::simple_format!(
+            { param_style: caps }
+            HEADER
+            docs!()
+        );
+
+        const TABLE: &'static str = crate::table_format!(HEADER docs!() Self::FOOTER);
+    }
+}
+
+#[test]
+fn test_const_lines() {
+    assert_eq!(
+        TestConstLines::HELP,
+        format!(
+            include_str!("tests/const_lines.txt"),
+            version = env!("CARGO_PKG_VERSION")
+        )
+    );
+    assert_eq!(
+        TestConstLines::CAPS_HELP,
+        "Usage: test [OPTIONS]\n  --help, -h \n    Displays this help message.\n\n\n"
+    );
+    assert_eq!(
+        TestConstLines::TABLE,
+        "Usage: test [OPTIONS]\n  --help, -h  Displays this help message.\n\
+         See the README for more information.\n"
+    );
+}
+
+struct TestPlain;
+
+argtea_impl! {
+    {
+        /// Runs `argtea_test [FLAGS]` on *every* file matching `src/*.rs`.
+        ///
+        /// * Files are **never** modified
+        /// * 2 * 3 = 6
+        /// * Matches a*b and *.txt but not **/*.log, see *`--help`* (and *`a* b`*)
+        ("--run", pattern) => {}
+    }
+
+    impl TestPlain {
+        const MARKDOWN: &'static str = crate::simple_format!(docs!());
+        const PLAIN: &'static str = crate::plain_format!(docs!());
+    }
+}
+
+#[test]
+fn test_plain_format() {
+    assert_eq!(
+        TestPlain::MARKDOWN,
+        "  --run <pattern> \n    \
+         Runs `argtea_test [FLAGS]` on *every* file matching `src/*.rs`.\n   \n    \
+         * Files are **never** modified\n    * 2 * 3 = 6\n    \
+         * Matches a*b and *.txt but not **/*.log, see *`--help`* (and *`a* b`*)\n\n\n"
+    );
+    assert_eq!(
+        TestPlain::PLAIN,
+        "  --run <pattern> \n    \
+         Runs argtea_test [FLAGS] on every file matching src/*.rs.\n   \n    \
+         * Files are never modified\n    * 2 * 3 = 6\n    \
+         * Matches a*b and *.txt but not **/*.log, see --help (and a* b)\n\n\n"
+    );
+}
+
+struct TestUndocumented;
+
+argtea_impl! {
+    {
+        /// Sets the output file path.
+        ("--output" | "-o", path) => {}
+
+        ("--quiet" | "-q") => {}
+
+        /// Displays this help message.
+        ("--help" | "-h") => {}
+
+        (file) => {}
+    }
+
+    impl TestUndocumented {
+        const FLAGS: &'static [Flag] = docs!();
+
+        const SHOW: &'static str = crate::simple_format!({ separator: "\n" } docs!());
+        const SKIP: &'static str = crate::simple_format!(
+            { undocumented: skip, separator: "" }
+            docs!()
+        );
+        const PLACEHOLDER: &'static str = crate::simple_format!(
+            { undocumented: placeholder("(undocumented)"), separator: "" }
+            docs!()
+        );
+
+        const TABLE_SKIP: &'static str = crate::table_format!({ undocumented: skip } docs!());
+        const TABLE_PLACEHOLDER: &'static str = crate::table_format!(
+            { undocumented: placeholder("(undocumented)") }
+            docs!()
+        );
+    }
+}
+
+#[test]
+fn test_undocumented_modes() {
+    crate::assert_help_snapshot!(TestUndocumented::SHOW, "tests/undocumented_show.txt");
+    crate::assert_help_snapshot!(TestUndocumented::SKIP, "tests/undocumented_skip.txt");
+    crate::assert_help_snapshot!(
+        TestUndocumented::PLACEHOLDER,
+        "tests/undocumented_placeholder.txt"
+    );
+    crate::assert_help_snapshot!(
+        TestUndocumented::TABLE_SKIP,
+        "tests/undocumented_table_skip.txt"
+    );
+    crate::assert_help_snapshot!(
+        TestUndocumented::TABLE_PLACEHOLDER,
+        "tests/undocumented_table_placeholder.txt"
+    );
+
+    // Skipping only affects how the flags are displayed
+    assert_eq!(TestUndocumented::FLAGS.len(), 4);
+
+    let render = |undocumented| {
+        let mut buf = String::new();
+        let options = HelpOptions {
+            undocumented,
+            ..Default::default()
+        };
+        wrapping_format_with(&mut buf, TestUndocumented::FLAGS, &options);
+        buf
+    };
+
+    crate::assert_help_snapshot!(
+        render(Undocumented::Skip),
+        "tests/undocumented_wrapping_skip.txt"
+    );
+    crate::assert_help_snapshot!(
+        render(Undocumented::Placeholder("(undocumented)")),
+        "tests/undocumented_wrapping_placeholder.txt"
+    );
+}
+
+struct TestParamStyle;
+
+argtea_impl! {
+    {
+        /// Sets the output file path.
+        ("--output" | "-o", output_path) => {}
+
+        /// Sets when colors are used.
+        ("--color", color in ["auto", "never"]) => {}
+
+        (file) => {}
+    }
+
+    impl TestParamStyle {
+        const FLAGS: &'static [Flag] = docs!();
+
+        const ANGLE: &'static str = crate::simple_format!(docs!());
+        const SQUARE: &'static str = crate::simple_format!({ param_style: square } docs!());
+        const CAPS: &'static str = crate::simple_format!({ param_style: caps } docs!());
+
+        const TABLE_CAPS: &'static str = crate::table_format!({ param_style: caps } docs!());
+    }
+}
+
+#[test]
+fn test_param_style() {
+    crate::assert_help_snapshot!(TestParamStyle::ANGLE, "tests/param_style_angle.txt");
+    crate::assert_help_snapshot!(TestParamStyle::SQUARE, "tests/param_style_square.txt");
+    crate::assert_help_snapshot!(TestParamStyle::CAPS, "tests/param_style_caps.txt");
+    assert_eq!(
+        TestParamStyle::TABLE_CAPS,
+        "  --output, -o OUTPUT_PATH  Sets the output file path.\n  \
+         --color COLOR             Sets when colors are used.\n  \
+         FILE\n"
+    );
+
+    let render = |param_style| {
+        let options = HelpOptions {
+            param_style,
+            ..Default::default()
+        };
+
+        render_flags(TestParamStyle::FLAGS, &mut ColumnRenderer::new(options))
+    };
+
+    assert_eq!(
+        render(ParamStyle::Angle),
+        "  -o, --output <output_path>  Sets the output file path.\n  \
+         --color <auto|never>        Sets when colors are used.\n\n\
+         Arguments:\n  <file>\n"
+    );
+    assert_eq!(
+        render(ParamStyle::Square),
+        "  -o, --output [output_path]  Sets the output file path.\n  \
+         --color [auto|never]        Sets when colors are used.\n\n\
+         Arguments:\n  [file]\n"
+    );
+    assert_eq!(
+        render(ParamStyle::Caps),
+        "  -o, --output OUTPUT_PATH  Sets the output file path.\n  \
+         --color {auto|never}      Sets when colors are used.\n\n\
+         Arguments:\n  FILE\n"
+    );
+}
+
+struct TestSections;
+
+argtea_impl! {
+    {
+        /// Sets the output file path.
+        ("--output" | "-o", path) => {}
+
+        /// Displays this help message.
+        ("--help" | "-h") => {}
+
+        /// The inputs to process.
+        (file) => {}
+    }
+
+    impl TestSections {
+        const FLAGS: &'static [Flag] = docs!();
+    }
+}
+
+#[test]
+fn test_wrapping_sections() {
+    let mut buf = String::new();
+    wrapping_format(&mut buf, TestSections::FLAGS);
+
+    crate::assert_help_snapshot!(buf, "tests/wrapping_sections.txt");
+
+    let options = HelpOptions {
+        options_heading: Some("Options:"),
+        positional_heading: "Files:",
+        positional_placement: Placement::Before,
+        ..Default::default()
+    };
+
+    let mut buf = String::new();
+    wrapping_format_with(&mut buf, TestSections::FLAGS, &options);
+
+    crate::assert_help_snapshot!(buf, "tests/wrapping_sections_before.txt");
+}
+
+#[test]
+fn test_color_format() {
+    let options = HelpOptions {
+        options_heading: Some("Options:"),
+        ..Default::default()
+    };
+
+    let mut plain = String::new();
+    wrapping_format_with(&mut plain, TestSections::FLAGS, &options);
+
+    let mut colored = String::new();
+    color_format_with(&mut colored, TestSections::FLAGS, &options);
+
+    assert!(colored.starts_with("\x1b[1;4mOptions:\x1b[0m\n  \x1b[1m"));
+    assert_eq!(
+        colored
+            .replace("\x1b[1;4m", "")
+            .replace("\x1b[1m", "")
+            .replace("\x1b[0m", ""),
+        plain
+    );
+}
+
+#[test]
+fn test_color_auto() {
+    let env = |vars: &'static [(&'static str, &'static str)]| {
+        move |name: &str| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.into())
+        }
+    };
+
+    assert!(use_color(env(&[]), true));
+    assert!(!use_color(env(&[]), false));
+    assert!(!use_color(env(&[("NO_COLOR", "1")]), true));
+    assert!(use_color(env(&[("NO_COLOR", "")]), true));
+    assert!(use_color(env(&[("CLICOLOR_FORCE", "1")]), false));
+    assert!(use_color(env(&[("FORCE_COLOR", "true")]), false));
+    assert!(!use_color(env(&[("FORCE_COLOR", "0")]), false));
+    assert!(!use_color(
+        env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]),
+        true
+    ));
+}
+
+#[test]
+fn test_write_help_error() {
+    struct BrokenPipe;
+
+    impl Write for BrokenPipe {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    // `print_help` exits with its exit code instead of panicking when the help can't be written
+    write_help(BrokenPipe, "Usage: prog [OPTIONS]");
+
+    let mut buf = Vec::new();
+    write_help(&mut buf, "Usage: prog [OPTIONS]");
+    assert_eq!(buf, b"Usage: prog [OPTIONS]\n");
+}
+
+struct TestSorting;
+
+argtea_impl! {
+    {
+        ("--zebra" | "-a") => {}
+
+        #[hidden]
+        ("--hidden") => {}
+
+        (file) => {}
+
+        ("-v") => {}
+
+        ("-V") => {}
+
+        ("--alpha", value) => {}
+    }
+
+    impl TestSorting {
+        const FLAGS: &'static [Flag] = docs!();
+    }
+}
+
+#[test]
+fn test_sort_order() {
+    let format = |sort| {
+        let mut buf = String::new();
+        let options = HelpOptions {
+            sort,
+            ..Default::default()
+        };
+
+        wrapping_format_with(&mut buf, TestSorting::FLAGS, &options);
+        buf
+    };
+
+    assert_eq!(
+        format(SortOrder::Declaration),
+        "  -a, --zebra\n\n  -v\n\n  -V\n\n  --alpha <value>\n\nArguments:\n  <file>\n\n"
+    );
+    assert_eq!(
+        format(SortOrder::Alphabetical),
+        "  --alpha <value>\n\n  -v\n\n  -V\n\n  -a, --zebra\n\nArguments:\n  <file>\n\n"
+    );
+    assert_eq!(
+        format(SortOrder::Custom(|a, b| b
+            .params
+            .len()
+            .cmp(&a.params.len()))),
+        "  --alpha <value>\n\n  -a, --zebra\n\n  -v\n\n  -V\n\nArguments:\n  <file>\n\n"
+    );
+}
+
+#[test]
+fn test_alias_style() {
+    let flag = Flag {
+        doc: &[],
+        flags: &["--output", "-o", "--out", "-O"],
+        params: &["path", "mode"],
+        ..Flag::EMPTY
+    };
+
+    assert_eq!(flag.to_string(), "-o, -O, --output, --out <path> <mode>");
+    assert_eq!(TestSections::FLAGS[2].to_string(), "<file>");
+
+    let mut buf = String::new();
+    let options = HelpOptions {
+        alias_style: AliasStyle::Declaration,
+        ..Default::default()
+    };
+
+    wrapping_format_with(&mut buf, &[flag], &options);
+    assert_eq!(buf, "  --output -o --out -O <path> <mode>\n\n");
+}
+
+#[test]
+fn test_wrapped_indentation() {
+    let flags = [
+        Flag {
+            doc: &[" Sets the output file path."],
+            flags: &["--output", "-o"],
+            params: &["path"],
+            ..Flag::EMPTY
+        },
+        Flag {
+            doc: &[
+                " Sets the number of jobs that run at the same time.",
+                " Defaults to the number of CPUs.",
+                "",
+                " Higher numbers use more memory, but compiling is faster",
+                " as long as there are enough CPUs.",
+                "",
+                " Zero means no limit.",
+            ],
+            flags: &["--jobs", "-j"],
+            params: &["jobs"],
+            ..Flag::EMPTY
+        },
+    ];
+
+    let mut buf = String::new();
+    let options = HelpOptions {
+        width: 30,
+        ..Default::default()
+    };
+    wrapping_format_with(&mut buf, &flags, &options);
+
+    assert_eq!(
+        buf,
+        "  -o, --output <path>
+    Sets the output file path.
+
+  -j, --jobs <jobs>
+    Sets the number of jobs
+    that run at the same time.
+    Defaults to the number of
+    CPUs.
+    Higher numbers use more
+    memory, but compiling is
+    faster as long as there
+    are enough CPUs.
+    Zero means no limit.
+
+"
+    );
+
+    for line in buf.lines().filter(|line| !line.is_empty()) {
+        let indent = line.len() - line.trim_start().len();
+
+        if line.trim_start().starts_with('-') {
+            assert_eq!(indent, 2, "{line:?}");
+        } else {
+            assert_eq!(indent, 4, "{line:?}");
+            assert!(line.len() <= options.width, "{line:?}");
+        }
+    }
+}
+
+#[test]
+fn test_undocumented_spacing() {
+    let flags = [
+        Flag {
+            doc: &["Sets the output file path."],
+            flags: &["--output", "-o"],
+            params: &["path"],
+            ..Flag::EMPTY
+        },
+        Flag {
+            doc: &[],
+            flags: &["--quiet", "-q"],
+            params: &[],
+            ..Flag::EMPTY
+        },
+        Flag {
+            doc: &[],
+            flags: &["--verbose"],
+            params: &[],
+            ..Flag::EMPTY
+        },
+        Flag {
+            doc: &["Displays this help message."],
+            flags: &["--help", "-h"],
+            params: &[],
+            ..Flag::EMPTY
+        },
+    ];
+
+    let mut buf = String::new();
+    wrapping_format(&mut buf, &flags);
+
+    assert_eq!(
+        buf,
+        "  -o, --output <path>
+    Sets the output file path.
+
+  -q, --quiet
+
+  --verbose
+
+  -h, --help
+    Displays this help message.
+
+"
+    );
+}
+
+#[test]
+fn test_header_wrapping() {
+    let flags = [Flag {
+        doc: &["Sets the compression level of the output archive."],
+        flags: &[
+            "--compression-level",
+            "-c",
+            "--compress",
+            "--level",
+            "-l",
+            "--compression",
+        ],
+        params: &["algorithm", "level", "dictionary-size"],
+        ..Flag::EMPTY
+    }];
+
+    let options = |width| HelpOptions {
+        width,
+        ..Default::default()
+    };
+
+    let mut buf = String::new();
+    wrapping_format_with(&mut buf, &flags, &options(80));
+    assert_eq!(
+        buf,
+        "  -c, -l, --compression-level, --compress, --level, --compression
+  <algorithm> <level> <dictionary-size>
+    Sets the compression level of the output archive.
+
+"
+    );
+
+    let mut buf = String::new();
+    wrapping_format_with(&mut buf, &flags, &options(50));
+    assert_eq!(
+        buf,
+        "  -c, -l, --compression-level, --compress,
+  --level, --compression
+  <algorithm> <level> <dictionary-size>
+    Sets the compression level of the output
+    archive.
+
+"
+    );
+
+    // Headers that are wrapped are never followed by documentation
+    assert_eq!(
+        render_flags(&flags, &mut ColumnRenderer::new(options(80))),
+        "  -c, -l, --compression-level, --compress, --level, --compression
+  <algorithm> <level> <dictionary-size>
+                                Sets the compression level of the output
+                                archive.
+"
+    );
+    assert_eq!(
+        render_flags(&flags, &mut ColumnRenderer::new(options(50))),
+        "  -c, -l, --compression-level, --compress,
+  --level, --compression
+  <algorithm> <level> <dictionary-size>
+                                Sets the
+                                compression level
+                                of the output
+                                archive.
+"
+    );
+}
+
+#[test]
+fn test_render_links() {
+    let flags = [Flag {
+        doc: &[
+            "See [`Flag`] and [the manual](https://example.com/a_(b)) for details.",
+            "Uses `[x](y)` and [refs][1].",
+            "Unmatched [bracket and `backtick.",
+        ],
+        flags: &["-x"],
+        params: &[],
+        ..Flag::EMPTY
+    }];
+
+    let format = |render_links, strip_code_spans| {
+        let mut buf = String::new();
+        let options = HelpOptions {
+            render_links,
+            strip_code_spans,
+            ..Default::default()
+        };
+
+        wrapping_format_with(&mut buf, &flags, &options);
+        buf
+    };
+
+    assert_eq!(
+        format(false, false),
+        "  -x\n    See [`Flag`] and [the manual](https://example.com/a_(b)) for details. Uses\n    \
+         `[x](y)` and [refs][1]. Unmatched [bracket and `backtick.\n\n"
+    );
+    assert_eq!(
+        format(true, false),
+        "  -x\n    See `Flag` and the manual (https://example.com/a_(b)) for details. Uses\n    \
+         `[x](y)` and refs. Unmatched [bracket and `backtick.\n\n"
+    );
+    assert_eq!(
+        format(true, true),
+        "  -x\n    See Flag and the manual (https://example.com/a_(b)) for details. Uses [x](y)\n    \
+         and refs. Unmatched [bracket and `backtick.\n\n"
+    );
+}
+
+/// Renders flags as a numbered list, grouped by section.
+struct NumberedRenderer;
+
+impl HelpRenderer for NumberedRenderer {
+    fn positional_placement(&self) -> Placement {
+        Placement::Before
+    }
+
+    fn begin(&mut self, buf: &mut String, flags: &[&Flag]) {
+        *buf += &format!("{} flags\n", flags.len());
+    }
+
+    fn section(&mut self, buf: &mut String, section: Section) {
+        *buf += &format!("[{section:?}]\n");
+    }
+
+    fn flag(&mut self, buf: &mut String, flag: &Flag, context: FlagContext) {
+        *buf += &format!("{}. {flag}", context.index + 1);
+        *buf += if context.is_last { " (last)\n" } else { "\n" };
+    }
+
+    fn positional(&mut self, buf: &mut String, flag: &Flag, _context: FlagContext) {
+        *buf += &format!("* {flag}\n");
+    }
+
+    fn end(&mut self, buf: &mut String) {
+        *buf += "end\n";
+    }
+}
+
+#[test]
+fn test_help_renderer() {
+    assert_eq!(
+        render_flags(TestSections::FLAGS, &mut NumberedRenderer),
+        "3 flags\n[Positionals]\n* <file>\n[Options]\n1. -o, --output <path>\n\
+         2. -h, --help (last)\nend\n"
+    );
+
+    let options = HelpOptions {
+        options_heading: Some("Options:"),
+        ..Default::default()
+    };
+
+    crate::assert_help_snapshot!(
+        render_flags(
+            TestSections::FLAGS,
+            &mut ColumnRenderer::new(options.clone())
+        ),
+        "tests/columns.txt"
+    );
+
+    let mut buf = String::new();
+    wrapping_format_with(&mut buf, TestSections::FLAGS, &options);
+    assert_eq!(
+        render_flags(TestSections::FLAGS, &mut WrappingRenderer::new(options)),
+        buf
+    );
+
+    let flags = [Flag {
+        doc: &["A flag with a long header. Its description is long enough to be wrapped onto a second line."],
+        flags: &["--a-flag-with-a-long-name"],
+        params: &["and", "parameters"],
+        ..Flag::EMPTY
+    }];
+    assert_eq!(
+        render_flags(&flags, &mut ColumnRenderer::new(HelpOptions::default())),
+        "  --a-flag-with-a-long-name <and> <parameters>\n                                \
+         A flag with a long header. Its description is\n                                \
+         long enough to be wrapped onto a second line.\n"
+    );
+}
+
+/// Parses `args` with [`TestEvents`] and summarizes the events as `flag`, `flag=value`, or
+/// `<positional>`.
+fn summarize_events(args: &[&str]) -> Vec<String> {
+    let args = testing::args(args);
+
+    TestEvents::parse(args)
+        .into_iter()
+        .map(|event| match event {
+            Event::Flag { name } => name,
+            Event::Value { flag, value } => format!("{flag}={value}"),
+            Event::Positional(value) => format!("<{value}>"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_cluster_non_flag_chars() {
+    let tests: &[(&[&str], &[&str])] = &[
+        (&["-v--", "-v"], &["-v", "<-->", "-v"]),
+        (&["-v-v"], &["-v", "-v"]),
+        (&["-v-vo", "file"], &["-v", "-v", "-o", "-o=file"]),
+        (&["-v=x"], &["-v", "<x>"]),
+        (&["-v="], &["-v", "<>"]),
+        (&["-vv=x", "file"], &["-v", "-v", "<x>", "<file>"]),
+        (&["--verbose=x"], &["--verbose", "<x>"]),
+        (&["-o=out"], &["-o", "-o=out"]),
+        (&["-vo--", "file"], &["-v", "-o", "-o=--", "<file>"]),
+        (&["-o=", "file"], &["-o", "-o=", "<file>"]),
+        (&["-m--", "file"], &["-m", "-m=--", "<file>"]),
+        (&["-=x", "file"], &["<-=x>", "<file>"]),
+    ];
+
+    for (test, expected) in tests {
+        assert_eq!(summarize_events(test), *expected, "{test:?}");
+    }
+}
+
+#[test]
+fn test_usage_line() {
+    assert_eq!(
+        usage_line("test", TestA::FLAGS),
+        "test [OPTIONS] [--warning <warning>] [--weird-flag <p>] [<other>...]"
+    );
+    assert_eq!(
+        usage_line_with("test", TestA::FLAGS, 1),
+        "test [OPTIONS] [<other>...]"
+    );
+
+    const POSITIONALS: &[Flag] = &[
+        Flag {
+            doc: &[],
+            flags: &["-v"],
+            params: &[],
+            ..Flag::EMPTY
+        },
+        Flag {
+            doc: &[],
+            flags: &[],
+            params: &["source"],
+            ..Flag::EMPTY
+        },
+        Flag {
+            doc: &[],
+            flags: &[],
+            params: &["destination"],
+            ..Flag::EMPTY
+        },
+    ];
+
+    assert_eq!(
+        usage_line("cp", POSITIONALS),
+        "cp [OPTIONS] [<source>...] [<destination>...]"
+    );
+    assert_eq!(usage_line("cp", &[]), "cp");
+}
+
+#[test]
+fn test_cluster_values() {
+    let tests: &[(&[&str], &[&str])] = &[
+        (&["-o", "file"], &["-o", "-o=file"]),
+        (&["-ofile"], &["-o", "-o=file"]),
+        (&["-o=file"], &["-o", "-o=file"]),
+        (&["-vofile"], &["-v", "-o", "-o=file"]),
+        (&["-vo", "file"], &["-v", "-o", "-o=file"]),
+        (&["-vovfile", "x"], &["-v", "-o", "-o=vfile", "<x>"]),
+    ];
+
+    for (test, expected) in tests {
+        assert_eq!(summarize_events(test), *expected, "{test:?}");
+    }
+}
+
+#[cfg(feature = "man")]
+#[test]
+fn test_man() {
+    use crate::man::{self, ManMeta};
+
+    const META: ManMeta = ManMeta {
+        name: "argtea_test",
+        section: "1",
+        version: "1.0.0",
+        date: "2024-01-01",
+        about: "a demo argtea project",
+    };
+
+    crate::assert_help_snapshot!(man::render(&META, TestSections::FLAGS), "tests/man.txt");
+
+    const ESCAPING: &[Flag] = &[Flag {
+        doc: &[
+            " Reads C:\\path\\to\\file (like --path-file)",
+            "",
+            ".starts with a dot",
+        ],
+        flags: &["--path"],
+        params: &[],
+        ..Flag::EMPTY
+    }];
+
+    assert!(man::render(&META, ESCAPING).ends_with(
+        ".SH OPTIONS\n.TP\n\\fB\\-\\-path\\fR\nReads C:\\epath\\eto\\efile (like \\-\\-path\\-file)\n.IP\n\\&.starts with a dot\n"
+    ));
+
+    let sections = man::render(&META, TestDocSections::VISIBLE);
+    let common = sections
+        .find(".SS \"Common\"\n.TP\n\\fB\\-\\-help\\fR")
+        .unwrap();
+    let advanced = sections
+        .find(".SS \"Advanced\"\n.TP\n\\fB\\-\\-debug\\fR")
+        .unwrap();
+    assert!(common < advanced && advanced < sections.find(".SH ARGUMENTS").unwrap());
+
+    assert!(man::render(&META, TestExamples::FLAGS).ends_with(
+        ".SH EXAMPLES\n.nf\nmyprog \\-o out.txt input.c\nmyprog \\-\\-output=\\- input.c\nmyprog \\-v input.c\n.fi\n"
+    ));
+}
+
+#[test]
+fn test_flag_view() {
+    let mut view = FlagView::new();
+
+    for c in ['a', 'ä', '€', '😀'] {
+        assert_eq!(c.len_utf8(), view.get(c).len() - 1);
+        assert_eq!(*view.get(c), format!("-{c}"));
+    }
+
+    assert_eq!(summarize_events(&["-😀x"]), ["-😀", "-x"]);
+    assert_eq!(summarize_events(&["-x😀😀"]), ["-x", "-😀", "-😀"]);
+    assert_eq!(summarize_events(&["-😀o😀"]), ["-😀", "-o", "-o=😀"]);
+}
+
+#[test]
+fn test_normalize_args() {
+    let normalize = |args: &[&str]| crate::normalize_args(testing::args(args)).collect::<Vec<_>>();
+
+    assert_eq!(normalize(&["-sw", "80"]), ["-s", "-w", "80"]);
+    assert_eq!(normalize(&["-ä😀", "-o=a=b"]), ["-ä", "-😀", "-o", "a=b"]);
+    assert_eq!(
+        normalize(&["--output=", "-a--"]),
+        ["--output", "", "-a", "--"]
+    );
+    assert_eq!(normalize(&["-a=", "-=x"]), ["-a", "", "-=x"]);
+    assert_eq!(
+        normalize(&["-v-ab", "-v--", "-ab"]),
+        ["-v", "-a", "-b", "-v", "--", "-ab"]
+    );
+    assert_eq!(
+        normalize(&["-", "-v", "--", "-sw", "--x=y"]),
+        ["-", "-v", "--", "-sw", "--x=y"]
+    );
+}
+
+#[test]
+fn test_cluster_multibyte_flags() {
+    let tests: &[(&[&str], &[&str])] = &[
+        (&["-ä"], &["-ä"]),
+        (&["-äx"], &["-ä", "-x"]),
+        (&["-xä"], &["-x", "-ä"]),
+        (&["-ää😀"], &["-ä", "-ä", "-😀"]),
+        (&["-äoäx"], &["-ä", "-o", "-o=äx"]),
+        (&["-ä=ä"], &["-ä", "<ä>"]),
+        (&["-ä-ä"], &["-ä", "-ä"]),
+        (&["-äü"], &["-ä", "<-ü>"]),
+    ];
+
+    for (test, expected) in tests {
+        assert_eq!(summarize_events(test), *expected, "{test:?}");
+    }
+}
+
+#[test]
+fn test_cluster_multibyte_values() {
+    // The rest of a cluster is handed to a value-taking flag, so it must be split on a `char`
+    // boundary
+    let tests: &[(&[&str], &[&str])] = &[
+        (&["-ä😀"], &["-ä", "-😀"]),
+        (&["-😀ä"], &["-😀", "-ä"]),
+        (&["-ä😀o😀ä"], &["-ä", "-😀", "-o", "-o=😀ä"]),
+        (&["-äo=😀"], &["-ä", "-o", "-o=😀"]),
+        (&["-äo", "ä"], &["-ä", "-o", "-o=ä"]),
+        (&["-😀m😀"], &["-😀", "-m", "-m=😀"]),
+        (&["-äm", "-ä"], &["-ä", "-m", "-ä"]),
+    ];
+
+    for (test, expected) in tests {
+        assert_eq!(summarize_events(test), *expected, "{test:?}");
+    }
+}
+
+#[test]
+fn test_markdown() {
+    const FLAGS: &[Flag] = &[
+        Flag {
+            doc: &[" Sets the output file path.", "", " Use `-` for stdout."],
+            flags: &["--output", "-o"],
+            params: &["path"],
+            ..Flag::EMPTY
+        },
+        Flag {
+            doc: &[
+                " Sets the color mode (`always|never`)",
+                " Defaults to `never`.",
+            ],
+            flags: &["--color"],
+            params: &["when"],
+            ..Flag::EMPTY
+        },
+        Flag {
+            doc: &[],
+            flags: &["--quiet"],
+            params: &[],
+            ..Flag::EMPTY
+        },
+        Flag {
+            doc: &[" The inputs to process."],
+            flags: &[],
+            params: &["file"],
+            ..Flag::EMPTY
+        },
+    ];
+
+    crate::assert_help_snapshot!(
+        to_markdown(FLAGS, MarkdownStyle::Definitions),
+        "tests/markdown_definitions.md"
+    );
+    crate::assert_help_snapshot!(
+        to_markdown(FLAGS, MarkdownStyle::Table),
+        "tests/markdown_table.md"
+    );
+}
+
+#[test]
+fn test_strict_value() {
+    let tests: &[(&[&str], &[&str])] = &[
+        (
+            &["--output", "--verbose"],
+            &["--output", "--output=--verbose"],
+        ),
+        (&["--message", "--verbose"], &["--message", "--verbose"]),
+        (
+            &["--message", "-vx", "a"],
+            &["--message", "-v", "-x", "<a>"],
+        ),
+        (
+            &["--message=--verbose"],
+            &["--message", "--message=--verbose"],
+        ),
+        (&["-m", "hi"], &["-m", "-m=hi"]),
+        (&["-m", "-"], &["-m", "-m=-"]),
+        (&["-m-x"], &["-m", "-m=-x"]),
+        (&["-m"], &["-m"]),
+    ];
+
+    for (test, expected) in tests {
+        assert_eq!(summarize_events(test), *expected, "{test:?}");
+    }
+}
+
+#[test]
+fn test_bash_completions() {
+    let script = completions::bash("argtea's test", TestA::FLAGS);
+
+    for alias in ["'-Wall'", "'--warning'", "'-W'", "'--weird-flag'", "'-f'"] {
+        assert_eq!(script.matches(alias).count(), 1, "{alias}");
+    }
+
+    assert!(script.contains("complete -F _argtea_s_test_completions 'argtea'\\''s test'\n"));
+
+    let Ok(mut bash) = Command::new("bash").arg("-n").stdin(Stdio::piped()).spawn() else {
+        return;
+    };
+
+    bash.stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    assert!(bash.wait().unwrap().success());
+}
+
+#[test]
+fn test_zsh_completions() {
+    crate::assert_help_snapshot!(
+        completions::zsh("argtea_test", TestA::FLAGS),
+        "tests/zsh.txt"
+    );
+
+    let flag = Flag {
+        doc: &["  Sets [the] 'output': path. Extra", "text.", "", "More."],
+        flags: &["--output"],
+        params: &["path"],
+        ..Flag::EMPTY
+    };
+
+    assert_eq!(flag.first_sentence(), "Sets [the] 'output': path");
+    assert!(completions::zsh("a", &[flag])
+        .contains(r"'--output[Sets \[the\] '\''output'\''\: path]:path:_files'"));
+
+    let script = completions::zsh("argtea's test", &[]);
+    assert!(!script.contains("#compdef"));
+    assert!(script.contains("compdef _argtea_s_test 'argtea'\\''s test'\n"));
+
+    let script = completions::dynamic_script("zsh", "prog").unwrap();
+    assert!(script.starts_with("#compdef prog\n"));
+    assert!(script.contains("compdef _prog_complete 'prog'\n"));
+}
+
+#[test]
+fn test_fish_completions() {
+    crate::assert_help_snapshot!(
+        completions::fish("argtea_test", TestA::FLAGS),
+        "tests/fish.txt"
+    );
+
+    let flag = Flag {
+        doc: &[r"Doesn't escape \n"],
+        flags: &["--dont", "--do-not", "-d"],
+        params: &[],
+        ..Flag::EMPTY
+    };
+
+    assert_eq!(
+        completions::fish("a", &[flag]),
+        "complete -c 'a' -s 'd' -l 'dont' -d 'Doesn\\'t escape \\\\n'\n\
+         complete -c 'a' -l 'do-not' -d 'Doesn\\'t escape \\\\n'\n"
+    );
+}
+
+#[test]
+fn test_powershell_completions() {
+    crate::assert_help_snapshot!(
+        completions::powershell("argtea_test", TestA::FLAGS),
+        "tests/powershell.txt"
+    );
+
+    let flag = Flag {
+        doc: &["Don't run `$cmd` or \u{2019}this\u{2019}"],
+        flags: &["-d"],
+        params: &[],
+        ..Flag::EMPTY
+    };
+
+    assert!(completions::powershell("a", &[flag]).contains(
+        "@{ Name = '-d'; Tooltip = 'Don''t run `$cmd` or \u{2019}\u{2019}this\u{2019}\u{2019}' }"
+    ));
+}
+
+#[test]
+fn test_parse_dynamic() {
+    let specs = [
+        FlagSpec {
+            flags: &["--verbose", "-v"],
+            params: &[],
+        },
+        FlagSpec {
+            flags: &["--output", "-o"],
+            params: &["path"],
+        },
+        FlagSpec {
+            flags: &["--define", "-D"],
+            params: &["key", "value"],
+        },
+    ];
+
+    let parse = |args: &[&str]| parse_dynamic(&specs, testing::args(args));
+    let summarize = |matches: Matches| {
+        let mut summary: Vec<String> = matches
+            .flags
+            .iter()
+            .map(|m| format!("{}{:?}", m.name, m.values))
+            .collect();
+
+        summary.extend(matches.positionals.iter().map(|p| format!("<{p}>")));
+        summary
+    };
+
+    assert_eq!(
+        summarize(parse(&["-vofile", "a", "--output=b", "-D", "k", "v", "-v--", "-"]).unwrap()),
+        [
+            "-v[]",
+            "-o[\"file\"]",
+            "--output[\"b\"]",
+            "-D[\"k\", \"v\"]",
+            "-v[]",
+            "<a>",
+            "<->"
+        ]
+    );
+    assert_eq!(
+        summarize(parse(&["-v=x", "-o-", "-vo=-v", "file"]).unwrap()),
+        ["-v[]", "-o[\"-\"]", "-v[]", "-o[\"-v\"]", "<x>", "<file>"]
+    );
+    assert_eq!(
+        summarize(parse(&["-v-v", "-v--", "-v"]).unwrap()),
+        ["-v[]", "-v[]", "-v[]", "<-v>"]
+    );
+    assert_eq!(
+        summarize(parse(&["-o=x", "--", "-v", "--output"]).unwrap()),
+        ["-o[\"x\"]", "<-v>", "<--output>"]
+    );
+    assert_eq!(
+        parse(&["-vofile"]).unwrap().flags[0].aliases,
+        specs[0].flags
+    );
+
+    assert_eq!(
+        parse(&["-vx"]),
+        Err(ParseError::UnknownFlag {
+            flag: "-x".to_owned(),
+            suggestion: None,
+        })
+    );
+    assert_eq!(
+        parse(&["-=x"]),
+        Err(ParseError::UnknownFlag {
+            flag: "-=x".to_owned(),
+            suggestion: None,
+        })
+    );
+    assert_eq!(
+        parse(&["--verbos"]),
+        Err(ParseError::UnknownFlag {
+            flag: "--verbos".to_owned(),
+            suggestion: Some("--verbose"),
+        })
+    );
+    assert_eq!(
+        parse(&["--verbose", "-D", "k"]),
+        Err(ParseError::MissingValue {
+            flag: "-D".to_owned(),
+            param: "value"
+        })
+    );
+    assert_eq!(
+        String::from(parse(&["--output"]).unwrap_err()),
+        "expected <path> after `--output`"
+    );
+
+    let matches = parse(&["-vv", "-o", "1", "-D", "a", "2", "--output=3", "-Dx", "y"]).unwrap();
+    assert!(matches.contains("-v") && matches.contains("--verbose"));
+    assert!(!parse(&[]).unwrap().contains("-v"));
+    assert_eq!(matches.count("--verbose"), 2);
+    assert_eq!(matches.count("-D"), 2);
+    assert_eq!(matches.get_one::<u32>("-o"), Some(Ok(3)));
+    assert_eq!(matches.get_one::<u32>("-v"), None);
+    assert_eq!(matches.get_one::<u32>("--missing"), None);
+    assert_eq!(matches.get_many::<u32>("--output"), Ok(vec![1, 3]));
+    assert_eq!(
+        matches.get_many::<String>("-D").unwrap(),
+        ["a", "2", "x", "y"]
+    );
+    assert_eq!(matches.get_many::<u32>("-v"), Ok(vec![]));
+    assert_eq!(
+        matches.get_many::<u32>("-D").unwrap_err().to_string(),
+        "invalid value `a` for `-D`: invalid digit found in string"
+    );
+
+    let specs: Vec<FlagSpec> = TestA::FLAGS.iter().map(FlagSpec::from).collect();
+    let matches = parse_dynamic(&specs, ["-Wall".to_owned()]).unwrap();
+    assert_eq!(matches.flags[0].name, "-W");
+    assert_eq!(matches.flags[0].values, ["all"]);
+
+    let matches = parse_dynamic(&specs, ["-Wno-error".to_owned()]).unwrap();
+    assert_eq!(matches.flags[0].name, "-W");
+    assert_eq!(matches.flags[0].values, ["no-error"]);
+}
+
+#[test]
+fn test_dynamic_completions() {
+    let complete = |line: &str, point: usize| {
+        completions::dynamic(TestA::FLAGS, line, point, |flag| {
+            let values: &[&str] = match flag.flags.first() {
+                Some(&"--warning") => &["all", "error", "unused"],
+                Some(_) => &[],
+                None => &["file.c", "file.h"],
+            };
+
+            values.iter().map(|value| value.to_string()).collect()
+        })
+    };
+    let complete_end = |line: &str| complete(line, line.len());
+
+    // Cursor on a flag
+    assert_eq!(complete_end("prog --w"), ["--warning", "--weird-flag"]);
+    assert_eq!(
+        complete_end("prog file.c -"),
+        ["-Wall", "--warning", "-W", "--weird-flag", "-f"]
+    );
+
+    // Cursor on a value
+    assert_eq!(complete_end("prog --warning "), ["all", "error", "unused"]);
+    assert_eq!(complete_end("prog -W e"), ["error"]);
+    assert_eq!(complete_end("prog --warning=a"), ["--warning=all"]);
+    assert_eq!(complete_end("prog --weird-flag "), Vec::<String>::new());
+    assert_eq!(complete_end("prog 'file.c' --warning \"u"), ["unused"]);
+    assert_eq!(complete("prog --warning a file.c", 16), ["all"]);
+
+    // Cursor in the middle of a cluster
+    assert_eq!(complete_end("prog -Wu"), ["-Wunused"]);
+    assert_eq!(complete_end("prog -W=e"), ["-W=error"]);
+    assert_eq!(complete_end("prog -a-Wu"), ["-a-Wunused"]);
+
+    // Cursor on a positional argument
+    assert_eq!(complete_end("prog --warning=all "), ["file.c", "file.h"]);
+    assert_eq!(complete_end("prog -Wall file.h f"), ["file.c", "file.h"]);
+    assert_eq!(complete_end("prog -W all -- -"), Vec::<String>::new());
+    assert_eq!(complete_end("prog"), Vec::<String>::new());
+
+    assert_eq!(
+        completions::shell_split(r#"a 'b c' "d\"e" f\ g "h"#),
+        ["a", "b c", "d\"e", "f g", "h"]
+    );
+
+    for shell in ["bash", "zsh", "fish", "powershell"] {
+        let script = completions::dynamic_script(shell, "prog").unwrap();
+        assert!(script.contains("--__complete"), "{shell}");
+    }
+    assert_eq!(completions::dynamic_script("cmd.exe", "prog"), None);
+
+    let script = completions::dynamic_script("bash", "prog").unwrap();
+    let Ok(mut bash) = Command::new("bash").arg("-n").stdin(Stdio::piped()).spawn() else {
+        return;
+    };
+
+    bash.stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    assert!(bash.wait().unwrap().success());
+}
+
+struct TestTyped;
+
+argtea_impl! {
+    {
+        /// Sets the width.
+        ("--width" | "-w", width: u32) => {
+            widths.push(width);
+        }
+
+        ("--size", width: u32, height: u32,) => {
+            widths.push(width * height);
+        }
+
+        (_file) => {}
+    }
+
+    impl TestTyped {
+        const FLAGS: &'static [Flag] = docs!();
+
+        fn parse(args: &[&str]) -> Result<Vec<u32>, String> {
+            let mut widths = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            Ok(widths)
+        }
+    }
+}
+
+#[test]
+fn test_typed_params() {
+    assert_eq!(TestTyped::FLAGS[0].params, ["width"]);
+    assert_eq!(TestTyped::FLAGS[1].params, ["width", "height"]);
+
+    assert_eq!(
+        TestTyped::parse(&["-w80", "--width=5", "--size", "2", "3"]),
+        Ok(vec![80, 5, 6])
+    );
+    assert_eq!(
+        TestTyped::parse(&["--width", "abc"]),
+        Err("invalid value `abc` for `--width`: expected u32".to_owned())
+    );
+    assert_eq!(
+        TestTyped::parse(&["--size", "1"]),
+        Err("expected <height> after `--size`".to_owned())
+    );
+}
+
+struct TestOptionalTyped;
+
+type OptionalParams = (Vec<Option<usize>>, Vec<Option<String>>);
+
+argtea_impl! {
+    {
+        ("--count" | "-c", count: Option<usize>) => {
+            counts.push(count);
+        }
+
+        ("--name", name: Option<String>, _suffix: ::std::option::Option<String>) => {
+            names.push(name);
+        }
+
+        ("--limit", limit: core::option::Option<usize>) => {
+            counts.push(limit);
+        }
+
+        ("--raw", raw) => {
+            names.push(raw);
+        }
+
+        (_file) => {}
+    }
+
+    impl TestOptionalTyped {
+        fn parse(args: &[&str]) -> Result<OptionalParams, String> {
+            let mut counts = Vec::new();
+            let mut names = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            Ok((counts, names))
+        }
+    }
+}
+
+#[test]
+fn test_optional_typed_params() {
+    assert_eq!(
+        TestOptionalTyped::parse(&["-c3", "--name", "a", "b", "--raw", "c", "--count"]),
+        Ok((
+            vec![Some(3), None],
+            vec![Some("a".to_owned()), Some("c".to_owned())]
+        ))
+    );
+    assert_eq!(
+        TestOptionalTyped::parse(&["--count", "x"]),
+        Err("invalid value `x` for `--count`: expected usize".to_owned())
+    );
+    assert_eq!(
+        TestOptionalTyped::parse(&["--name"]),
+        Ok((vec![], vec![None]))
+    );
+    assert_eq!(
+        TestOptionalTyped::parse(&["--limit", "2", "--limit"]),
+        Ok((vec![Some(2), None], vec![]))
+    );
+}
+
+struct TestChoices;
+
+argtea_impl! {
+    {
+        /// Sets the color mode.
+        ("--color" | "-c", color in ["auto", "always", "never"]) => {
+            colors.push(color);
+        }
+
+        ("--both", a, b in ["x", "y",]) => {
+            colors.push(b);
+        }
+
+        ("--ci", value in ["Auto", "never"] ci) => {
+            colors.push(value);
+        }
+
+        (_file) => {}
+    }
+
+    impl TestChoices {
+        const FLAGS: &'static [Flag] = docs!();
+
+        fn parse(args: &[&str]) -> Result<Vec<&'static str>, String> {
+            let mut colors = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            Ok(colors)
+        }
+    }
+}
+
+#[test]
+fn test_choices() {
+    assert_eq!(
+        TestChoices::parse(&["--color=auto", "-cnever", "--both", "a", "y"]),
+        Ok(vec!["auto", "never", "y"])
+    );
+    assert_eq!(
+        TestChoices::parse(&["--color", "sometimes"]),
+        Err(
+            "invalid value `sometimes` for `--color`: expected one of `auto`, `always`, `never`"
+                .to_owned()
+        )
+    );
+    assert_eq!(
+        TestChoices::parse(&["-c"]),
+        Err("expected <color> after `-c`".to_owned())
+    );
+
+    assert_eq!(
+        TestChoices::parse(&["--ci=auto", "--ci", "AUTO", "--ci=Never", "--ci=NEVER"]),
+        Ok(vec!["Auto", "Auto", "never", "never"])
+    );
+    assert_eq!(
+        TestChoices::parse(&["--color=Auto"]),
+        Err(
+            "invalid value `Auto` for `--color`: expected one of `auto`, `always`, `never`"
+                .to_owned()
+        )
+    );
+    assert_eq!(
+        TestChoices::parse(&["--ci", "sometimes"]),
+        Err("invalid value `sometimes` for `--ci`: expected one of `Auto`, `never`".to_owned())
+    );
+
+    let [color, both, ci, _file] = TestChoices::FLAGS else {
+        panic!("expected four flags");
+    };
+
+    assert_eq!(color.possible_values(0), ["auto", "always", "never"]);
+    assert_eq!(both.possible_values, [&[][..], &["x", "y"]]);
+    assert_eq!(color.to_string(), "-c, --color <auto|always|never>");
+    assert_eq!(both.to_string(), "--both <a> <x|y>");
+    assert_eq!(ci.possible_values(0), ["Auto", "never"]);
+
+    assert!(completions::fish("prog", TestChoices::FLAGS)
+        .contains(" -l 'color' -d 'Sets the color mode' -x -a 'auto always never'\n"));
+    assert!(completions::zsh("prog", TestChoices::FLAGS).contains("'--both:a:_files:b:(x y)'"));
+
+    let script = completions::bash("prog", TestChoices::FLAGS);
+    assert!(script.contains(
+        "'--color'|'-c') COMPREPLY=($(compgen -W 'auto always never' -- \"$cur\")); return 0 ;;"
+    ));
+
+    let Ok(mut bash) = Command::new("bash").arg("-n").stdin(Stdio::piped()).spawn() else {
+        return;
+    };
+
+    bash.stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    assert!(bash.wait().unwrap().success());
+}
+
+struct TestConfig;
+
+argtea_impl! {
+    {
+        ("--output" | "-o", output) => {
+            output_ = output;
+        }
+
+        ("--level", level: u32) => {
+            level_ = level;
+        }
+
+        (_file) => {}
+    }
+
+    impl TestConfig {
+        /// Parses `args` on top of the config file values `output` and `level`.
+        fn parse(args: &[&str], output: &str, level: u32) -> Result<(String, u32), String> {
+            let mut output_ = Some(output.to_owned());
+            let mut level_ = level;
+
+            parse!(testing::args(args).into_iter());
+
+            Ok((output_.unwrap(), level_))
+        }
+    }
+}
+
+#[test]
+fn test_config_defaults() {
+    let parse = |args: &[&str]| TestConfig::parse(args, "config.out", 1).unwrap();
+
+    assert_eq!(parse(&[]), ("config.out".to_owned(), 1));
+    assert_eq!(parse(&["file"]), ("config.out".to_owned(), 1));
+    assert_eq!(parse(&["-o", "cli.out"]), ("cli.out".to_owned(), 1));
+    assert_eq!(
+        parse(&["--level=2", "-oa", "--output", "b", "--level", "3"]),
+        ("b".to_owned(), 3)
+    );
+
+    assert_eq!(crate::merge(Some(2), Some(1)), Some(2));
+    assert_eq!(crate::merge(None, Some(1)), Some(1));
+}
+
+#[test]
+fn test_render_bin() {
+    const HELP: &str = "Usage: {bin} [OPTIONS]\n{{bin}} {x} {bin}{bin}";
+
+    assert_eq!(
+        help::render(HELP, "tool"),
+        "Usage: tool [OPTIONS]\n{bin} {x} tooltool"
+    );
+    assert_eq!(help::render("no placeholder {", "tool"), "no placeholder {");
+    assert_eq!(help::render("{{{bin}}}", "tool"), "{{bin}}");
+
+    assert!(!help::program_name().is_empty());
+    assert!(!help::program_name().contains(std::path::MAIN_SEPARATOR));
+}
+
+struct TestCfg;
+
+argtea_impl! {
+    {
+        /// Always available.
+        #[cfg(all())]
+        ("--present") => {
+            log.push("present".to_owned());
+        }
+
+        /// Never available.
+        #[cfg(any())]
+        #[strict_value]
+        ("--absent", value) => {
+            log.push("absent".to_owned());
+        }
+
+        #[cfg(all())]
+        #[cfg(not(any()))]
+        /// Only shown in the documentation.
+        #[fake]
+        ("--fake") => {}
+
+        #[eager]
+        #[cfg(any())]
+        ("--eager-absent") => {
+            log.push("eager".to_owned());
+        }
+
+        (other) => {
+            log.push(other);
+        }
+    }
+
+    impl TestCfg {
+        const FLAGS: &'static [Flag] = docs!();
+        const HELP: &'static str = crate::simple_format!(docs!());
+
+        fn parse(args: &[&str]) -> Vec<String> {
+            let mut log = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            log
+        }
+    }
+}
+
+#[test]
+fn test_cfg_flags() {
+    let flags: Vec<_> = TestCfg::FLAGS.iter().map(|flag| flag.flags).collect();
+    assert_eq!(flags, [&["--present"][..], &["--fake"], &[]]);
+
+    assert_eq!(
+        TestCfg::HELP,
+        "  --present \n    Always available.\n\n\n  --fake \n    Only shown in the documentation.\n\n\n  <other> \n\n\n"
+    );
+
+    assert_eq!(
+        TestCfg::parse(&["--present", "--absent", "--eager-absent"]),
+        ["present", "--absent", "--eager-absent"]
+    );
+}
+
+// Every flag with a `#[cfg]` is only split once, so the formatting macro isn't expanded 2^16 times
+struct TestManyCfgs;
+
+argtea_impl! {
+    {
+        #[cfg(all())]
+        ("-a") => {}
+        #[cfg(any())]
+        ("-b") => {}
+        #[cfg(all())]
+        ("-c") => {}
+        #[cfg(any())]
+        ("-d") => {}
+        #[cfg(all())]
+        ("-e") => {}
+        #[cfg(any())]
+        ("-f") => {}
+        #[cfg(all())]
+        ("-g") => {}
+        #[cfg(any())]
+        ("-h") => {}
+        #[cfg(all())]
+        ("-i") => {}
+        #[cfg(any())]
+        ("-j") => {}
+        #[cfg(all())]
+        ("-k") => {}
+        #[cfg(any())]
+        ("-l") => {}
+        #[cfg(all())]
+        ("-m") => {}
+        #[cfg(any())]
+        ("-n") => {}
+        #[cfg(all())]
+        ("-o") => {}
+        #[cfg(any())]
+        ("-p") => {}
+    }
+
+    impl TestManyCfgs {
+        const HELP: &'static str = crate::simple_format!(docs!());
+    }
+}
+
+#[test]
+fn test_many_cfg_flags() {
+    let flags: Vec<_> = TestManyCfgs::HELP.split_whitespace().collect();
+    assert_eq!(flags, ["-a", "-c", "-e", "-g", "-i", "-k", "-m", "-o"]);
+}
+
+struct TestForwardedAttrs;
+
+argtea_impl! {
+    {
+        /// Never available.
+        #[cfg_attr(all(), cfg(any()), allow(unused_variables))]
+        ("--unparsed") => {
+            log.push("unparsed".to_owned());
+        }
+
+        /// Always available.
+        #[cfg_attr(any(), cfg(any()))]
+        ("--parsed") => {
+            log.push("parsed".to_owned());
+        }
+
+        /// Takes a value and ignores it.
+        #[allow(unused_variables)]
+        #[eager]
+        ("--ignored", value) => {}
+
+        (other) => {
+            log.push(other);
+        }
+    }
+
+    impl TestForwardedAttrs {
+        const FLAGS: &'static [Flag] = docs!();
+        const HELP: &'static str = crate::simple_format!(docs!());
+
+        fn parse(args: &[&str]) -> Vec<String> {
+            let mut log = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            log
+        }
+    }
+}
+
+#[test]
+fn test_forwarded_attributes() {
+    let flags: Vec<_> = TestForwardedAttrs::FLAGS
+        .iter()
+        .map(|flag| flag.flags)
+        .collect();
+    assert_eq!(flags, [&["--parsed"][..], &["--ignored"], &[]]);
+    assert_eq!(
+        TestForwardedAttrs::HELP,
+        "  --parsed \n    Always available.\n\n\n  --ignored <value> \n    Takes a value and ignores it.\n\n\n  <other> \n\n\n"
+    );
+
+    assert_eq!(
+        TestForwardedAttrs::parse(&["--unparsed", "--parsed", "--ignored", "a", "b"]),
+        ["--unparsed", "parsed", "b"]
+    );
+}
+
+argtea_impl! {
+    {
+        ("--output" | "-o", path) => {
+            output_path = path.unwrap_or_default();
+        }
+
+        ("--verbose" | "-v") => {
+            verbosity += 1;
+        }
+
+        (file) => {
+            files.push(file);
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct TestStruct {
+        /// The output path
+        output_path: String = "a.out".to_owned(),
+        verbosity: u8,
+        pub(crate) files: Vec<String>,
+    }
+
+    impl TestStruct {
+        fn parse(args: &[&str]) -> Self {
+            parse_fields!(testing::args(args).into_iter())
+        }
+    }
+}
+
+#[test]
+fn test_parse_fields() {
+    assert_eq!(
+        TestStruct::parse(&["a", "-vv", "b"]),
+        TestStruct {
+            output_path: "a.out".to_owned(),
+            verbosity: 2,
+            files: vec!["a".to_owned(), "b".to_owned()],
+        }
+    );
+
+    assert_eq!(TestStruct::parse(&["-o", "out"]).output_path, "out");
+}
+
+argtea_impl! {
+    {
+        ("--output" | "-o", path) => {
+            output_path = path;
+        }
+
+        ("--jobs" | "-j", count: u32) => {
+            jobs = Some(count);
+        }
+
+        ("--color", choice) => {
+            color = choice;
+        }
+
+        (file) => {
+            files.push(file);
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct TestBuild {
+        output_path: String = "a.out".to_owned(),
+        jobs: u32 = 4,
+        color: Option<String> = Some("auto".to_owned()),
+        files: Vec<String>,
+    }
+
+    impl TestBuild {
+        fn parse(args: &[&str]) -> Result<Self, ParseError> {
+            let mut output_path = None;
+            let mut jobs = None;
+            let mut color = None;
+            let mut files = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            Ok(build!())
+        }
+    }
+}
+
+#[test]
+fn test_build() {
+    // Omitted flags fall back to the fields' defaults
+    assert_eq!(
+        TestBuild::parse(&["a"]),
+        Ok(TestBuild {
+            output_path: "a.out".to_owned(),
+            jobs: 4,
+            color: Some("auto".to_owned()),
+            files: vec!["a".to_owned()],
+        })
+    );
+
+    assert_eq!(
+        TestBuild::parse(&["-o", "out", "-j8", "--color=never", "b"]),
+        Ok(TestBuild {
+            output_path: "out".to_owned(),
+            jobs: 8,
+            color: Some("never".to_owned()),
+            files: vec!["b".to_owned()],
+        })
+    );
+}
+
+struct TestArgIndex;
+
+argtea_impl! {
+    {
+        ("-a") => {
+            log.push(("a", arg_index));
+        }
+
+        ("-b") => {
+            log.push(("b", arg_index));
+        }
+
+        #[strict_value]
+        ("--output" | "-o", output) => {
+            log.push(("output", arg_index));
+        }
+
+        #[eager]
+        ("--help") => {
+            log.push(("help", arg_index));
+        }
+
+        (file) => {
+            log.push(("file", arg_index));
+        }
+    }
+
+    impl TestArgIndex {
+        fn parse(args: &[&str]) -> Vec<(&'static str, usize)> {
+            let mut log = Vec::new();
+
+            parse!(testing::args(args).into_iter(), index: arg_index);
+
+            log
+        }
+    }
+}
+
+#[test]
+fn test_arg_index() {
+    assert_eq!(
+        TestArgIndex::parse(&[
+            "-ab",
+            "--output=x",
+            "-o",
+            "y",
+            "file",
+            "--help",
+            "-o",
+            "-a",
+            "-a-"
+        ]),
+        [
+            ("help", 5),
+            ("a", 0),
+            ("b", 0),
+            ("output", 1),
+            ("output", 2),
+            ("file", 4),
+            ("output", 6),
+            ("a", 7),
+            ("a", 8),
+            ("file", 8),
+        ]
+    );
+}
+
+struct TestConsumed;
+
+argtea_impl! {
+    {
+        ("--output" | "-o", output) => {}
+
+        #[strict_value]
+        ("--level", level) => {}
+
+        ("--") => { break }
+
+        (file) => {}
+    }
+
+    impl TestConsumed {
+        fn parse(args: &[&str]) -> (usize, Vec<String>) {
+            let mut args = testing::args(args).into_iter();
+            let n;
+            parse!(args, consumed: n);
+
+            (n, args.collect())
+        }
+
+        /// `parse!()` is `()` without `consumed: ...`, so it can be the last expression of a
+        /// function
+        fn parse_all(args: &[&str]) {
+            parse!(testing::args(args).into_iter())
+        }
+    }
+}
+
+#[test]
+fn test_parse_consumed() {
+    let remaining = |args: &[&str]| testing::args(args).into_iter().collect::<Vec<_>>();
+
+    assert_eq!(TestConsumed::parse(&[]), (0, vec![]));
+    assert_eq!(
+        TestConsumed::parse(&["a", "-o", "b", "-ofile", "c"]),
+        (5, vec![])
+    );
+    assert_eq!(
+        TestConsumed::parse(&["a", "--", "b", "c"]),
+        (2, remaining(&["b", "c"]))
+    );
+    assert_eq!(
+        TestConsumed::parse(&["--output=x", "--level", "--"]),
+        (3, vec![])
+    );
+    assert_eq!(
+        TestConsumed::parse(&["--level", "--", "a"]),
+        (2, remaining(&["a"]))
+    );
+
+    let () = TestConsumed::parse_all(&["a", "--level", "1"]);
+}
+
+struct TestEager;
+
+argtea_impl! {
+    {
+        /// Displays this help message.
+        #[eager]
+        ("--help" | "-h") => {
+            return Ok(vec!["help".to_owned()]);
+        }
+
+        /// Prints the version.
+        #[eager]
+        #[strict_value]
+        (flag @ "--version") => {
+            log.push(flag.to_string());
+        }
+
+        (file) => {
+            if file.starts_with('-') {
+                return Err(format!("invalid flag `{file}`"));
+            }
+
+            log.push(file);
+        }
+    }
+
+    impl TestEager {
+        const FLAGS: &'static [Flag] = docs!();
+
+        fn parse(args: &[&str]) -> Result<Vec<String>, String> {
+            let mut log = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            Ok(log)
+        }
+    }
+}
+
+#[test]
+fn test_eager() {
+    assert_eq!(TestEager::FLAGS.len(), 3);
+
+    assert_eq!(
+        TestEager::parse(&["--nonsense", "--help"]),
+        Ok(vec!["help".to_owned()])
+    );
+    assert_eq!(
+        TestEager::parse(&["--nonsense", "-h"]),
+        Ok(vec!["help".to_owned()])
+    );
+    assert_eq!(
+        TestEager::parse(&["--nonsense"]),
+        Err("invalid flag `--nonsense`".to_owned())
+    );
+
+    // Eager flags only run once, before everything else
+    assert_eq!(
+        TestEager::parse(&["a", "--version", "b", "--version=x"]),
+        Ok(vec!["--version", "--version", "a", "b", "x"]
+            .into_iter()
+            .map(String::from)
+            .collect())
+    );
+}
+
+struct TestEagerValues;
+
+argtea_impl! {
+    {
+        ("--verbose" | "-v") => {
+            log.push("verbose".to_owned());
+        }
+
+        ("--output" | "-o", path) => {
+            log.push(format!("output {path:?}"));
+        }
+
+        #[eager]
+        ("--help" | "-h") => {
+            log.push("help".to_owned());
+        }
+
+        #[eager]
+        ("--config" | "-c", path) => {
+            log.push(format!("config {path:?}"));
+        }
+
+        ("--") => {
+            break;
+        }
+
+        (file) => {
+            log.push(file);
+        }
+    }
+
+    impl TestEagerValues {
+        fn parse(args: &[&str]) -> Vec<String> {
+            let mut log = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            log
+        }
+    }
+}
+
+#[test]
+fn test_eager_values() {
+    let tests: &[(&[&str], &[&str])] = &[
+        (&["-vh"], &["help", "verbose"]),
+        (&["--config=b.toml"], &["config Some(\"b.toml\")"]),
+        (
+            &["--config", "a.toml", "file"],
+            &["config Some(\"a.toml\")", "file"],
+        ),
+        (
+            &["-vca.toml", "file"],
+            &["config Some(\"a.toml\")", "verbose", "file"],
+        ),
+        (&["--config", "--help"], &["config Some(\"--help\")"]),
+        (&["-c"], &["config None"]),
+        (&["-ohelp"], &["output Some(\"help\")"]),
+        (&["-h=x", "file"], &["help", "x", "file"]),
+        (&["-v--", "--help"], &["verbose"]),
+        (&["-o", "-h"], &["output Some(\"-h\")"]),
+        (&["--output", "--help"], &["output Some(\"--help\")"]),
+        (&["file", "--", "--help", "-h"], &["file"]),
+    ];
+
+    for (test, expected) in tests {
+        assert_eq!(TestEagerValues::parse(test), *expected, "{test:?}");
+    }
+}
+
+struct TestPackageHelp;
+
+argtea_impl! {
+    {
+        /// Displays this help message.
+        ("--help" | "-h") => {}
+    }
+
+    impl TestPackageHelp {
+        const HELP: &'static str = crate::simple_format!(
+            env!("CARGO_PKG_NAME")
+            concat!("version ", env!("CARGO_PKG_VERSION"))
+            docs!()
+            ::core::concat!("end")
+        );
+    }
+}
+
+#[test]
+fn test_package_help() {
+    assert_eq!(
+        TestPackageHelp::HELP,
+        concat!(
+            "argtea\nversion ",
+            env!("CARGO_PKG_VERSION"),
+            "\n  --help, -h \n    Displays this help message.\n\n\nend\n"
+        )
+    );
+}
+
+struct TestHelpWidth;
+
+argtea_impl! {
+    #[help_width(32)]
+    {
+        /// Sets the output file path, which is `a.out` by default.
+        ("--output" | "-o", path) => {}
+
+        /// Displays this help message.
+        ("--help" | "-h") => {}
+    }
+
+    impl TestHelpWidth {
+        const SIMPLE: &'static str = crate::simple_format!("Options:" docs!());
+        const WIDE: &'static str = crate::simple_format!({ width: 80 } "Options:" docs!());
+        const TABLE: &'static str = crate::table_format!({ max_width: 16 } docs!());
+        const CONCAT: &'static str = concat!("a", "b");
+    }
+}
+
+#[test]
+fn test_help_width() {
+    assert_eq!(
+        TestHelpWidth::SIMPLE,
+        "Options:\n  --output, -o <path> \n    Sets the output file path,\n    which is `a.out` by default.\n\n\n\
+         \x20 --help, -h \n    Displays this help message.\n\n\n"
+    );
+    assert_eq!(
+        TestHelpWidth::WIDE,
+        "Options:\n  --output, -o <path> \n    Sets the output file path, which is `a.out` by default.\n\n\n\
+         \x20 --help, -h \n    Displays this help message.\n\n\n"
+    );
+    assert_eq!(
+        TestHelpWidth::TABLE,
+        "  --output, -o <path>\n              Sets the output\n              file path, which\n              is `a.out` by\n              default.\n\
+         \x20 --help, -h  Displays this help\n              message.\n"
+    );
+    assert_eq!(TestHelpWidth::CONCAT, "ab");
+}
+
+struct TestNestedParse;
+
+argtea_impl! {
+    {
+        ("-v") => {
+            verbose = true;
+        }
+
+        (file) => {
+            files.push(file);
+        }
+    }
+
+    impl TestNestedParse {
+        fn parse_if(interactive: bool) -> (bool, Vec<String>) {
+            let mut verbose = false;
+            let mut files = Vec::new();
+
+            if interactive {
+                parse!(["-v".to_owned()].into_iter());
+            } else {
+                let flag_buf = "a";
+                parse!([flag_buf.to_owned(), "b".to_owned()].into_iter());
+            }
+
+            (verbose, files)
+        }
+
+        fn parse_match(args: Option<&[&str]>) -> (bool, Vec<String>) {
+            let mut verbose = false;
+            let mut files = Vec::new();
+
+            match args {
+                Some(args) => {
+                    parse!(testing::args(args).into_iter());
+                }
+                None => {}
+            }
+
+            (verbose, files)
+        }
+
+        fn parse_closure(lines: &[&str]) -> Vec<(bool, Vec<String>)> {
+            lines
+                .iter()
+                .map(|line| {
+                    let mut verbose = false;
+                    let mut files = Vec::new();
+
+                    parse!(line.split_whitespace().map(str::to_owned));
+
+                    (verbose, files)
+                })
+                .collect()
+        }
+    }
+}
+
+#[test]
+fn test_nested_parse() {
+    assert_eq!(TestNestedParse::parse_if(true), (true, vec![]));
+    assert_eq!(
+        TestNestedParse::parse_if(false),
+        (false, vec!["a".to_owned(), "b".to_owned()])
+    );
+
+    assert_eq!(
+        TestNestedParse::parse_match(Some(&["x", "-v"])),
+        (true, vec!["x".to_owned()])
+    );
+    assert_eq!(TestNestedParse::parse_match(None), (false, vec![]));
+
+    assert_eq!(
+        TestNestedParse::parse_closure(&["-v a", "b"]),
+        [(true, vec!["a".to_owned()]), (false, vec!["b".to_owned()])]
+    );
+}
+
+struct TestGenerics {
+    verbose: bool,
+    files: Vec<String>,
+}
+
+argtea_impl! {
+    {
+        ("-v") => {
+            verbose = true;
+        }
+
+        (file) => {
+            files.push(file);
+        }
+    }
+
+    impl TestGenerics {
+        fn parse_from<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+            let mut verbose = false;
+            let mut files = Vec::new();
+
+            parse!(args.into_iter());
+
+            Ok(Self { verbose, files })
+        }
+
+        fn count<I>(mut args: I) -> usize
+        where
+            I: Iterator<Item = String>,
+        {
+            let mut verbose = false;
+            let mut files = Vec::new();
+
+            let consumed;
+            parse!(args, consumed: consumed);
+
+            consumed + usize::from(verbose) + files.len()
+        }
+    }
+}
+
+#[test]
+fn test_generics() {
+    let parsed = TestGenerics::parse_from(vec!["-v".to_owned(), "a".to_owned()]).unwrap();
+    assert!(parsed.verbose);
+    assert_eq!(parsed.files, ["a"]);
+
+    let parsed = TestGenerics::parse_from(std::env::args().take(0)).unwrap();
+    assert!(!parsed.verbose);
+    assert!(parsed.files.is_empty());
+
+    assert_eq!(
+        TestGenerics::count(["a", "b"].into_iter().map(str::to_owned)),
+        4
+    );
+}
+
+trait ParseArgs: Sized {
+    fn parse_args(args: &[&str]) -> Result<Self, String>;
+}
+
+argtea_impl! {
+    {
+        ("--output" | "-o", path) => {
+            output = path.ok_or("expected a path")?;
+        }
+
+        (file) => {
+            files.push(file);
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct TestTraitImpls {
+        output: String = "a.out".to_owned(),
+        files: Vec<String>,
+    }
+
+    impl ParseArgs for TestTraitImpls {
+        fn parse_args(args: &[&str]) -> Result<Self, String> {
+            Ok(parse_fields!(testing::args(args).into_iter()))
+        }
+    }
+
+    impl FromIterator<String> for TestTraitImpls {
+        fn from_iter<I: IntoIterator<Item = String>>(args: I) -> Self {
+            let mut output = String::new();
+            let mut files = Vec::new();
+
+            let parse = || -> Result<(), String> {
+                parse!(args.into_iter());
+                Ok(())
+            };
+            parse().unwrap();
+
+            Self { output, files }
+        }
+    }
+
+    impl TestTraitImpls {
+        fn files(&self) -> usize {
+            self.files.len()
+        }
+    }
+}
+
+struct TestAbout;
+
+argtea_impl! {
+    #[about = "Compiles C files."]
+    #[help_width(30)]
+    {
+        /// Sets the output file path, which is `a.out` by default.
+        ("--output" | "-o", path) => {}
+    }
+
+    impl ParseArgs for TestAbout {
+        fn parse_args(_args: &[&str]) -> Result<Self, String> {
+            Ok(TestAbout)
+        }
+    }
+
+    impl TestAbout {
+        const HELP: &'static str = crate::simple_format!(Self::ABOUT "" "Options:" docs!());
+        const FLAGS: &'static [Flag] = docs!();
+    }
+
+    // `ABOUT` is only added to the first impl block
+    impl TestAbout {
+        fn help() -> String {
+            let mut buf = format!("{}\n\n", Self::ABOUT);
+            wrapping_format(&mut buf, Self::FLAGS);
+            buf
+        }
+    }
+}
+
+#[test]
+fn test_about() {
+    assert!(TestAbout::parse_args(&[]).is_ok());
+    assert_eq!(TestAbout::ABOUT, "Compiles C files.");
+    assert_eq!(
+        TestAbout::HELP,
+        "Compiles C files.\n\nOptions:\n  --output, -o <path> \n    Sets the output file path,\n    which is `a.out` by\n    default.\n\n\n"
+    );
+    assert!(TestAbout::help().starts_with("Compiles C files.\n\n  -o, --output <path>\n"));
+}
+
+#[test]
+fn test_trait_impls() {
+    let parsed = TestTraitImpls::parse_args(&["a", "-o", "b"]).unwrap();
+    assert_eq!(parsed.output, "b");
+    assert_eq!(parsed.files(), 1);
+    assert_eq!(
+        TestTraitImpls::parse_args(&["-o"]).unwrap_err(),
+        "expected a path"
+    );
+
+    let collected: TestTraitImpls = ["-o", "c", "d"].into_iter().map(str::to_owned).collect();
+    assert_eq!(
+        collected,
+        TestTraitImpls {
+            output: "c".to_owned(),
+            files: vec!["d".to_owned()],
+        }
+    );
+}
+
+struct TestPositionals;
+
+argtea_impl! {
+    {
+        ("-v") => {
+            log.push("-v".to_owned());
+        }
+
+        ("--") => {
+            break;
+        }
+
+        #[strict_value]
+        (file) => {
+            log.push(file);
+        }
+    }
+
+    impl TestPositionals {
+        fn parse_strict(args: &[&str]) -> Result<Vec<String>, ParseError> {
+            let mut log = Vec::new();
+            let mut args = testing::args(args).into_iter();
+
+            parse!(args);
+
+            log.extend(args);
+            Ok(log)
+        }
+    }
+}
+
+argtea_impl! {
+    {
+        ("-v") => {
+            log.push("-v".to_owned());
+        }
+
+        (file) => {
+            log.push(file);
+        }
+    }
+
+    impl TestPositionals {
+        fn parse_raw(args: &[&str]) -> Vec<String> {
+            let mut log = Vec::new();
+            parse!(testing::args(args).into_iter());
+            log
+        }
+    }
+}
+
+#[test]
+fn test_strict_positional() {
+    assert_eq!(
+        TestPositionals::parse_strict(&["a", "-v", "-", "b"]).unwrap(),
+        ["a", "-v", "-", "b"]
+    );
+    assert_eq!(
+        TestPositionals::parse_strict(&["a", "-x", "b"]),
+        Err(ParseError::UnknownFlag {
+            flag: "-x".to_owned(),
+            suggestion: None,
+        })
+    );
+    assert_eq!(
+        TestPositionals::parse_strict(&["--unknown"]),
+        Err(ParseError::UnknownFlag {
+            flag: "--unknown".to_owned(),
+            suggestion: None,
+        })
+    );
+    assert_eq!(
+        TestPositionals::parse_strict(&["a", "--", "-x"]).unwrap(),
+        ["a", "-x"]
+    );
+
+    assert_eq!(
+        TestPositionals::parse_raw(&["a", "-x", "-v", "--unknown"]),
+        ["a", "-x", "-v", "--unknown"]
+    );
+}
+
+struct TestSuggestions;
+
+argtea_impl! {
+    {
+        ("--version" | "-V") => {}
+
+        ("--verbose") => {}
+
+        #[strict_value]
+        (_file) => {}
+    }
+
+    impl TestSuggestions {
+        fn parse(args: &[&str]) -> Result<(), ParseError> {
+            parse!(testing::args(args).into_iter());
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn test_unknown_flag_suggestions() {
+    let unknown = |flag: &str, suggestion| {
+        Err(ParseError::UnknownFlag {
+            flag: flag.to_owned(),
+            suggestion,
+        })
+    };
+
+    assert_eq!(
+        TestSuggestions::parse(&["--verison"]),
+        unknown("--verison", Some("--version"))
+    );
+    assert_eq!(
+        TestSuggestions::parse(&["--verbos"]),
+        unknown("--verbos", Some("--verbose"))
+    );
+    assert_eq!(
+        TestSuggestions::parse(&["--unknown"]),
+        unknown("--unknown", None)
+    );
+    assert_eq!(TestSuggestions::parse(&["a", "-V"]), Ok(()));
+}
+
+#[test]
+fn test_parse_error_display() {
+    let errors = [
+        (
+            ParseError::UnknownFlag {
+                flag: "-x".to_owned(),
+                suggestion: None,
+            },
+            "unknown flag `-x`",
+        ),
+        (
+            ParseError::UnknownFlag {
+                flag: "--verbos".to_owned(),
+                suggestion: Some("--verbose"),
+            },
+            "unknown flag `--verbos`, did you mean `--verbose`?",
+        ),
+        (
+            ParseError::MissingValue {
+                flag: "-o".to_owned(),
+                param: "path",
+            },
+            "expected <path> after `-o`",
+        ),
+        (
+            ParseError::InvalidValue {
+                flag: "--width".to_owned(),
+                value: "wide".to_owned(),
+                reason: "expected u32".to_owned(),
+            },
+            "invalid value `wide` for `--width`: expected u32",
+        ),
+        (
+            ParseError::Custom("too many files".to_owned()),
+            "too many files",
+        ),
+    ];
+
+    for (error, expected) in errors {
+        assert_eq!(error.to_string(), expected);
+        assert_eq!(String::from(error), expected);
+    }
+
+    assert_eq!(
+        ParseError::from("too many files".to_owned()),
+        ParseError::Custom("too many files".to_owned())
+    );
+}
+
+struct TestExpectValue;
+
+argtea_impl! {
+    {
+        (flag @ "--output" | "-o", path) => {
+            output = Some(crate::expect_value(flag, path)?);
+        }
+
+        (flag @ "--jobs" | "-j", count) => {
+            jobs = crate::expect_parsed(flag, count)?;
+        }
+
+        (_arg) => {}
+    }
+
+    impl TestExpectValue {
+        fn parse(args: &[&str]) -> Result<(Option<String>, u32), ParseError> {
+            let mut output = None;
+            let mut jobs = 1;
+
+            parse!(testing::args(args).into_iter());
+
+            Ok((output, jobs))
+        }
+    }
+}
+
+#[test]
+fn test_expect_value() {
+    assert_eq!(
+        TestExpectValue::parse(&["-o", "out", "--jobs=4"]),
+        Ok((Some("out".to_owned()), 4))
+    );
+    assert_eq!(
+        TestExpectValue::parse(&["-j"]),
+        Err(ParseError::MissingValue {
+            flag: "-j".to_owned(),
+            param: "value"
+        })
+    );
+    assert_eq!(
+        TestExpectValue::parse(&["--output"])
+            .unwrap_err()
+            .to_string(),
+        "expected <value> after `--output`"
+    );
+    assert_eq!(
+        TestExpectValue::parse(&["--jobs", "-1"]),
+        Err(ParseError::InvalidValue {
+            flag: "--jobs".to_owned(),
+            value: "-1".to_owned(),
+            reason: "invalid digit found in string".to_owned(),
+        })
+    );
+    assert_eq!(
+        TestExpectValue::parse(&["-jfour"]).unwrap_err().to_string(),
+        "invalid value `four` for `-j`: invalid digit found in string"
+    );
+}
+
+#[test]
+fn test_parse_bool() {
+    use crate::value::{parse_bool, parse_bool_opt};
+
+    for value in ["yes", "true", "on", "1", "YES", "True", "oN"] {
+        assert_eq!(parse_bool("--color", value), Ok(true), "{value}");
+    }
+    for value in ["no", "false", "off", "0", "NO", "False", "oFF"] {
+        assert_eq!(parse_bool("--color", value), Ok(false), "{value}");
+    }
+
+    assert_eq!(
+        parse_bool("--follow-symlinks", "2"),
+        Err(ParseError::InvalidValue {
+            flag: "--follow-symlinks".to_owned(),
+            value: "2".to_owned(),
+            reason: "expected one of `yes`, `no`, `true`, `false`, `on`, `off`, `1`, `0`"
+                .to_owned(),
+        })
+    );
+    assert!(parse_bool("--color", "").is_err());
+
+    assert_eq!(parse_bool_opt("--color", None, true), Ok(true));
+    assert_eq!(parse_bool_opt("--color", None, false), Ok(false));
+    assert_eq!(
+        parse_bool_opt("--color", Some("Off".to_owned()), true),
+        Ok(false)
+    );
+    assert!(parse_bool_opt("--color", Some("auto".to_owned()), true).is_err());
+}
+
+struct TestDefines;
+
+argtea_impl! {
+    {
+        (flag @ "--define" | "-D", define) => {
+            let define = crate::expect_value(flag, define)?;
+            defines.push(crate::value::split_kv_opt(flag, &define)?);
+        }
+
+        (flag @ "--set", pair) => {
+            let pair = crate::expect_value(flag, pair)?;
+            let (name, value) = crate::value::split_kv(flag, &pair)?;
+            defines.push((name, Some(value)));
+        }
+
+        (_arg) => {}
+    }
+
+    impl TestDefines {
+        fn parse(args: &[&str]) -> Result<Vec<(String, Option<String>)>, ParseError> {
+            let mut defines = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            Ok(defines)
+        }
+    }
+}
+
+#[test]
+fn test_split_kv() {
+    let define = |name: &str, value: Option<&str>| (name.to_owned(), value.map(str::to_owned));
+
+    // Only the first `=` of `--define=...` is removed by the parser, so values can contain `=`
+    assert_eq!(
+        TestDefines::parse(&[
+            "--define=A=b=c",
+            "--define",
+            "B=",
+            "-DC=1",
+            "-D=D=2",
+            "-D",
+            "E",
+            "--set=F=",
+        ]),
+        Ok(vec![
+            define("A", Some("b=c")),
+            define("B", Some("")),
+            define("C", Some("1")),
+            define("D", Some("2")),
+            define("E", None),
+            define("F", Some("")),
+        ])
+    );
+    assert_eq!(
+        TestDefines::parse(&["--define==b"]),
+        Err(ParseError::InvalidValue {
+            flag: "--define".to_owned(),
+            value: "=b".to_owned(),
+            reason: "expected a name before `=`".to_owned(),
+        })
+    );
+    assert_eq!(
+        TestDefines::parse(&["--set=A"]),
+        Err(ParseError::InvalidValue {
+            flag: "--set".to_owned(),
+            value: "A".to_owned(),
+            reason: "expected `NAME=VALUE`".to_owned(),
+        })
+    );
+    assert_eq!(
+        TestDefines::parse(&["-D"]),
+        Err(ParseError::MissingValue {
+            flag: "-D".to_owned(),
+            param: "value",
+        })
+    );
+    assert_eq!(
+        crate::value::split_kv_opt("-D", "")
+            .unwrap_err()
+            .to_string(),
+        "invalid value `` for `-D`: expected a name"
+    );
+}
+
+#[test]
+fn test_in_range() {
+    use crate::value::in_range;
+
+    let reason = |result: Result<i32, ParseError>| match result {
+        Err(ParseError::InvalidValue { reason, .. }) => reason,
+        other => panic!("expected an invalid value, found {other:?}"),
+    };
+
+    assert_eq!(in_range("-j", 1, 1..=64), Ok(1));
+    assert_eq!(in_range("-j", 64, 1..=64), Ok(64));
+    assert_eq!(
+        in_range("-j", 65, 1..=64),
+        Err(ParseError::InvalidValue {
+            flag: "-j".to_owned(),
+            value: "65".to_owned(),
+            reason: "expected a value from 1 to 64".to_owned(),
+        })
+    );
+    assert_eq!(
+        reason(in_range("-j", 0, 1..=64)),
+        "expected a value from 1 to 64"
+    );
+
+    assert_eq!(in_range("-j", 63, 1..64), Ok(63));
+    assert_eq!(
+        reason(in_range("-j", 64, 1..64)),
+        "expected a value that is at least 1 and less than 64"
+    );
+    assert_eq!(in_range("-j", i32::MAX, 1..), Ok(i32::MAX));
+    assert_eq!(
+        reason(in_range("-j", 0, 1..)),
+        "expected a value that is at least 1"
+    );
+    assert_eq!(in_range("-j", i32::MIN, ..=0), Ok(i32::MIN));
+    assert_eq!(
+        reason(in_range("-j", 1, ..=0)),
+        "expected a value that is at most 0"
+    );
+    assert_eq!(
+        reason(in_range("-j", 0, ..0)),
+        "expected a value that is less than 0"
+    );
+    assert_eq!(in_range("-j", -5, ..), Ok(-5));
+
+    let exclusive_start = (std::ops::Bound::Excluded(0), std::ops::Bound::Included(10));
+    assert_eq!(in_range("-j", 10, exclusive_start), Ok(10));
+    assert_eq!(
+        reason(in_range("-j", 0, exclusive_start)),
+        "expected a value that is greater than 0 and at most 10"
+    );
+
+    assert_eq!(in_range("--ratio", 0.5, 0.0..=1.0), Ok(0.5));
+    assert!(in_range("--ratio", f64::NAN, 0.0..=1.0).is_err());
+}
+
+#[test]
+fn test_one_of() {
+    use crate::value::one_of;
+
+    const PROFILES: &[&str] = &["fast", "small", "Debug"];
+    const EXPECTED: &str = "expected one of `fast`, `small`, `Debug`";
+
+    assert_eq!(one_of("-O", "fast", PROFILES, false), Ok("fast"));
+    assert_eq!(one_of("-O", "Debug", PROFILES, false), Ok("Debug"));
+    assert_eq!(one_of("-O", "SMALL", PROFILES, true), Ok("small"));
+    assert_eq!(one_of("-O", "debug", PROFILES, true), Ok("Debug"));
+
+    assert_eq!(
+        one_of("-O", "debug", PROFILES, false),
+        Err(ParseError::InvalidValue {
+            flag: "-O".to_owned(),
+            value: "debug".to_owned(),
+            reason: format!("{EXPECTED} (did you mean `Debug`?)"),
+        })
+    );
+    assert_eq!(
+        one_of("-O", "smal", PROFILES, false)
+            .unwrap_err()
+            .to_string(),
+        format!("invalid value `smal` for `-O`: {EXPECTED} (did you mean `small`?)")
+    );
+    assert_eq!(
+        one_of("-O", "DEBUGG", PROFILES, true)
+            .unwrap_err()
+            .to_string(),
+        format!("invalid value `DEBUGG` for `-O`: {EXPECTED} (did you mean `Debug`?)")
+    );
+    assert_eq!(
+        one_of("-O", "size", PROFILES, true)
+            .unwrap_err()
+            .to_string(),
+        format!("invalid value `size` for `-O`: {EXPECTED}")
+    );
+    assert_eq!(
+        one_of("-O", "", PROFILES, false).unwrap_err().to_string(),
+        format!("invalid value `` for `-O`: {EXPECTED}")
+    );
+
+    // Only ASCII letters are compared case-insensitively (like in `parse_bool`)
+    assert_eq!(one_of("--mode", "ÉTÉ", &["été"], true).ok(), None);
+    assert_eq!(one_of("--mode", "éTé", &["été"], true), Ok("été"));
+}
+
+#[test]
+fn test_parse_size() {
+    use crate::value::{expect_size, parse_size, ValueError};
+
+    let sizes: &[(&str, u64)] = &[
+        ("0", 0),
+        ("512", 512),
+        ("512B", 512),
+        ("512b", 512),
+        ("1k", 1_000),
+        ("1kB", 1_000),
+        ("1KB", 1_000),
+        ("10M", 10_000_000),
+        ("10MB", 10_000_000),
+        ("10mb", 10_000_000),
+        ("3G", 3_000_000_000),
+        ("2TB", 2_000_000_000_000),
+        ("1PB", 1_000_000_000_000_000),
+        ("1Ki", 1024),
+        ("1KiB", 1024),
+        ("1kib", 1024),
+        ("4MiB", 4 << 20),
+        ("1.5GiB", 3 << 29),
+        ("1TiB", 1 << 40),
+        ("1PiB", 1 << 50),
+        ("0.5k", 500),
+        ("1.0001k", 1_000),
+        ("1.5", 1),
+        ("0.000000000000000000001PB", 0),
+        ("007", 7),
+        ("18446744073709551615", u64::MAX),
+        ("16383.999999999999999PiB", 18446744073709551614),
+    ];
+    for &(value, size) in sizes {
+        assert_eq!(parse_size(value), Ok(size), "{value}");
+    }
+
+    let errors: &[(&str, ValueError)] = &[
+        ("", ValueError::Empty),
+        ("MB", ValueError::InvalidNumber),
+        ("-1", ValueError::InvalidNumber),
+        (".5M", ValueError::InvalidNumber),
+        ("1.M", ValueError::InvalidNumber),
+        ("1.", ValueError::InvalidNumber),
+        ("10XB", ValueError::UnknownUnit("XB".to_owned())),
+        ("10BB", ValueError::UnknownUnit("BB".to_owned())),
+        ("10 MB", ValueError::UnknownUnit(" MB".to_owned())),
+        ("10MB ", ValueError::UnknownUnit("MB ".to_owned())),
+        ("1EiB", ValueError::UnknownUnit("EiB".to_owned())),
+        ("1.2.3", ValueError::UnknownUnit(".3".to_owned())),
+        ("5€", ValueError::UnknownUnit("€".to_owned())),
+        ("18446744073709551616", ValueError::Overflow),
+        ("16384PiB", ValueError::Overflow),
+        ("18446744073709551.616kB", ValueError::Overflow),
+        (
+            "999999999999999999999999999999999999999999999999",
+            ValueError::Overflow,
+        ),
+    ];
+    for (value, err) in errors {
+        assert_eq!(parse_size(value).as_ref(), Err(err), "{value}");
+    }
+
+    assert_eq!(
+        expect_size("--cache", Some("1.5GiB".to_owned())),
+        Ok(3 << 29)
+    );
+    assert_eq!(
+        expect_size("--cache", Some("1.5GiX".to_owned())),
+        Err(ParseError::InvalidValue {
+            flag: "--cache".to_owned(),
+            value: "1.5GiX".to_owned(),
+            reason: "unknown unit `GiX`".to_owned(),
+        })
+    );
+    assert_eq!(
+        expect_size("--cache", None),
+        Err(ParseError::MissingValue {
+            flag: "--cache".to_owned(),
+            param: "value",
+        })
+    );
+}
+
+#[test]
+fn test_parse_duration() {
+    use crate::value::{expect_duration, parse_duration, ValueError};
+    use std::time::Duration;
+
+    let durations: &[(&str, Duration)] = &[
+        ("0s", Duration::ZERO),
+        ("250ms", Duration::from_millis(250)),
+        ("30s", Duration::from_secs(30)),
+        ("2m", Duration::from_secs(120)),
+        ("3h", Duration::from_secs(3 * 60 * 60)),
+        ("7d", Duration::from_secs(7 * 24 * 60 * 60)),
+        ("2m30s", Duration::from_secs(150)),
+        ("1h30m", Duration::from_secs(90 * 60)),
+        ("1d2h3m4s5ms", Duration::new(93_784, 5_000_000)),
+        ("30s1h", Duration::from_secs(3630)),
+        ("1s1s", Duration::from_secs(2)),
+        ("1.5s", Duration::from_millis(1500)),
+        ("0.5ms", Duration::from_micros(500)),
+        ("1.5m30s", Duration::from_secs(120)),
+        ("0.0000000001s", Duration::ZERO),
+        ("90m", Duration::from_secs(5400)),
+        ("18446744073709551615s", Duration::from_secs(u64::MAX)),
+    ];
+    for &(value, duration) in durations {
+        assert_eq!(parse_duration(value), Ok(duration), "{value}");
+    }
+
+    let errors: &[(&str, ValueError)] = &[
+        ("", ValueError::Empty),
+        ("s", ValueError::InvalidNumber),
+        ("-1s", ValueError::InvalidNumber),
+        ("1h-30m", ValueError::InvalidNumber),
+        ("1h ", ValueError::InvalidNumber),
+        (".5s", ValueError::InvalidNumber),
+        ("1.s", ValueError::InvalidNumber),
+        ("30", ValueError::MissingUnit),
+        ("1h30", ValueError::MissingUnit),
+        ("1 h", ValueError::MissingUnit),
+        ("30S", ValueError::UnknownUnit("S".to_owned())),
+        ("30sec", ValueError::UnknownUnit("sec".to_owned())),
+        ("1w", ValueError::UnknownUnit("w".to_owned())),
+        ("1us", ValueError::UnknownUnit("us".to_owned())),
+        ("18446744073709551616s", ValueError::Overflow),
+        ("213503982334602d", ValueError::Overflow),
+        ("1s18446744073709551615s", ValueError::Overflow),
+        (
+            "999999999999999999999999999999999999999999999999ms",
+            ValueError::Overflow,
+        ),
+    ];
+    for (value, err) in errors {
+        assert_eq!(parse_duration(value).as_ref(), Err(err), "{value}");
+    }
+
+    assert_eq!(
+        expect_duration("--timeout", Some("2m30s".to_owned())),
+        Ok(Duration::from_secs(150))
+    );
+    assert_eq!(
+        expect_duration("--timeout", Some("2x".to_owned())),
+        Err(ParseError::InvalidValue {
+            flag: "--timeout".to_owned(),
+            value: "2x".to_owned(),
+            reason: "unknown unit `x`".to_owned(),
+        })
+    );
+    assert_eq!(ValueError::Overflow.to_string(), "value is too large");
+}
+
+struct TestTrailing;
+
+argtea_impl! {
+    {
+        ("--output" | "-o", path) => {
+            output = path;
+        }
+
+        ("--verbose" | "-v") => {}
+
+        #[eager]
+        ("--help") => {
+            show_help = true;
+        }
+
+        (file) => {
+            files.push(file);
+        }
+    }
+
+    impl TestTrailing {
+        fn parse(args: &[&str]) -> (Option<String>, Vec<String>, Vec<String>, usize) {
+            let mut output = None;
+            let mut files = Vec::new();
+            let mut show_help = false;
+            let mut trailing = Vec::new();
+
+            let consumed;
+            parse!(testing::args(args).into_iter(), trailing: trailing, consumed: consumed);
+
+            assert!(!show_help);
+            (output, files, trailing, consumed)
+        }
+
+        fn parse_help(args: &[&str]) -> bool {
+            let mut output = None;
+            let mut files = Vec::new();
+            let mut show_help = false;
+            let mut trailing = Vec::new();
+
+            parse!(testing::args(args).into_iter(), index: _i, trailing: trailing, strict);
+
+            let _ = (output, files);
+            show_help
+        }
+    }
+}
+
+#[test]
+fn test_trailing() {
+    let strings = |strs: &[&str]| strs.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        TestTrailing::parse(&["-o", "out", "--", "-x", "file"]),
+        (Some("out".to_owned()), vec![], strings(&["-x", "file"]), 5)
+    );
+    assert_eq!(
+        TestTrailing::parse(&["a", "-o", "--", "b"]),
+        (Some("--".to_owned()), strings(&["a", "b"]), vec![], 4)
+    );
+    assert_eq!(
+        TestTrailing::parse(&["-o--", "--", "--"]),
+        (Some("--".to_owned()), vec![], strings(&["--"]), 3)
+    );
+
+    // A `--` at the end of a cluster is a `--` of its own
+    assert_eq!(
+        TestTrailing::parse(&["a", "-v--", "-x", "--"]),
+        (None, strings(&["a"]), strings(&["-x", "--"]), 4)
+    );
+
+    assert!(TestTrailing::parse_help(&["a", "--help", "--"]));
+    assert!(!TestTrailing::parse_help(&["a", "--", "--help"]));
+    assert!(!TestTrailing::parse_help(&["-v--", "--help"]));
+}
+
+struct TestRemaining;
+
+argtea_impl! {
+    {
+        ("-a") => {
+            log.push("a".to_owned());
+        }
+
+        ("--stop" | "-b") => {
+            break;
+        }
+
+        ("-c") => {
+            log.push("c".to_owned());
+        }
+
+        #[strict_value]
+        ("--strict", value) => {
+            if value.is_none() {
+                break;
+            }
+        }
+
+        (arg) => {
+            log.push(arg);
+        }
+    }
+
+    impl TestRemaining {
+        fn parse(args: &[&str]) -> (Vec<String>, Vec<String>, usize) {
+            let mut log = Vec::new();
+            let mut remaining = Vec::new();
+
+            let consumed;
+            parse!(testing::args(args).into_iter(), remaining: remaining, consumed: consumed);
+
+            (log, remaining, consumed)
+        }
+    }
+}
+
+#[test]
+fn test_remaining() {
+    let strings = |strs: &[&str]| strs.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        TestRemaining::parse(&["-abc", "val"]),
+        (strings(&["a"]), strings(&["-c", "val"]), 1)
+    );
+    assert_eq!(
+        TestRemaining::parse(&["-ab=x", "val"]),
+        (strings(&["a"]), strings(&["x", "val"]), 1)
+    );
+    assert_eq!(
+        TestRemaining::parse(&["-c", "--stop=value", "-a"]),
+        (strings(&["c"]), strings(&["value", "-a"]), 2)
+    );
+    assert_eq!(
+        TestRemaining::parse(&["--strict", "-c", "file"]),
+        (vec![], strings(&["-c", "file"]), 1)
+    );
+    assert_eq!(
+        TestRemaining::parse(&["-ac", "--verbose=x", "file"]),
+        (strings(&["a", "c", "--verbose", "x", "file"]), vec![], 3)
+    );
+}
+
+struct TestSubcommandBoundary;
+
+argtea_impl! {
+    {
+        ("-C", path) => {
+            dir = path;
+        }
+
+        ("--verbose" | "-v") => {
+            verbose = true;
+        }
+
+        (subcommand) => {
+            rest.push(subcommand);
+            break;
+        }
+    }
+
+    impl TestSubcommandBoundary {
+        fn parse(args: &[&str]) -> (Option<String>, bool, Vec<String>) {
+            let mut dir = None;
+            let mut verbose = false;
+            let mut rest = Vec::new();
+
+            parse!(testing::args(args).into_iter(), remaining: rest);
+
+            (dir, verbose, rest)
+        }
+    }
+}
+
+#[test]
+fn test_subcommand_boundary() {
+    assert_eq!(
+        TestSubcommandBoundary::parse(&["-C", "dir", "commit", "-m", "x"]),
+        (
+            Some("dir".to_owned()),
+            false,
+            vec!["commit".into(), "-m".into(), "x".into()]
+        )
+    );
+    // The subcommand's own flags (even ones that the parent also has) are left for it
+    assert_eq!(
+        TestSubcommandBoundary::parse(&["-v", "commit", "-v", "--", "a"]),
+        (
+            None,
+            true,
+            vec!["commit".into(), "-v".into(), "--".into(), "a".into()]
+        )
+    );
+    assert_eq!(TestSubcommandBoundary::parse(&["-v"]), (None, true, vec![]));
+}
+
+struct TestFallible;
+
+argtea_impl! {
+    {
+        ("--output" | "-o", path) => {
+            output = path;
+        }
+
+        #[eager]
+        ("--verbose") => {
+            log.push("verbose".to_owned());
+        }
+
+        (arg) => {
+            log.push(arg);
+        }
+    }
+
+    impl TestFallible {
+        fn parse(
+            args: Vec<std::io::Result<String>>,
+        ) -> std::io::Result<(Option<String>, Vec<String>)> {
+            let mut output = None;
+            let mut log = Vec::new();
+
+            parse!(args.into_iter(), fallible);
+
+            Ok((output, log))
+        }
+
+        fn parse_logged(
+            args: Vec<Result<&str, &str>>,
+            log: &mut Vec<String>,
+        ) -> Result<usize, String> {
+            let mut output = None;
+            let mut args = args.into_iter().map(|arg| arg.map(str::to_owned));
+
+            let consumed;
+            parse!(args, fallible, consumed: consumed);
+            let _ = output;
+
+            Ok(consumed)
+        }
+    }
+}
+
+#[test]
+fn test_fallible() {
+    let error = || std::io::Error::other("unreadable argument file");
+
+    assert_eq!(
+        TestFallible::parse(vec![Ok("-o".to_owned()), Ok("out".to_owned())]).unwrap(),
+        (Some("out".to_owned()), vec![])
+    );
+
+    let err = TestFallible::parse(vec![
+        Ok("a".to_owned()),
+        Ok("--verbose".to_owned()),
+        Err(error()),
+        Ok("b".to_owned()),
+    ])
+    .unwrap_err();
+    assert_eq!(err.to_string(), "unreadable argument file");
+
+    // The arguments before the error are processed, and the error is converted with `From`
+    let mut log = Vec::new();
+    assert_eq!(
+        TestFallible::parse_logged(vec![Ok("a"), Ok("b"), Err("bad"), Ok("c")], &mut log),
+        Err("bad".to_owned())
+    );
+    assert_eq!(log, ["a", "b"]);
+
+    let mut log = Vec::new();
+    assert_eq!(
+        TestFallible::parse_logged(vec![Ok("-o"), Err("bad")], &mut log),
+        Err("bad".to_owned())
+    );
+    assert_eq!(
+        TestFallible::parse_logged(vec![Ok("--verbose"), Ok("a")], &mut log),
+        Ok(2)
+    );
+    assert_eq!(log, ["verbose", "a"]);
+}
+
+mod io_flag_set {
+    crate::argtea_flags! {
+        /// Flags for the input and output files.
+        pub(crate) io_flags(output, inputs) {
+            /// Sets the output file.
+            ("--output" | "-o", path) => {
+                *output = path;
+            }
+
+            /// Adds an input file.
+            ("--input" | "-i", path) => inputs.extend(path),
+
+            #[hidden]
+            ("--stdin") => {
+                inputs.push("-".to_owned());
+            },
+        }
+    }
+}
+
+mod net_flag_set {
+    crate::argtea_flags! {
+        pub(super) net_flags(port) {
+            /// Sets the port.
+            ("--port", number) if port.is_none() => {
+                *port = number;
+            }
+        }
+    }
+
+    crate::argtea_flags! {
+        pub(super) offline_flags {
+            /// Disables the network.
+            ("--offline") => {}
+        }
+    }
+}
+
+struct TestFlagSets;
+
+argtea_impl! {
+    {
+        use_flags!(io_flag_set::io_flags(output, files));
+        use_flags!(crate::tests::net_flag_set::net_flags(port));
+        use_flags!(net_flag_set::offline_flags);
+
+        /// Displays this help message.
+        ("--help") => {
+            help = true;
+        }
+
+        (arg) => {
+            files.push(arg);
+        }
+    }
+
+    impl TestFlagSets {
+        const FLAGS: &'static [Flag] = docs!();
+
+        fn parse(args: &[&str]) -> (Option<String>, Vec<String>, Option<String>, bool) {
+            let mut output = None;
+            let mut files = Vec::new();
+            let mut port = None;
+            let mut help = false;
+
+            parse!(testing::args(args).into_iter());
+
+            (output, files, port, help)
+        }
+    }
+}
+
+#[test]
+fn test_flag_sets() {
+    let flags = TestFlagSets::FLAGS
+        .iter()
+        .map(|flag| flag.flags)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        flags,
+        [
+            &["--output", "-o"][..],
+            &["--input", "-i"],
+            &["--port"],
+            &["--offline"],
+            &["--help"],
+            &[],
+        ]
+    );
+    assert_eq!(TestFlagSets::FLAGS[0].doc, [" Sets the output file."]);
+
+    assert_eq!(
+        TestFlagSets::parse(&[
+            "a",
+            "-i",
+            "b",
+            "--stdin",
+            "-o",
+            "out",
+            "--port=80",
+            "--port=81"
+        ]),
+        (
+            Some("out".to_owned()),
+            vec![
+                "a".into(),
+                "b".into(),
+                "-".into(),
+                "--port".into(),
+                "81".into()
+            ],
+            Some("80".to_owned()),
+            false
+        )
+    );
+    assert_eq!(
+        TestFlagSets::parse(&["--offline", "--help"]),
+        (None, vec![], None, true)
+    );
+}
+
+struct TestAsync;
+
+/// Stands in for a value that is fetched over the network.
+async fn fetch_profile(name: String) -> String {
+    format!("profile {name}")
+}
+
+/// Runs a future that never waits for anything.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+
+    loop {
+        if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+argtea_impl! {
+    {
+        ("--profile", name) => {
+            let name = name.ok_or("expected a profile name")?;
+            profiles.push(fetch_profile(name).await);
+        }
+
+        (file) => {
+            profiles.push(std::future::ready(file).await);
+        }
+    }
+
+    impl TestAsync {
+        async fn parse(args: &[&str]) -> Result<Vec<String>, String> {
+            let mut profiles = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            Ok(profiles)
+        }
+    }
+}
+
+#[test]
+fn test_async() {
+    assert_eq!(
+        block_on(TestAsync::parse(&["--profile", "a", "b", "--profile=c"])).unwrap(),
+        ["profile a", "b", "profile c"]
+    );
+    assert_eq!(
+        block_on(TestAsync::parse(&["--profile"])).unwrap_err(),
+        "expected a profile name"
+    );
+}
+
+struct TestKeyValue;
+
+argtea_impl! {
+    {
+        ("-o", output) => {
+            log.push(format!("-o {output:?}"));
+        }
+
+        #[key_value]
+        (define) => {
+            log.push(format!("{define:?}"));
+        }
+    }
+
+    impl TestKeyValue {
+        fn parse(args: &[&str]) -> Vec<String> {
+            let mut log = Vec::new();
+            parse!(testing::args(args).into_iter());
+            log
+        }
+    }
+}
+
+#[test]
+fn test_key_value() {
+    // Positionals are never split at `=` unless they are `#[key_value]`
+    assert_eq!(
+        summarize_events(&["file=name", "--output=a=b", "-o=c"]),
+        ["<file=name>", "--output", "--output=a=b", "-o", "-o=c"]
+    );
+
+    assert_eq!(
+        TestKeyValue::parse(&["VAR=a=b", "target", "=x", "y=", "-o", "k=v"]),
+        [
+            r#"("VAR", Some("a=b"))"#,
+            r#"("target", None)"#,
+            r#"("", Some("x"))"#,
+            r#"("y", Some(""))"#,
+            r#"-o Some("k=v")"#,
+        ]
+    );
+}
+
+#[derive(Debug, PartialEq)]
+struct TestQualifiers {
+    verbose: bool,
+    files: usize,
+}
+
+argtea_impl! {
+    {
+        ("-v") => {
+            args.verbose = true;
+        }
+
+        (_file) => {
+            args.files += 1;
+        }
+    }
+
+    impl TestQualifiers {
+        const fn default_args() -> Self {
+            Self {
+                verbose: false,
+                files: 0,
+            }
+        }
+
+        /// # Safety
+        ///
+        /// `argv` must point to `argc` valid nul-terminated strings.
+        unsafe extern "C" fn parse_raw(argc: usize, argv: *const *const std::ffi::c_char) -> usize {
+            let mut args = Self::default_args();
+            let argv = unsafe { std::slice::from_raw_parts(argv, argc) };
+
+            parse!(argv.iter().map(|&arg| {
+                unsafe { std::ffi::CStr::from_ptr(arg) }.to_string_lossy().into_owned()
+            }));
+
+            args.files + usize::from(args.verbose) * 100
+        }
+
+        fn parse(params: &[&str]) -> Self {
+            let mut args = Self::default_args();
+            parse!(testing::args(params).into_iter());
+            args
+        }
+    }
+}
+
+#[test]
+fn test_qualifiers() {
+    const DEFAULT: TestQualifiers = TestQualifiers::default_args();
+    assert_eq!(DEFAULT.files, 0);
+
+    assert_eq!(
+        TestQualifiers::parse(&["a", "-v"]),
+        TestQualifiers {
+            verbose: true,
+            files: 1
+        }
+    );
+
+    let argv = [c"-v".as_ptr(), c"a".as_ptr(), c"b".as_ptr()];
+    assert_eq!(
+        unsafe { TestQualifiers::parse_raw(argv.len(), argv.as_ptr()) },
+        102
+    );
+}
+
+struct TestGuards;
+
+argtea_impl! {
+    {
+        /// Enables debug output (only in development builds).
+        ("--debug") if dev_build => {
+            log.push("debug".to_owned());
+        }
+
+        #[eager]
+        (flag @ "--help" | "-h") if flag == "--help" || dev_build => {
+            log.push("help".to_owned());
+        }
+
+        (arg) => {
+            log.push(format!("<{arg}>"));
+        }
+    }
+
+    impl TestGuards {
+        const FLAGS: &'static [Flag] = docs!();
+
+        fn parse(args: &[&str], dev_build: bool) -> Vec<String> {
+            let mut log = Vec::new();
+            parse!(testing::args(args).into_iter());
+            log
+        }
+    }
+}
+
+#[test]
+fn test_guards() {
+    assert_eq!(TestGuards::parse(&["--debug", "a"], true), ["debug", "<a>"]);
+    assert_eq!(
+        TestGuards::parse(&["--debug", "a"], false),
+        ["<--debug>", "<a>"]
+    );
+
+    assert_eq!(TestGuards::parse(&["-h", "--help"], true), ["help", "help"]);
+    assert_eq!(
+        TestGuards::parse(&["-h", "--help"], false),
+        ["help", "<-h>"]
+    );
+
+    assert_eq!(TestGuards::FLAGS.len(), 3);
+    assert_eq!(TestGuards::FLAGS[0].flags, ["--debug"]);
+}
+
+struct TestExpressionBodies;
+
+argtea_impl! {
+    {
+        /// Enables verbose output.
+        ("--verbose" | "-v") => verbose = true,
+
+        /// Sets the output file.
+        ("--output" | "-o", path) => {
+            output = path;
+        },
+
+        #[fake]
+        ("--fake") => unreachable!(),
+
+        ("--quiet") if !verbose => log.push("quiet".to_owned()),
+
+        #[eager]
+        ("--version") => log.push("version".to_owned()),
+
+        (arg) => log.push(arg)
+    }
+
+    impl TestExpressionBodies {
+        const FLAGS: &'static [Flag] = docs!();
+        const HELP: &'static str = crate::simple_format!(docs!());
+
+        fn parse(args: &[&str]) -> (bool, Option<String>, Vec<String>) {
+            let mut verbose = false;
+            let mut output = None;
+            let mut log = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            (verbose, output, log)
+        }
+    }
+}
+
+#[test]
+fn test_expression_bodies() {
+    assert_eq!(
+        TestExpressionBodies::parse(&["--quiet", "-vo", "out", "--quiet", "a", "--version"]),
+        (
+            true,
+            Some("out".to_owned()),
+            vec![
+                "version".to_owned(),
+                "quiet".to_owned(),
+                "--quiet".to_owned(),
+                "a".to_owned()
+            ]
+        )
+    );
+
+    let flags: Vec<_> = TestExpressionBodies::FLAGS
+        .iter()
+        .map(|flag| flag.flags)
+        .collect();
+    assert_eq!(
+        flags,
+        [
+            &["--verbose", "-v"][..],
+            &["--output", "-o"],
+            &["--fake"],
+            &["--quiet"],
+            &["--version"],
+            &[]
+        ]
+    );
+    assert!(TestExpressionBodies::HELP.starts_with(
+        "  --verbose, -v \n    Enables verbose output.\n\n\n  --output, -o <path> \n    Sets the output file.\n\n\n"
+    ));
+}
+
+macro_rules! example_list {
+    ([$({
+        doc: [$($doc:expr),*],
+        flags: [$($flag:literal),*],
+        params: [$($param:ident),*],
+        examples: [$($example:literal),*]
+        $(, $($rest:tt)*)?
+    }),*]) => {
+        concat!($($($example, "\n",)*)*)
+    };
+}
+
+struct TestExamples;
+
+argtea_impl! {
+    {
+        /// Sets the output file path.
+        #[example("myprog -o out.txt input.c")]
+        #[example = "myprog --output=- input.c"]
+        ("--output" | "-o", path) => {
+            output = path;
+        }
+
+        /// Enables verbose output.
+        #[example("myprog -v input.c")]
+        #[eager]
+        ("--verbose" | "-v") => {}
+
+        /// An input file.
+        (file) => {
+            files.push(file);
+        }
+    }
+
+    impl TestExamples {
+        const FLAGS: &'static [Flag] = docs!();
+        const EXAMPLES: &'static str = example_list!(docs!());
+
+        fn parse(args: &[&str]) -> (Option<String>, Vec<String>) {
+            let mut output = None;
+            let mut files = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            (output, files)
+        }
+    }
+}
+
+#[test]
+fn test_examples() {
+    assert_eq!(
+        TestExamples::parse(&["-o", "out.txt", "input.c"]),
+        (Some("out.txt".to_owned()), vec!["input.c".to_owned()])
+    );
+
+    assert_eq!(
+        TestExamples::FLAGS[0].examples,
+        ["myprog -o out.txt input.c", "myprog --output=- input.c"]
+    );
+    assert!(TestExamples::FLAGS[2].examples.is_empty());
+    assert_eq!(
+        TestExamples::EXAMPLES,
+        "myprog -o out.txt input.c\nmyprog --output=- input.c\nmyprog -v input.c\n"
+    );
+
+    let mut buf = String::new();
+    wrapping_format(&mut buf, TestExamples::FLAGS);
+    assert_eq!(
+        buf,
+        "  -o, --output <path>
+    Sets the output file path.
+
+    Examples:
+      myprog -o out.txt input.c
+      myprog --output=- input.c
+
+  -v, --verbose
+    Enables verbose output.
+
+    Example:
+      myprog -v input.c
+
+Arguments:
+  <file>
+    An input file.
+
+"
+    );
+
+    assert_eq!(
+        render_flags(
+            TestExamples::FLAGS,
+            &mut ColumnRenderer::new(HelpOptions::default())
+        ),
+        "  -o, --output <path>  Sets the output file path.
+                       Examples:
+                         myprog -o out.txt input.c
+                         myprog --output=- input.c
+  -v, --verbose        Enables verbose output.
+                       Example:
+                         myprog -v input.c
+
+Arguments:
+  <file>               An input file.
+"
+    );
+}
+
+struct TestFlagBindings;
+
+argtea_impl! {
+    {
+        /// Sets the output width.
+        (flag @ "--width" | "-w", width: u32) => {
+            log.push(format!("{flag} {width}"));
+        }
+
+        /// Sets the color mode.
+        #[example("myprog --color=never")]
+        (flag @ "--color", mode in ["auto", "never"]) if !log.is_empty() => {
+            log.push(format!("{flag} {mode}"));
+        }
+
+        #[hidden]
+        (flag @ "--dump") => {
+            log.push(flag.to_owned());
+        }
+
+        (file) => {
+            log.push(file);
+        }
+    }
+
+    impl TestFlagBindings {
+        const FLAGS: &'static [Flag] = docs!();
+        const HELP: &'static str = crate::simple_format!(docs!());
+
+        fn parse(args: &[&str]) -> Result<Vec<String>, ParseError> {
+            let mut log = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            Ok(log)
+        }
+    }
+}
+
+#[test]
+fn test_flag_bindings() {
+    let flags = TestFlagBindings::FLAGS
+        .iter()
+        .map(|flag| (flag.flags, flag.params, flag.doc))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        flags,
+        [
+            (
+                &["--width", "-w"][..],
+                &["width"][..],
+                &[" Sets the output width."][..]
+            ),
+            (&["--color"], &["mode"], &[" Sets the color mode."]),
+            (&[], &["file"], &[]),
+        ]
+    );
+    assert_eq!(
+        TestFlagBindings::FLAGS[1].possible_values,
+        [["auto", "never"]]
+    );
+    assert_eq!(
+        TestFlagBindings::FLAGS[1].examples,
+        ["myprog --color=never"]
+    );
+
+    assert_eq!(
+        TestFlagBindings::HELP,
+        "  --width, -w <width> \n    Sets the output width.\n\n\n\
+         \x20 --color <mode> \n    Sets the color mode.\n\n\n\
+         \x20 <file> \n\n\n"
+    );
+
+    assert_eq!(
+        TestFlagBindings::parse(&["-w", "80", "a", "--color=never", "--dump"]).unwrap(),
+        ["-w 80", "a", "--color never", "--dump"]
+    );
+}
+
+struct TestHygiene;
+
+argtea_impl! {
+    {
+        /// Uses the same names as the variables inside of `parse!()`.
+        ("--set" | "-s", value) => {
+            let flag = value.unwrap_or_default();
+            flag_buf.push(format!("{flag} {stashed_value} {charview}"));
+        }
+
+        ("--rest") => {
+            flag_buf.extend(&mut args);
+        }
+
+        /// Stops parsing from inside of a loop if the value contains a `!`.
+        ("--stop-at", value) => {
+            for c in value.unwrap_or_default().chars() {
+                if c == '!' {
+                    break 'stop_parsing;
+                }
+            }
+        }
+
+        #[eager]
+        ("--eager") => {
+            pending += 1;
+        }
+
+        (flag) => {
+            flag_buf.push(flag);
+        }
+    }
+
+    impl TestHygiene {
+        fn parse(args: &[&str]) -> (Vec<String>, usize, usize) {
+            let mut flag_buf = Vec::new();
+            let stashed_value = "stashed";
+            let charview = "charview";
+            let flag = "flag";
+            let mut pending = 0;
+            let mut args = testing::args(args).into_iter();
+
+            let consumed;
+            parse!(args, consumed: consumed);
+            assert_eq!(flag, "flag");
+
+            (flag_buf, pending, consumed)
+        }
+
+        fn events(args: &[&str]) -> Vec<String> {
+            let mut sink = Vec::new();
+            let mut flag_buf = Vec::new();
+            let stashed_value = "";
+            let charview = "";
+            let mut pending = 0;
+
+            let mut args = testing::args(args).into_iter();
+
+            parse_events!(args, |event| sink.push(format!("{event:?}")));
+            let _ = (flag_buf, pending);
+
+            sink
+        }
+    }
+}
+
+#[test]
+fn test_hygiene() {
+    assert_eq!(
+        TestHygiene::parse(&["-sa", "--set=b", "c", "--eager", "--rest", "-x", "--y"]),
+        (
+            vec![
+                "a stashed charview".to_owned(),
+                "b stashed charview".to_owned(),
+                "c".to_owned(),
+                "-x".to_owned(),
+                "--y".to_owned()
+            ],
+            1,
+            7
+        )
+    );
+
+    assert_eq!(
+        TestHygiene::parse(&["a", "--stop-at", "b", "c", "--stop-at=!", "d"]),
+        (vec!["a".to_owned(), "c".to_owned()], 0, 5)
+    );
+
+    assert_eq!(TestHygiene::events(&["-s", "a"]).len(), 2);
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct TestReceivers {
+    output: Option<String>,
+    verbose: bool,
+}
+
+argtea_impl! {
+    {
+        ("--output" | "-o", path) => {
+            self.output = Some(path.ok_or("expected a path")?);
+        }
+
+        ("-v") => {
+            self.verbose = true;
+        }
+
+        (arg) => {
+            return Err(format!("unexpected argument `{arg}`"));
+        }
+    }
+
+    impl TestReceivers {
+        pub fn apply_args(&mut self, mut args: impl Iterator<Item = String>) -> Result<(), String> {
+            parse!(args);
+            Ok(())
+        }
+
+        fn with_args(mut self, args: &[&str]) -> Result<Self, String> {
+            parse!(testing::args(args).into_iter());
+            Ok(self)
+        }
+    }
+}
+
+#[test]
+fn test_receivers() {
+    let mut config = TestReceivers {
+        output: Some("config.out".to_owned()),
+        verbose: false,
+    };
+
+    config.apply_args(["-v".to_owned()].into_iter()).unwrap();
+    assert_eq!(config.output.as_deref(), Some("config.out"));
+    assert!(config.verbose);
+
+    config
+        .apply_args(["-o".to_owned(), "cli.out".to_owned()].into_iter())
+        .unwrap();
+    assert_eq!(config.output.as_deref(), Some("cli.out"));
+
+    assert_eq!(
+        TestReceivers::default().with_args(&["-vo", "a"]),
+        Ok(TestReceivers {
+            output: Some("a".to_owned()),
+            verbose: true,
+        })
+    );
+    assert_eq!(
+        TestReceivers::default().with_args(&["b"]),
+        Err("unexpected argument `b`".to_owned())
+    );
+}
+
+mod private_default {
+    use crate::argtea_impl;
+
+    pub struct TestVisibility {
+        pub files: Vec<String>,
+    }
+
+    argtea_impl! {
+        #![default_visibility(private)]
+        #[about = "Collects files."]
+        {
+            (file) => {
+                files.push(file);
+            }
+        }
+
+        impl TestVisibility {
+            pub fn parse(args: &[&str]) -> Self {
+                Self {
+                    files: Self::collect(args),
+                }
+            }
+
+            pub(super) fn count(args: &[&str]) -> usize {
+                Self::collect(args).len()
+            }
+
+            // Not nameable outside of this module
+            fn collect(args: &[&str]) -> Vec<String> {
+                let mut files = Vec::new();
+                parse!(crate::testing::args(args).into_iter());
+                files
+            }
+        }
+    }
+}
+
+#[test]
+fn test_default_visibility() {
+    use private_default::TestVisibility;
+
+    assert_eq!(TestVisibility::parse(&["a", "b"]).files, ["a", "b"]);
+    assert_eq!(TestVisibility::count(&["a", "b", "c"]), 3);
+}
+
+mod higher_ranked {
+    use crate::argtea_impl;
+
+    pub struct TestHigherRanked<F>(pub F);
+
+    argtea_impl! {
+        {
+            (file) => {
+                if (filter.0)(&file) {
+                    files.push(file);
+                }
+            }
+        }
+
+        impl<F: for<'a> Fn(&'a str) -> bool> TestHigherRanked<F> {
+            // `pub` by default
+            fn filter_files(filter: &Self, args: &[&str]) -> Vec<String> {
+                let mut files = Vec::new();
+                parse!(crate::testing::args(args).into_iter());
+                files
+            }
+        }
+
+        impl<F> TestHigherRanked<F>
+        where
+            for<'a> F: Fn(&'a str) -> bool,
+        {
+            fn count_files(&self, args: &[&str]) -> usize {
+                Self::filter_files(self, args).len()
+            }
+        }
+    }
+}
+
+#[test]
+fn test_higher_ranked_bounds() {
+    use higher_ranked::TestHigherRanked;
+
+    let filter = TestHigherRanked(|file: &str| file.ends_with(".rs"));
+    assert_eq!(
+        TestHigherRanked::filter_files(&filter, &["a.rs", "b.txt"]),
+        ["a.rs"]
+    );
+    assert_eq!(filter.count_files(&["a.rs", "b.rs", "c"]), 2);
+}
+
+trait Subcommand: Sized {
+    type Error;
+    const NAME: &'static str;
+    const FLAGS: &'static [Flag];
+
+    fn parse(args: &[&str]) -> Result<Self, Self::Error>;
+}
+
+struct TestPassThrough {
+    width: usize,
+}
+
+argtea_impl! {
+    {
+        /// Sets the width.
+        ("--width", width: usize) => {
+            this.width = width;
+        }
+
+        (arg) => {
+            return Err(format!("unexpected argument `{arg}`"));
+        }
+    }
+
+    impl Subcommand for TestPassThrough {
+        type Error = String;
+        const NAME: &'static str = concat!("pass", "through");
+        const FLAGS: &'static [Flag] = docs!();
+
+        fn parse(args: &[&str]) -> Result<Self, String> {
+            let mut this = Self { width: Self::DEFAULT_WIDTH };
+            parse!(testing::args(args).into_iter());
+            Ok(this)
+        }
+    }
+
+    impl TestPassThrough {
+        pub const DEFAULT_WIDTH: usize = 40 * 2;
+        const HELP: &'static str = crate::simple_format!(docs!());
+        const MAX_WIDTH: usize = Self::DEFAULT_WIDTH * 2;
+    }
+}
+
+#[test]
+fn test_pass_through() {
+    assert_eq!(TestPassThrough::parse(&[]).unwrap().width, 80);
+    assert_eq!(TestPassThrough::parse(&["--width=20"]).unwrap().width, 20);
+    assert_eq!(
+        TestPassThrough::parse(&["--width", "x"]).err().unwrap(),
+        "invalid value `x` for `--width`: expected usize"
+    );
+
+    assert_eq!(TestPassThrough::MAX_WIDTH, 160);
+    assert_eq!(TestPassThrough::NAME, "passthrough");
+    assert_eq!(TestPassThrough::FLAGS.len(), 2);
+    assert!(TestPassThrough::HELP.contains("Sets the width."));
+}
+
+struct TestStatements;
+
+argtea_impl! {
+    {
+        ("-v") => {
+            verbose = true;
+        }
+
+        (file) => {
+            files.push(file);
+        }
+    }
+
+    impl TestStatements {
+        fn parse(args: &[&str]) -> Vec<String> {
+            let mut verbose = false;
+            let mut files = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            let mut summary = Vec::new();
+            for file in &files {
+                summary.push(file.to_uppercase())
+            }
+
+            if files.is_empty() {
+                summary.push("no files".to_owned())
+            } else if verbose {
+                summary.push(format!("{} files", files.len()))
+            }
+
+            summary
+        }
+
+        fn parse_if(args: &[&str], enabled: bool) -> (bool, Vec<String>) {
+            let mut verbose = false;
+            let mut files = Vec::new();
+
+            if enabled {
+                parse!(testing::args(args).into_iter());
+            }
+            (verbose, files)
+        }
+
+        fn count(args: &[&str]) -> usize {
+            let mut verbose = false;
+            let mut files = Vec::new();
+
+            if args.is_empty() {
+                0
+            } else {
+                let consumed;
+                parse!(testing::args(args).into_iter(), consumed: consumed);
+
+                consumed + usize::from(verbose) * 100
+            }
+        }
+    }
+}
+
+#[test]
+fn test_statements() {
+    assert_eq!(TestStatements::parse(&[]), ["no files"]);
+    assert_eq!(
+        TestStatements::parse(&["a", "-v", "b"]),
+        ["A", "B", "2 files"]
+    );
+    assert_eq!(TestStatements::parse(&["a"]), ["A"]);
+
+    assert_eq!(
+        TestStatements::parse_if(&["a", "-v", "b"], true),
+        (true, vec!["a".to_owned(), "b".to_owned()])
+    );
+    assert_eq!(
+        TestStatements::parse_if(&["a", "-v", "b"], false),
+        (false, vec![])
+    );
+
+    assert_eq!(TestStatements::count(&[]), 0);
+    assert_eq!(TestStatements::count(&["-v", "a"]), 102);
+}
+
+struct TestDenyUnused;
+
+// Checks that argtea's own bindings don't trigger lints that are enabled on flags
+argtea_impl! {
+    {
+        #[deny(unused_variables, unused_mut)]
+        (flag @ "--output" | "-o", path) => {
+            log.push(format!("{flag} {path:?}"));
+        }
+
+        #[deny(unused_variables, unused_mut)]
+        ("--jobs", jobs: u32, color in ["auto", "never"]) => {
+            log.push(format!("{jobs} {color}"));
+        }
+
+        #[deny(unused_variables, unused_mut)]
+        #[optional_value]
+        ("--level", level) => {
+            log.push(format!("{level:?}"));
+        }
+
+        #[deny(unused_variables, unused_mut)]
+        #[eager]
+        ("--eager", value) => {
+            log.push(format!("eager {value:?}"));
+        }
+
+        #[deny(unused_variables, unused_mut)]
+        (file) => {
+            log.push(file);
+        }
+    }
+
+    impl TestDenyUnused {
+        fn parse(args: &[&str]) -> Result<Vec<String>, ParseError> {
+            let mut log = Vec::new();
+            parse!(testing::args(args).into_iter());
+            Ok(log)
+        }
+    }
+}
+
+#[test]
+fn test_deny_unused() {
+    assert_eq!(
+        TestDenyUnused::parse(&["-o", "a", "--jobs", "2", "never", "--level", "x", "--eager", "e"])
+            .unwrap(),
+        ["eager Some(\"e\")", "-o Some(\"a\")", "2 never", "Set", "x"]
+    );
+}
+
+struct TestStrict;
+
+// Checks that argtea's own bindings don't cause warnings in strict mode (`-D warnings`)
+argtea_impl! {
+    {
+        (flag @ "--output" | "-o", path) => {
+            log.push(format!("{flag} {path:?}"));
+        }
+
+        ("--jobs", jobs: u32) => {
+            log.push(format!("{jobs}"));
+        }
+
+        ("-v") => {
+            log.push("-v".to_owned());
+        }
+
+        #[eager]
+        ("--help") => {
+            log.push("help".to_owned());
+        }
+
+        (file) => {
+            log.push(file);
+        }
+    }
+
+    impl TestStrict {
+        fn parse(args: &[&str]) -> Result<Vec<String>, ParseError> {
+            let mut log = Vec::new();
+            parse!(testing::args(args).into_iter(), strict);
+            Ok(log)
+        }
+
+        fn parse_events(args: &[&str]) -> Result<(Vec<String>, Vec<Event>), ParseError> {
+            let mut log = Vec::new();
+            let mut events = Vec::new();
+            parse_events!(
+                testing::args(args).into_iter(),
+                |event| events.push(event),
+                index: _index,
+                strict
+            );
+            Ok((log, events))
+        }
+    }
+}
+
+#[test]
+fn test_strict() {
+    assert_eq!(
+        TestStrict::parse(&["-vo", "a", "--jobs=2", "--help", "b"]).unwrap(),
+        ["help", "-v", "-o Some(\"a\")", "2", "b"]
+    );
+    assert_eq!(TestStrict::parse_events(&["-v"]).unwrap().1.len(), 1);
+}
+
+struct TestFlagState;
+
+argtea_impl! {
+    {
+        #[optional_value]
+        ("--color" | "-c", when) => {
+            color = when;
+        }
+
+        #[optional_value]
+        ("--jobs" | "-j", jobs: u32) => {
+            jobs_ = jobs;
+        }
+
+        ("-v") => {
+            verbose = true;
+        }
+
+        (file) => {
+            files.push(file);
+        }
+    }
+
+    impl TestFlagState {
+        fn parse(args: &[&str]) -> Result<(FlagState, FlagState<u32>, bool, Vec<String>), ParseError> {
+            let mut color = FlagState::Absent;
+            let mut jobs_ = FlagState::Absent;
+            let mut verbose = false;
+            let mut files = Vec::new();
+
+            parse!(testing::args(args).into_iter());
+
+            Ok((color, jobs_, verbose, files))
+        }
+    }
+}
+
+#[test]
+fn test_optional_value() {
+    let parse = |args: &[&str]| TestFlagState::parse(args).unwrap();
+
+    assert_eq!(parse(&["a"]).0, FlagState::Absent);
+    assert_eq!(parse(&["--color", "never"]).0, FlagState::Set);
+    assert_eq!(parse(&["--color", "never"]).3, ["never"]);
+    assert_eq!(
+        parse(&["--color=never"]).0,
+        FlagState::SetWith("never".to_owned())
+    );
+    assert_eq!(
+        parse(&["-cnever"]).0,
+        FlagState::SetWith("never".to_owned())
+    );
+    assert_eq!(
+        parse(&["-c", "-v"]),
+        (FlagState::Set, FlagState::Absent, true, vec![])
+    );
+
+    assert_eq!(parse(&["-j"]).1, FlagState::Set);
+    assert_eq!(parse(&["--jobs=4"]).1, FlagState::SetWith(4));
+    assert_eq!(parse(&["-vj4"]).1, FlagState::SetWith(4));
+    assert!(matches!(
+        TestFlagState::parse(&["--jobs=x"]),
+        Err(ParseError::InvalidValue { .. })
+    ));
+
+    assert!(!FlagState::<u32>::Absent.is_present());
+    assert_eq!(parse(&["--jobs=4"]).1.value(), Some(&4));
+    assert_eq!(parse(&["--jobs"]).1.value(), None);
+}
+
+struct TestFlagWidth;
+
+argtea_impl! {
+    {
+        /// Sets the output file path.
+        ("--output" | "-o", path) => {}
+
+        /// Colors the output.
+        ("--color", when in ["auto", "always", "never"]) => {}
+
+        /// Sets the größe.
+        ("--größe" | "-g", größe) => {}
+
+        #[fake]
+        ("--fake" | "-F") => {}
+
+        #[hidden]
+        ("--a-very-long-hidden-flag", with, many, parameters) => {}
+
+        (input, output) => {}
+    }
+
+    impl TestFlagWidth {
+        const FLAGS: &'static [Flag] = docs!();
+        const HIDDEN: &'static [Flag] = docs!(hidden);
+        const MAX_FLAG_WIDTH: usize = flag_width!();
+        const MAX_HIDDEN_WIDTH: usize = flag_width!(hidden);
+    }
+}
+
+#[test]
+fn test_flag_width() {
+    let max_width = |flags: &[Flag]| {
+        flags
+            .iter()
+            .map(|flag| flag.to_string().chars().count())
+            .max()
+            .unwrap_or_default()
+    };
+
+    for flag in TestFlagWidth::FLAGS.iter().chain(TestFlagWidth::HIDDEN) {
+        assert_eq!(flag.width(), flag.to_string().chars().count(), "{flag}");
+    }
+
+    assert_eq!(TestFlagWidth::MAX_FLAG_WIDTH, 27);
+    assert_eq!(
+        TestFlagWidth::MAX_FLAG_WIDTH,
+        max_width(TestFlagWidth::FLAGS)
+    );
+    assert_eq!(
+        TestFlagWidth::MAX_HIDDEN_WIDTH,
+        max_width(TestFlagWidth::HIDDEN)
+    );
+}
+
+struct TestParserTrait {
+    verbose: bool,
+}
+
+struct TestParserAll<T>(core::marker::PhantomData<T>);
+
+argtea_impl! {
+    {
+        /// Prints more information.
+        ("--verbose" | "-v") => {
+            verbose = true;
+        }
+
+        /// Prints this help message.
+        #[hidden]
+        ("--help" | "-h") => {}
+
+        (_arg) => {}
+    }
+
+    impl crate::Parser for TestParserTrait {
+        const FLAGS: &'static [Flag] = docs!();
+    }
+
+    impl<T> crate::Parser for TestParserAll<T> {
+        const FLAGS: &'static [Flag] = docs!(all);
+    }
+
+    impl TestParserTrait {
+        fn parse(args: &[&str]) -> Self {
+            let mut verbose = false;
+
+            parse!(testing::args(args).into_iter());
+
+            Self { verbose }
+        }
+    }
+}
+
+#[test]
+fn test_parser_trait() {
+    fn help<T: crate::Parser>() -> String {
+        let mut buf = String::new();
+        wrapping_format(&mut buf, T::FLAGS);
+
+        buf
+    }
+
+    assert!(TestParserTrait::parse(&["-v"]).verbose);
+
+    let help_trait = help::<TestParserTrait>();
+    assert!(help_trait.contains("--verbose"), "{help_trait}");
+    assert!(!help_trait.contains("--help"), "{help_trait}");
+
+    let help_all = help::<TestParserAll<String>>();
+    assert!(help_all.contains("--verbose"), "{help_all}");
+    assert!(help_all.contains("--help"), "{help_all}");
+    assert_ne!(help_trait, help_all);
+}
+
+struct TestDocFlagName;
+
+argtea_impl! {
+    {
+        /// Sets the output file (eg `{flag} out.txt`).
+        ///
+        /// {flag} can't be used more than once; {flag}{flag} is {not} replaced inside of
+        /// {flag words}. {{flag}} is written as-is.
+        ("--output" | "-o", path) => {}
+
+        #[doc = "See also `{flag}`."]
+        #[doc = include_str!("tests/included_docs.md")]
+        ("-v" | "--verbose") => {}
+
+        /// Adds {flag} as an input file.
+        (file) => {}
+    }
+
+    impl TestDocFlagName {
+        const FLAGS: &'static [Flag] = docs!();
+
+        const HELP: &'static str = crate::simple_format!(docs!());
+
+        const TABLE: &'static str = crate::table_format!(docs!());
+    }
+}
+
+#[test]
+fn test_doc_flag_name() {
+    let flags = TestDocFlagName::FLAGS;
+
+    assert_eq!(
+        flags[0].doc,
+        [
+            " Sets the output file (eg `--output out.txt`).",
+            "",
+            " --output can't be used more than once; --output--output is {not} replaced inside of",
+            " {flag words}. {flag} is written as-is.",
+        ]
+    );
+    assert_eq!(flags[1].doc[0], "See also `-v`.");
+    crate::assert_help_snapshot!(flags[1].doc[1], "tests/included_docs.md");
+    assert_eq!(flags[2].doc, [" Adds {flag} as an input file."]);
+
+    let mut help = String::new();
+    wrapping_format(&mut help, flags);
+    assert!(
+        help.contains("Sets the output file (eg `--output out.txt`)."),
+        "{help}"
+    );
+    assert!(!help.contains("`{flag}`"), "{help}");
+
+    let help = TestDocFlagName::HELP;
+    assert!(
+        help.contains("Sets the output file (eg `--output out.txt`)."),
+        "{help}"
+    );
+    assert!(
+        help.contains("--output--output is {not} replaced"),
+        "{help}"
+    );
+    assert!(help.contains("{flag} is written as-is."), "{help}");
+    assert!(help.contains("See also `-v`."), "{help}");
+    assert!(help.contains("Adds {flag} as an input file."), "{help}");
+
+    let table = TestDocFlagName::TABLE;
+    assert!(
+        table.contains("Sets the output file (eg `--output out.txt`)."),
+        "{table}"
+    );
+    assert!(table.contains("See also `-v`."), "{table}");
+}
+
+crate::assert_formatter!(crate::simple_format);
+crate::assert_formatter!(crate::plain_format);
+crate::assert_formatter!(crate::table_format);
+
+#[test]
+fn test_snapshot_mismatch() {
+    let path = testing::snapshot_path(env!("CARGO_MANIFEST_DIR"), file!(), "tests/table.txt");
+    assert!(path.ends_with("src/tests/table.txt"), "{}", path.display());
+    assert!(path.exists(), "{}", path.display());
+
+    testing::check_snapshot("a\nb\n", "a\nb\n", path.clone());
+
+    let message = |actual: &'static str, expected: &'static str| {
+        let path = path.clone();
+        let panic = std::panic::catch_unwind(move || {
+            testing::check_snapshot(actual, expected, path);
+        })
+        .unwrap_err();
+
+        panic.downcast_ref::<String>().unwrap().clone()
+    };
+
+    let mismatch = message("a\nc\nd\n", "a\nb\nd\n");
+    assert!(
+        mismatch.contains("doesn't match (first difference on line 2)"),
+        "{mismatch}"
+    );
+    assert!(mismatch.contains("expected: \"b\""), "{mismatch}");
+    assert!(mismatch.contains("actual:   \"c\""), "{mismatch}");
+    assert!(!mismatch.contains("\"d\""), "{mismatch}");
+
+    let shorter = message("a\n", "a\nb");
+    assert!(shorter.contains("line 2"), "{shorter}");
+    assert!(shorter.contains("expected: \"b\""), "{shorter}");
+    assert!(shorter.contains("actual:   \"\""), "{shorter}");
+
+    let longer = message("a\nb\nc", "a\nb");
+    assert!(longer.contains("line 3"), "{longer}");
+    assert!(longer.contains("expected: <end of file>"), "{longer}");
+}
+
+#[test]
+fn test_compile_errors() {
+    trybuild::TestCases::new().compile_fail("src/tests/ui/*.rs");
+}
@@ -0,0 +1,16 @@
+struct Foo;
+
+argtea::argtea_impl! {
+    #[about = "Does nothing."]
+    {
+        (_file) => {}
+    }
+    impl Default for Foo {
+        fn default() -> Self {
+            parse!(std::env::args());
+            Foo
+        }
+    }
+}
+
+fn main() {}
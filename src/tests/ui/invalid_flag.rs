@@ -0,0 +1,15 @@
+struct Foo;
+
+argtea::argtea_impl! {
+    {
+        "-v" => {}
+        (_file) => {}
+    }
+    impl Foo {
+        fn parse() {
+            parse!(std::env::args());
+        }
+    }
+}
+
+fn main() {}
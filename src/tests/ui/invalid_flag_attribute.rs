@@ -0,0 +1,16 @@
+struct Foo;
+
+argtea::argtea_impl! {
+    {
+        #[hiden]
+        ("--verbose") => {}
+        (_file) => {}
+    }
+    impl Foo {
+        fn parse() {
+            parse!(std::env::args());
+        }
+    }
+}
+
+fn main() {}
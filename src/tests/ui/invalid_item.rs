@@ -0,0 +1,10 @@
+struct Foo;
+
+argtea::argtea_impl! {
+    {}
+    impl Foo {
+        struct Bar;
+    }
+}
+
+fn main() {}
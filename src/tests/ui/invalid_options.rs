@@ -0,0 +1,14 @@
+struct Foo;
+
+argtea::argtea_impl! {
+    {
+        (_file) => {}
+    }
+    impl Foo {
+        fn parse() {
+            parse!(std::env::args(), bogus: 1);
+        }
+    }
+}
+
+fn main() {}
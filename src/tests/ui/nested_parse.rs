@@ -0,0 +1,19 @@
+struct Foo;
+
+argtea::argtea_impl! {
+    {
+        ("--sub") => {
+            if true {
+                parse!(std::env::args());
+            }
+        }
+        (_file) => {}
+    }
+    impl Foo {
+        fn parse() {
+            parse!(std::env::args());
+        }
+    }
+}
+
+fn main() {}
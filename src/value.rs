@@ -0,0 +1,579 @@
+//! Helpers for the values of flags' parameters.
+//!
+//! The most common ones ([`expect_value`], [`expect_parsed`], and [`merge`]) are also exported
+//! from the crate root.
+
+use core::{
+    fmt::{self, Display},
+    ops::{Bound, RangeBounds},
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{error::suggest, ParseError};
+
+/// Merges a value from the command line with a value from another source (eg a config file). The
+/// command line value always wins.
+///
+/// ```rust
+/// assert_eq!(argtea::merge(Some("cli"), Some("config")), Some("cli"));
+/// assert_eq!(argtea::merge(None, Some("config")), Some("config"));
+/// assert_eq!(argtea::merge::<&str>(None, None), None);
+/// ```
+pub fn merge<T>(cli: Option<T>, config: Option<T>) -> Option<T> {
+    cli.or(config)
+}
+
+/// Returns the value of a flag's parameter, or a [`ParseError::MissingValue`] if it is missing.
+///
+/// `flag` is the alias that was used (eg from `(flag @ "--output" | "-o", path)`), so this
+/// replaces a `let ... else` in the flag's code:
+/// ```rust
+/// # use argtea::{expect_value, ParseError};
+/// assert_eq!(expect_value("-o", Some("out".to_owned())), Ok("out".to_owned()));
+/// assert_eq!(
+///     expect_value("-o", None).unwrap_err().to_string(),
+///     "expected <value> after `-o`"
+/// );
+/// ```
+pub fn expect_value(flag: &str, value: Option<String>) -> Result<String, ParseError> {
+    value.ok_or_else(|| ParseError::MissingValue {
+        flag: flag.to_owned(),
+        param: "value",
+    })
+}
+
+/// Like [`expect_value`], but the value is also converted using [`FromStr`]. A value that can't be
+/// converted results in a [`ParseError::InvalidValue`].
+/// ```rust
+/// # use argtea::expect_parsed;
+/// assert_eq!(expect_parsed::<u32>("-j", Some("4".to_owned())), Ok(4));
+/// assert_eq!(
+///     expect_parsed::<u32>("-j", Some("four".to_owned())).unwrap_err().to_string(),
+///     "invalid value `four` for `-j`: invalid digit found in string"
+/// );
+/// ```
+pub fn expect_parsed<T>(flag: &str, value: Option<String>) -> Result<T, ParseError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = expect_value(flag, value)?;
+
+    match value.parse() {
+        Ok(parsed) => Ok(parsed),
+        Err(err) => Err(invalid_value(flag, value, err)),
+    }
+}
+
+/// A [`ParseError::InvalidValue`] for `value`, with `reason` as its reason.
+fn invalid_value(flag: &str, value: String, reason: impl Display) -> ParseError {
+    ParseError::InvalidValue {
+        flag: flag.to_owned(),
+        value,
+        reason: reason.to_string(),
+    }
+}
+
+/// The values accepted by [`parse_bool`] (in any case), as `(true, false)` pairs.
+const BOOLS: [(&str, &str); 4] = [("yes", "no"), ("true", "false"), ("on", "off"), ("1", "0")];
+
+/// Parses a boolean value (eg `--color=yes` or `--follow-symlinks 0`). The following values are
+/// accepted in any case (eg `Yes` or `TRUE`):
+///
+/// | `true`  | `false` |
+/// |---------|---------|
+/// | `yes`   | `no`    |
+/// | `true`  | `false` |
+/// | `on`    | `off`   |
+/// | `1`     | `0`     |
+///
+/// Any other value results in a [`ParseError::InvalidValue`] that lists the accepted values.
+/// ```rust
+/// # use argtea::value::parse_bool;
+/// assert_eq!(parse_bool("--color", "On"), Ok(true));
+/// assert_eq!(parse_bool("--color", "0"), Ok(false));
+/// assert_eq!(
+///     parse_bool("--color", "maybe").unwrap_err().to_string(),
+///     "invalid value `maybe` for `--color`: expected one of \
+///      `yes`, `no`, `true`, `false`, `on`, `off`, `1`, `0`"
+/// );
+/// ```
+pub fn parse_bool(flag: &str, value: &str) -> Result<bool, ParseError> {
+    for (yes, no) in BOOLS {
+        if value.eq_ignore_ascii_case(yes) {
+            return Ok(true);
+        }
+
+        if value.eq_ignore_ascii_case(no) {
+            return Ok(false);
+        }
+    }
+
+    let accepted = BOOLS
+        .iter()
+        .flat_map(|(yes, no)| [yes, no])
+        .map(|value| format!("`{value}`"))
+        .collect::<Vec<_>>();
+
+    Err(invalid_value(
+        flag,
+        value.to_owned(),
+        format!("expected one of {}", accepted.join(", ")),
+    ))
+}
+
+/// [`parse_bool`] for the value of a flag's parameter, where a flag without a value (eg a bare
+/// `--color`) results in `default_when_bare`.
+///
+/// This is mostly useful with `#[optional_value]` (as `color.value().cloned()`), so that a bare
+/// flag doesn't take the next argument as its value.
+/// ```rust
+/// # use argtea::value::parse_bool_opt;
+/// assert_eq!(parse_bool_opt("--color", None, true), Ok(true));
+/// assert_eq!(parse_bool_opt("--color", Some("off".to_owned()), true), Ok(false));
+/// ```
+pub fn parse_bool_opt(
+    flag: &str,
+    value: Option<String>,
+    default_when_bare: bool,
+) -> Result<bool, ParseError> {
+    match value {
+        Some(value) => parse_bool(flag, &value),
+        None => Ok(default_when_bare),
+    }
+}
+
+/// Checks that a flag's value is in `range` (eg `1..=64` or `1..`), and returns it. A value
+/// outside of the range results in a [`ParseError::InvalidValue`] that describes the range.
+/// ```rust
+/// # use argtea::value::in_range;
+/// assert_eq!(in_range("--jobs", 64, 1..=64), Ok(64));
+/// assert_eq!(
+///     in_range("--jobs", 0, 1..=64).unwrap_err().to_string(),
+///     "invalid value `0` for `--jobs`: expected a value from 1 to 64"
+/// );
+/// assert_eq!(
+///     in_range("--ratio", 1.0, 0.0..1.0).unwrap_err().to_string(),
+///     "invalid value `1` for `--ratio`: expected a value that is at least 0 and less than 1"
+/// );
+/// ```
+pub fn in_range<T, R>(flag: &str, value: T, range: R) -> Result<T, ParseError>
+where
+    T: PartialOrd + Display,
+    R: RangeBounds<T>,
+{
+    if range.contains(&value) {
+        return Ok(value);
+    }
+
+    let start = match range.start_bound() {
+        Bound::Included(start) => Some(format!("at least {start}")),
+        Bound::Excluded(start) => Some(format!("greater than {start}")),
+        Bound::Unbounded => None,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => Some(format!("at most {end}")),
+        Bound::Excluded(end) => Some(format!("less than {end}")),
+        Bound::Unbounded => None,
+    };
+
+    let reason = match (range.start_bound(), range.end_bound()) {
+        (Bound::Included(start), Bound::Included(end)) => {
+            format!("expected a value from {start} to {end}")
+        }
+        _ => {
+            let bounds = start.into_iter().chain(end).collect::<Vec<_>>();
+            format!("expected a value that is {}", bounds.join(" and "))
+        }
+    };
+
+    Err(invalid_value(flag, value.to_string(), reason))
+}
+
+/// Checks that a flag's value is one of `choices` (like a `color in ["auto", "never"]`
+/// parameter), and returns the matching choice. When `ignore_case` is true, the value is compared
+/// ignoring ASCII case (like in [`parse_bool`]), but the choice is still returned with its
+/// original spelling.
+///
+/// Any other value results in a [`ParseError::InvalidValue`] that lists the choices, and that
+/// suggests the most similar choice if the value looks like a typo.
+/// ```rust
+/// # use argtea::value::one_of;
+/// const PROFILES: &[&str] = &["fast", "small", "debug"];
+///
+/// assert_eq!(one_of("--profile", "small", PROFILES, false), Ok("small"));
+/// assert_eq!(one_of("--profile", "DEBUG", PROFILES, true), Ok("debug"));
+/// assert_eq!(
+///     one_of("--profile", "smal", PROFILES, false).unwrap_err().to_string(),
+///     "invalid value `smal` for `--profile`: expected one of `fast`, `small`, `debug` \
+///      (did you mean `small`?)"
+/// );
+/// ```
+pub fn one_of<'a>(
+    flag: &str,
+    value: &str,
+    choices: &[&'a str],
+    ignore_case: bool,
+) -> Result<&'a str, ParseError> {
+    let found = choices.iter().find(|choice| {
+        if ignore_case {
+            choice.eq_ignore_ascii_case(value)
+        } else {
+            **choice == value
+        }
+    });
+
+    if let Some(choice) = found {
+        return Ok(choice);
+    }
+
+    let listed = choices
+        .iter()
+        .map(|choice| format!("`{choice}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let suggestion = if ignore_case {
+        let lowercase = choices
+            .iter()
+            .map(|choice| choice.to_ascii_lowercase())
+            .collect::<Vec<_>>();
+        let suggestion = suggest(
+            &value.to_ascii_lowercase(),
+            lowercase.iter().map(String::as_str),
+        );
+
+        suggestion
+            .and_then(|suggestion| lowercase.iter().position(|choice| choice == suggestion))
+            .map(|index| choices[index])
+    } else {
+        suggest(value, choices.iter().copied())
+    };
+
+    let reason = match suggestion {
+        Some(suggestion) => format!("expected one of {listed} (did you mean `{suggestion}`?)"),
+        None => format!("expected one of {listed}"),
+    };
+
+    Err(invalid_value(flag, value.to_owned(), reason))
+}
+
+/// Splits the value of a `--define NAME=VALUE` style flag at its first `=`, so `A=b=c` becomes
+/// `("A", "b=c")`. The value can be empty (`A=`), but the name can't, and a value without an `=`
+/// results in a [`ParseError::InvalidValue`].
+///
+/// `--define=A=b` passes `A=b` as the value, since only the first `=` of a long flag is removed
+/// (and likewise for `-DA=b` and `-D=A=b`).
+/// ```rust
+/// # use argtea::value::split_kv;
+/// assert_eq!(split_kv("-D", "DEBUG=1"), Ok(("DEBUG".to_owned(), "1".to_owned())));
+/// assert_eq!(split_kv("-D", "EMPTY="), Ok(("EMPTY".to_owned(), String::new())));
+/// assert_eq!(
+///     split_kv("-D", "DEBUG").unwrap_err().to_string(),
+///     "invalid value `DEBUG` for `-D`: expected `NAME=VALUE`"
+/// );
+/// assert_eq!(
+///     split_kv("-D", "=1").unwrap_err().to_string(),
+///     "invalid value `=1` for `-D`: expected a name before `=`"
+/// );
+/// ```
+pub fn split_kv(flag: &str, input: &str) -> Result<(String, String), ParseError> {
+    match split_kv_opt(flag, input)? {
+        (name, Some(value)) => Ok((name, value)),
+        (_, None) => Err(invalid_value(
+            flag,
+            input.to_owned(),
+            "expected `NAME=VALUE`",
+        )),
+    }
+}
+
+/// Like [`split_kv`], but a bare `NAME` is also accepted, and results in `(NAME, None)` (eg for
+/// `-DDEBUG` as a shorthand for `-DDEBUG=1`).
+/// ```rust
+/// # use argtea::value::split_kv_opt;
+/// assert_eq!(split_kv_opt("-D", "DEBUG"), Ok(("DEBUG".to_owned(), None)));
+/// assert_eq!(
+///     split_kv_opt("-D", "LEVEL=2"),
+///     Ok(("LEVEL".to_owned(), Some("2".to_owned())))
+/// );
+/// ```
+pub fn split_kv_opt(flag: &str, input: &str) -> Result<(String, Option<String>), ParseError> {
+    let (name, value) = match input.split_once('=') {
+        Some((name, value)) => (name, Some(value.to_owned())),
+        None => (input, None),
+    };
+
+    if name.is_empty() {
+        let reason = match value {
+            Some(_) => "expected a name before `=`",
+            None => "expected a name",
+        };
+
+        return Err(invalid_value(flag, input.to_owned(), reason));
+    }
+
+    Ok((name.to_owned(), value))
+}
+
+/// An error returned by [`parse_size`] and [`parse_duration`].
+///
+/// [`expect_size`] and [`expect_duration`] turn it into a [`ParseError::InvalidValue`] (with the
+/// error's message as the reason).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValueError {
+    /// The value is empty.
+    Empty,
+    /// The value (or one of the parts of a duration) doesn't start with a number.
+    InvalidNumber,
+    /// A duration's number isn't followed by a unit (eg `30` instead of `30s`).
+    MissingUnit,
+    /// The unit after a number isn't one of the accepted units.
+    UnknownUnit(String),
+    /// The value is too large for the result's type.
+    Overflow,
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("expected a value"),
+            Self::InvalidNumber => f.write_str("expected a number"),
+            Self::MissingUnit => f.write_str("expected a unit after the number"),
+            Self::UnknownUnit(unit) => write!(f, "unknown unit `{unit}`"),
+            Self::Overflow => f.write_str("value is too large"),
+        }
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+/// The units accepted by [`parse_size`] (in any case, with an optional `B`), and their sizes.
+const SIZE_UNITS: [(&str, u128); 11] = [
+    ("", 1),
+    ("k", 1_000),
+    ("M", 1_000_000),
+    ("G", 1_000_000_000),
+    ("T", 1_000_000_000_000),
+    ("P", 1_000_000_000_000_000),
+    ("Ki", 1 << 10),
+    ("Mi", 1 << 20),
+    ("Gi", 1 << 30),
+    ("Ti", 1 << 40),
+    ("Pi", 1 << 50),
+];
+
+/// The units accepted by [`parse_duration`], and their lengths in nanoseconds.
+const DURATION_UNITS: [(&str, u128); 5] = [
+    ("ms", 1_000_000),
+    ("s", 1_000_000_000),
+    ("m", 60 * 1_000_000_000),
+    ("h", 60 * 60 * 1_000_000_000),
+    ("d", 24 * 60 * 60 * 1_000_000_000),
+];
+
+/// Parses a human-readable size in bytes (eg `--max-size 10M` or `--cache 1.5GiB`).
+///
+/// The size is a number (optionally with a fractional part) followed by an optional unit. The
+/// units are case-insensitive, and the trailing `B` is optional (eg `10M`, `10MB`, and `10mb` are
+/// all 10,000,000 bytes). Since sizes are always in bytes, `b` and `B` mean the same thing (`Mb`
+/// is a megabyte, not a megabit):
+///
+/// | Decimal        | Binary             |
+/// |----------------|--------------------|
+/// | none, `B`      |                    |
+/// | `kB` (1000)    | `KiB` (1024)       |
+/// | `MB` (1000²)   | `MiB` (1024²)      |
+/// | `GB` (1000³)   | `GiB` (1024³)      |
+/// | `TB` (1000⁴)   | `TiB` (1024⁴)      |
+/// | `PB` (1000⁵)   | `PiB` (1024⁵)      |
+///
+/// Fractions of a byte are rounded down, and sizes that don't fit in a `u64` result in a
+/// [`ValueError::Overflow`].
+/// ```rust
+/// # use argtea::value::{parse_size, ValueError};
+/// assert_eq!(parse_size("512"), Ok(512));
+/// assert_eq!(parse_size("10M"), Ok(10_000_000));
+/// assert_eq!(parse_size("1.5GiB"), Ok(1_610_612_736));
+/// assert_eq!(parse_size("1Mb"), parse_size("1MB"));
+/// assert_eq!(parse_size("10XB"), Err(ValueError::UnknownUnit("XB".to_owned())));
+/// assert_eq!(parse_size("20EiB"), Err(ValueError::UnknownUnit("EiB".to_owned())));
+/// ```
+pub fn parse_size(value: &str) -> Result<u64, ValueError> {
+    if value.is_empty() {
+        return Err(ValueError::Empty);
+    }
+
+    let (number, unit) = split_number(value)?;
+    let prefix = unit.strip_suffix(['B', 'b']).unwrap_or(unit);
+
+    let (_, multiplier) = SIZE_UNITS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(prefix))
+        .ok_or_else(|| ValueError::UnknownUnit(unit.to_owned()))?;
+
+    number
+        .scale(*multiplier)
+        .and_then(|bytes| u64::try_from(bytes).ok())
+        .ok_or(ValueError::Overflow)
+}
+
+/// Parses a human-readable duration (eg `--timeout 30s` or `--timeout 2m30s`).
+///
+/// The duration is one or more numbers (optionally with a fractional part), each followed by one
+/// of the following units. The parts are added together, so `1h30m` is an hour and a half.
+///
+/// | Unit | Length       |
+/// |------|--------------|
+/// | `ms` | milliseconds |
+/// | `s`  | seconds      |
+/// | `m`  | minutes      |
+/// | `h`  | hours        |
+/// | `d`  | days         |
+///
+/// Unlike the units of [`parse_size`], these are case-sensitive (eg `30S` isn't accepted).
+///
+/// Fractions of a nanosecond are rounded down, and durations that don't fit in a [`Duration`]
+/// result in a [`ValueError::Overflow`].
+/// ```rust
+/// # use argtea::value::{parse_duration, ValueError};
+/// # use std::time::Duration;
+/// assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+/// assert_eq!(parse_duration("2m30s"), Ok(Duration::from_secs(150)));
+/// assert_eq!(parse_duration("1.5h"), Ok(Duration::from_secs(5400)));
+/// assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+/// assert_eq!(parse_duration("30"), Err(ValueError::MissingUnit));
+/// assert_eq!(parse_duration("30S"), Err(ValueError::UnknownUnit("S".to_owned())));
+/// ```
+pub fn parse_duration(value: &str) -> Result<Duration, ValueError> {
+    if value.is_empty() {
+        return Err(ValueError::Empty);
+    }
+
+    let mut rest = value;
+    let mut nanos: u128 = 0;
+
+    while !rest.is_empty() {
+        let (number, after_number) = split_number(rest)?;
+        let unit_len = after_number
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(after_number.len());
+        let unit = &after_number[..unit_len];
+        rest = &after_number[unit_len..];
+
+        if unit.is_empty() {
+            return Err(ValueError::MissingUnit);
+        }
+
+        let (_, multiplier) = DURATION_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .ok_or_else(|| ValueError::UnknownUnit(unit.to_owned()))?;
+
+        nanos = number
+            .scale(*multiplier)
+            .and_then(|part| nanos.checked_add(part))
+            .ok_or(ValueError::Overflow)?;
+    }
+
+    let secs = u64::try_from(nanos / 1_000_000_000).map_err(|_| ValueError::Overflow)?;
+    Ok(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+}
+
+/// [`parse_size`] for the value of a flag's parameter, like [`expect_parsed`].
+/// ```rust
+/// # use argtea::value::expect_size;
+/// assert_eq!(expect_size("--max-size", Some("4KiB".to_owned())), Ok(4096));
+/// assert_eq!(
+///     expect_size("--max-size", Some("4 KiB".to_owned())).unwrap_err().to_string(),
+///     "invalid value `4 KiB` for `--max-size`: unknown unit ` KiB`"
+/// );
+/// ```
+pub fn expect_size(flag: &str, value: Option<String>) -> Result<u64, ParseError> {
+    let value = expect_value(flag, value)?;
+
+    match parse_size(&value) {
+        Ok(size) => Ok(size),
+        Err(err) => Err(invalid_value(flag, value, err)),
+    }
+}
+
+/// [`parse_duration`] for the value of a flag's parameter, like [`expect_parsed`].
+/// ```rust
+/// # use argtea::value::expect_duration;
+/// # use std::time::Duration;
+/// assert_eq!(
+///     expect_duration("--timeout", Some("1h30m".to_owned())),
+///     Ok(Duration::from_secs(5400))
+/// );
+/// assert_eq!(
+///     expect_duration("--timeout", Some("30".to_owned())).unwrap_err().to_string(),
+///     "invalid value `30` for `--timeout`: expected a unit after the number"
+/// );
+/// ```
+pub fn expect_duration(flag: &str, value: Option<String>) -> Result<Duration, ParseError> {
+    let value = expect_value(flag, value)?;
+
+    match parse_duration(&value) {
+        Ok(duration) => Ok(duration),
+        Err(err) => Err(invalid_value(flag, value, err)),
+    }
+}
+
+/// A number with an optional fractional part, as parsed by [`split_number`].
+struct Number {
+    whole: u128,
+    fraction: u128,
+    /// The power of 10 that `fraction` is divided by.
+    denominator: u128,
+}
+
+impl Number {
+    /// Multiplies the number by `multiplier`, rounding down. Returns `None` on overflow.
+    fn scale(&self, multiplier: u128) -> Option<u128> {
+        let whole = self.whole.checked_mul(multiplier)?;
+        let fraction = self.fraction.checked_mul(multiplier)? / self.denominator;
+
+        whole.checked_add(fraction)
+    }
+}
+
+/// The most digits of a fraction that are taken into account (so that its denominator fits
+/// comfortably in a `u128` after being multiplied by a unit); later digits are ignored.
+const MAX_FRACTION_DIGITS: usize = 18;
+
+/// Splits the number at the start of `value` (eg `1.5` in `1.5GiB`) from the rest of it.
+fn split_number(value: &str) -> Result<(Number, &str), ValueError> {
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+
+    let whole_len = digits(value);
+    let (whole, rest) = value.split_at(whole_len);
+    if whole.is_empty() {
+        return Err(ValueError::InvalidNumber);
+    }
+
+    let whole = whole.parse().map_err(|_| ValueError::Overflow)?;
+    let mut number = Number {
+        whole,
+        fraction: 0,
+        denominator: 1,
+    };
+
+    let Some(rest) = rest.strip_prefix('.') else {
+        return Ok((number, rest));
+    };
+
+    let (fraction, rest) = rest.split_at(digits(rest));
+    if fraction.is_empty() {
+        return Err(ValueError::InvalidNumber);
+    }
+
+    for digit in fraction.bytes().take(MAX_FRACTION_DIGITS) {
+        number.fraction = number.fraction * 10 + u128::from(digit - b'0');
+        number.denominator *= 10;
+    }
+
+    Ok((number, rest))
+}